@@ -0,0 +1,96 @@
+//! Measures the overhead the client adds on top of the network: building URLs, parsing responses, cache lookups,
+//! retry bookkeeping, and full requests to the in-process mock server.
+//!
+//! Run with `cargo bench --features mock-server`.
+
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use iseven_api::{
+    mock::MockServer, sansio, IsEvenApiClient, IsEvenApiError, IsEvenApiResponse, Plan,
+    ProtocolError, ResponseCache, RetryPolicy, StatusCode,
+};
+
+/// How long each benchmark is run for, after warming up for a tenth of it.
+const DURATION: Duration = Duration::from_secs(2);
+
+/// Runs `f` repeatedly for [`DURATION`] and prints the mean time per iteration.
+fn bench<R>(name: &str, mut f: impl FnMut() -> R) {
+    let warm_up = Instant::now();
+    while warm_up.elapsed() < DURATION / 10 {
+        black_box(f());
+    }
+    let start = Instant::now();
+    let mut iterations = 0u64;
+    while start.elapsed() < DURATION {
+        black_box(f());
+        iterations += 1;
+    }
+    let mean = start.elapsed() / iterations as u32;
+    println!("{name:<30} {mean:>12.2?}/iter ({iterations} iterations)");
+}
+
+fn main() {
+    // `cargo test --benches` runs benches once with `--bench` absent, only to check they work
+    let quick = !std::env::args().any(|arg| arg == "--bench");
+
+    bench("request_url", || {
+        sansio::request_url(black_box("https://api.isevenapi.xyz/api/iseven/"), 42)
+    });
+
+    let body = br#"{"ad": "Buy isEvenCoin, the hottest new cryptocurrency!", "iseven": true}"#;
+    bench("parse_response", || {
+        sansio::parse_response(StatusCode::OK, black_box(body))
+    });
+    let error =
+        br#"{"error": "Number out of range. Upgrade to isEven API Premium or Enterprise."}"#;
+    bench("parse_response (error)", || {
+        sansio::parse_response(StatusCode::UNAUTHORIZED, black_box(error))
+    });
+
+    let cache = ResponseCache::new(1000);
+    for n in 0..1000 {
+        let response: IsEvenApiResponse = serde_json::from_slice(body).unwrap();
+        cache.insert(n.to_string(), response);
+    }
+    bench("cache hit", || cache.get(black_box("500")));
+    bench("cache miss", || cache.get(black_box("5000")));
+
+    let policy = RetryPolicy::default();
+    let transient = IsEvenApiError::from(ProtocolError::UnexpectedStatus(
+        serde_json::from_slice(br#"{"error": "Service Unavailable"}"#).unwrap(),
+        StatusCode::SERVICE_UNAVAILABLE,
+    ));
+    bench("retry bookkeeping", || {
+        (0..=policy.max_retries())
+            .take_while(|&retry| policy.should_retry(retry, black_box(&transient)))
+            .map(|retry| policy.delay_for(retry))
+            .sum::<Duration>()
+    });
+
+    if quick {
+        return;
+    }
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let server = runtime
+        .block_on(MockServer::start("127.0.0.1:0", Plan::Free))
+        .unwrap();
+    let client = IsEvenApiClient::builder()
+        .base_url(server.url())
+        .build()
+        .unwrap();
+    bench("get (mock server)", || {
+        runtime.block_on(client.get(black_box(42))).unwrap()
+    });
+    let cached = IsEvenApiClient::builder()
+        .base_url(server.url())
+        .cache(ResponseCache::new(10))
+        .build()
+        .unwrap();
+    bench("get (cached)", || {
+        runtime.block_on(cached.get(black_box(42))).unwrap()
+    });
+}
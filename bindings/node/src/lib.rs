@@ -0,0 +1,55 @@
+//! Node.js bindings for [`iseven_api`], built with [napi-rs](https://napi.rs/).
+//!
+//! ```js
+//! const { isEven } = require('@megascrapper/iseven-api');
+//!
+//! const { iseven, ad } = await isEven(42);
+//! ```
+//!
+//! Every call goes through one shared [`IsEvenApiClient`], so connections are reused between calls. Errors are
+//! rejected with the message of the [`IsEvenApiError`], and a `code` of `InvalidArg` for numbers the API refuses.
+
+use std::sync::OnceLock;
+
+use iseven_api::{ApiError, IsEvenApiClient, IsEvenApiError};
+use napi::{bindgen_prelude::Either, Error, Status};
+use napi_derive::napi;
+
+/// Answer from isEven API.
+#[napi(object)]
+pub struct IsEvenResult {
+    /// Whether the number is even
+    pub iseven: bool,
+    /// Advertisement sent along with the answer
+    pub ad: String,
+}
+
+fn client() -> &'static IsEvenApiClient {
+    static CLIENT: OnceLock<IsEvenApiClient> = OnceLock::new();
+    CLIENT.get_or_init(IsEvenApiClient::new)
+}
+
+fn to_js_error(error: IsEvenApiError) -> Error {
+    let status = match error {
+        IsEvenApiError::Api(ApiError::InvalidNumber(_) | ApiError::NumberOutOfRange(_))
+        | IsEvenApiError::InvalidInput { .. } => {
+            Status::InvalidArg
+        }
+        _ => Status::GenericFailure,
+    };
+    Error::new(status, error.to_string())
+}
+
+/// Checks whether `number`, given as an integer or a decimal string, is even.
+#[napi]
+pub async fn is_even(number: Either<i64, String>) -> napi::Result<IsEvenResult> {
+    let number = match number {
+        Either::A(n) => n.to_string(),
+        Either::B(s) => s,
+    };
+    let response = client().get_str(&number).await.map_err(to_js_error)?;
+    Ok(IsEvenResult {
+        iseven: response.iseven(),
+        ad: response.ad().to_string(),
+    })
+}
@@ -0,0 +1,11 @@
+// Tests of the Node.js bindings which need no network access, run with `npm test` after `npm run build`.
+
+const test = require('node:test');
+const assert = require('node:assert');
+
+const { isEven } = require('..');
+
+test('rejects input which cannot be a number without making a request', async () => {
+  await assert.rejects(isEven('abc'), { code: 'InvalidArg' });
+  await assert.rejects(isEven('4.2'), { code: 'InvalidArg' });
+});
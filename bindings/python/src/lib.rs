@@ -0,0 +1,211 @@
+//! Python bindings for [`iseven_api`], built with [maturin](https://www.maturin.rs/).
+//!
+//! ```python
+//! from iseven_api import IsEvenClient
+//!
+//! client = IsEvenClient(retries=3, cache_size=1000)
+//! print(client.is_even(42).iseven)
+//! print((await client.is_even_async(41)).isodd)
+//! ```
+//!
+//! Both methods go through the same [`IsEvenApiClient`], so they share its connection pool, cache and retries.
+//! Blocking calls release the GIL while waiting for the API.
+
+use std::time::Duration;
+
+use iseven_api::{
+    ApiError, IsEvenApiClient, IsEvenApiClientBuilder, IsEvenApiError, IsEvenApiResponse, ResponseCache,
+    RetryPolicy, Url,
+};
+use pyo3::{
+    create_exception,
+    exceptions::{PyException, PyValueError},
+    prelude::*,
+};
+
+create_exception!(
+    iseven_api,
+    IsEvenError,
+    PyException,
+    "Base class of errors from isEven API."
+);
+create_exception!(
+    iseven_api,
+    InvalidNumberError,
+    IsEvenError,
+    "The API rejected the number as invalid."
+);
+create_exception!(
+    iseven_api,
+    InvalidInputError,
+    IsEvenError,
+    "The number cannot be valid, and was rejected without making a request."
+);
+create_exception!(
+    iseven_api,
+    NumberOutOfRangeError,
+    IsEvenError,
+    "The number is outside the range allowed by the plan."
+);
+create_exception!(
+    iseven_api,
+    NetworkError,
+    IsEvenError,
+    "The API could not be reached, or its response could not be read."
+);
+
+fn to_py_err(error: IsEvenApiError) -> PyErr {
+    let message = error.to_string();
+    match error {
+        IsEvenApiError::Api(ApiError::InvalidNumber(_)) => InvalidNumberError::new_err(message),
+        IsEvenApiError::InvalidInput { .. } => InvalidInputError::new_err(message),
+        IsEvenApiError::Api(ApiError::NumberOutOfRange(_)) => {
+            NumberOutOfRangeError::new_err(message)
+        }
+        IsEvenApiError::Transport(_) => NetworkError::new_err(message),
+        _ => IsEvenError::new_err(message),
+    }
+}
+
+fn seconds(name: &str, secs: f64) -> PyResult<Duration> {
+    Duration::try_from_secs_f64(secs)
+        .map_err(|e| PyValueError::new_err(format!("invalid {}: {}", name, e)))
+}
+
+/// A number to check, given as an `int` or a `str`.
+#[derive(FromPyObject)]
+enum Number {
+    Int(i128),
+    Str(String),
+}
+
+impl Number {
+    fn into_string(self) -> String {
+        match self {
+            Number::Int(n) => n.to_string(),
+            Number::Str(s) => s,
+        }
+    }
+}
+
+/// Answer from isEven API.
+#[pyclass(frozen, module = "iseven_api")]
+#[derive(Debug, Clone)]
+struct IsEvenResponse {
+    /// Whether the number is even.
+    #[pyo3(get)]
+    iseven: bool,
+    /// Advertisement sent along with the answer.
+    #[pyo3(get)]
+    ad: String,
+}
+
+impl From<IsEvenApiResponse> for IsEvenResponse {
+    fn from(response: IsEvenApiResponse) -> Self {
+        Self {
+            iseven: response.iseven(),
+            ad: response.ad().to_string(),
+        }
+    }
+}
+
+#[pymethods]
+impl IsEvenResponse {
+    /// Whether the number is odd.
+    #[getter]
+    fn isodd(&self) -> bool {
+        !self.iseven
+    }
+
+    fn __repr__(&self) -> String {
+        format!("IsEvenResponse(iseven={}, ad={:?})", self.iseven, self.ad)
+    }
+}
+
+/// Client for isEven API.
+#[pyclass(frozen, module = "iseven_api")]
+struct IsEvenClient {
+    client: IsEvenApiClient,
+}
+
+#[pymethods]
+impl IsEvenClient {
+    /// Creates a client. Timeouts and TTLs are in seconds; responses are only cached if `cache_size` is given.
+    #[new]
+    #[pyo3(signature = (*, base_url=None, api_key=None, timeout=None, retries=0, cache_size=None, cache_ttl=None))]
+    fn new(
+        base_url: Option<&str>,
+        api_key: Option<String>,
+        timeout: Option<f64>,
+        retries: u32,
+        cache_size: Option<usize>,
+        cache_ttl: Option<f64>,
+    ) -> PyResult<Self> {
+        let mut builder = IsEvenApiClientBuilder::new().retry_policy(RetryPolicy::new(retries));
+        if let Some(base_url) = base_url {
+            let url = Url::parse(base_url)
+                .map_err(|e| PyValueError::new_err(format!("invalid base_url: {}", e)))?;
+            builder = builder.base_url(url);
+        }
+        if let Some(api_key) = api_key {
+            builder = builder.api_key(api_key);
+        }
+        if let Some(timeout) = timeout {
+            builder = builder.timeout(seconds("timeout", timeout)?);
+        }
+        if let Some(capacity) = cache_size {
+            let mut cache = ResponseCache::new(capacity);
+            if let Some(ttl) = cache_ttl {
+                cache = cache.with_ttl(seconds("cache_ttl", ttl)?);
+            }
+            builder = builder.cache(cache);
+        }
+        Ok(Self {
+            client: builder.build().map_err(to_py_err)?,
+        })
+    }
+
+    /// Checks whether `number` is even, blocking until the API answers.
+    fn is_even(&self, py: Python<'_>, number: Number) -> PyResult<IsEvenResponse> {
+        let number = number.into_string();
+        let client = self.client.clone();
+        py.allow_threads(move || {
+            pyo3_async_runtimes::tokio::get_runtime().block_on(client.get_str(&number))
+        })
+        .map(IsEvenResponse::from)
+        .map_err(to_py_err)
+    }
+
+    /// Checks whether `number` is even, returning an awaitable.
+    fn is_even_async<'py>(&self, py: Python<'py>, number: Number) -> PyResult<Bound<'py, PyAny>> {
+        let number = number.into_string();
+        let client = self.client.clone();
+        pyo3_async_runtimes::tokio::future_into_py(py, async move {
+            client
+                .get_str(&number)
+                .await
+                .map(IsEvenResponse::from)
+                .map_err(to_py_err)
+        })
+    }
+}
+
+#[pymodule]
+#[pyo3(name = "iseven_api")]
+fn iseven_api_py(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    let py = m.py();
+    m.add_class::<IsEvenClient>()?;
+    m.add_class::<IsEvenResponse>()?;
+    m.add("IsEvenError", py.get_type_bound::<IsEvenError>())?;
+    m.add(
+        "InvalidNumberError",
+        py.get_type_bound::<InvalidNumberError>(),
+    )?;
+    m.add("InvalidInputError", py.get_type_bound::<InvalidInputError>())?;
+    m.add(
+        "NumberOutOfRangeError",
+        py.get_type_bound::<NumberOutOfRangeError>(),
+    )?;
+    m.add("NetworkError", py.get_type_bound::<NetworkError>())?;
+    Ok(())
+}
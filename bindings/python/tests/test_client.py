@@ -0,0 +1,41 @@
+"""Tests of the Python bindings against the mock server of the `iseven_api mock-server` command.
+
+The server's URL is taken from ISEVEN_API_URL, and defaults to the one of `iseven_api mock-server` with its default
+port and the free plan.
+"""
+
+import asyncio
+import os
+import unittest
+
+from iseven_api import InvalidInputError, IsEvenClient, IsEvenResponse, NumberOutOfRangeError
+
+BASE_URL = os.environ.get("ISEVEN_API_URL", "http://127.0.0.1:8080/api/iseven/")
+
+
+class IsEvenClientTest(unittest.TestCase):
+    def setUp(self):
+        self.client = IsEvenClient(base_url=BASE_URL, cache_size=10)
+
+    def test_is_even(self):
+        even = self.client.is_even(42)
+        self.assertIsInstance(even, IsEvenResponse)
+        self.assertTrue(even.iseven)
+        self.assertFalse(even.isodd)
+        self.assertTrue(self.client.is_even("7").isodd)
+
+    def test_is_even_async(self):
+        async def check(number):
+            return await self.client.is_even_async(number)
+
+        self.assertTrue(asyncio.run(check(41)).isodd)
+
+    def test_errors(self):
+        with self.assertRaises(InvalidInputError):
+            self.client.is_even("abc")
+        with self.assertRaises(NumberOutOfRangeError):
+            self.client.is_even(1_000_000)
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,205 @@
+//! Soak test which checks numbers for a long time, to find leaks in the connection pool and cache.
+//!
+//! Mixes numbers in range, numbers out of range and invalid input, reporting the request and error rates, resident
+//! memory and open file descriptors every interval, and how they drifted from the first interval at the end.
+//!
+//! ```text
+//! cargo run --release --example soak --features mock-server -- --duration 3600 --concurrency 16
+//! ```
+//!
+//! Pass `--base-url` to soak an API other than the bundled mock server. Memory and file descriptors are only tracked
+//! on Linux.
+
+use std::{
+    process::ExitCode,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use iseven_api::{mock::MockServer, IsEvenApiClient, IsEvenApiError, Plan, ResponseCache, Url};
+
+/// Inputs checked in turn, after the numbers in range which most requests are for.
+const OTHER_INPUTS: &[&str] = &["1000000", "-1", "4.2", "abc", ""];
+
+struct Options {
+    base_url: Option<Url>,
+    duration: Duration,
+    interval: Duration,
+    concurrency: usize,
+}
+
+impl Options {
+    fn parse() -> Result<Self, String> {
+        let mut options = Options {
+            base_url: None,
+            duration: Duration::from_secs(60),
+            interval: Duration::from_secs(10),
+            concurrency: 8,
+        };
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            let value = args
+                .next()
+                .ok_or_else(|| format!("missing value for {arg}"))?;
+            let secs = || {
+                value
+                    .parse()
+                    .map(Duration::from_secs)
+                    .map_err(|e| format!("{arg}: {e}"))
+            };
+            match arg.as_str() {
+                "--base-url" => {
+                    options.base_url = Some(value.parse().map_err(|e| format!("{arg}: {e}"))?)
+                }
+                "--duration" => options.duration = secs()?,
+                "--interval" => options.interval = secs()?,
+                "--concurrency" => {
+                    options.concurrency = value.parse().map_err(|e| format!("{arg}: {e}"))?
+                }
+                _ => return Err(format!("unknown option {arg}")),
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// Counts of outcomes since the last report.
+#[derive(Default)]
+struct Counters {
+    ok: AtomicU64,
+    rejected: AtomicU64,
+    failed: AtomicU64,
+}
+
+/// Resources and error rate measured at the end of an interval.
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    error_rate: f64,
+    rss_kib: Option<u64>,
+    fds: Option<usize>,
+}
+
+/// Returns the resident memory of the process in KiB.
+fn rss_kib() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    line.split_whitespace().nth(1)?.parse().ok()
+}
+
+/// Returns the number of file descriptors open in the process.
+fn open_fds() -> Option<usize> {
+    Some(std::fs::read_dir("/proc/self/fd").ok()?.count())
+}
+
+async fn worker(
+    client: IsEvenApiClient,
+    counters: Arc<Counters>,
+    worker: usize,
+    deadline: Instant,
+) {
+    let mut i = worker;
+    while Instant::now() < deadline {
+        // mostly in range, cycling through enough numbers that the cache keeps evicting
+        let input = match i % 10 {
+            0 => OTHER_INPUTS[i / 10 % OTHER_INPUTS.len()].to_string(),
+            _ => (i % 5000).to_string(),
+        };
+        match client.get_str(&input).await {
+            Ok(_) => &counters.ok,
+            // answered by the API with an error, or rejected before making a request
+            Err(e) if e.status().is_some() || matches!(e, IsEvenApiError::InvalidInput { .. }) => {
+                &counters.rejected
+            }
+            Err(_) => &counters.failed,
+        }
+        .fetch_add(1, Ordering::Relaxed);
+        i += 7919;
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let options = match Options::parse() {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("error: {e}");
+            eprintln!("usage: soak [--base-url URL] [--duration SECS] [--interval SECS] [--concurrency N]");
+            return ExitCode::FAILURE;
+        }
+    };
+    let server = match options.base_url {
+        Some(_) => None,
+        None => Some(
+            MockServer::start("127.0.0.1:0", Plan::Free)
+                .await
+                .expect("failed to start mock server"),
+        ),
+    };
+    let base_url = options
+        .base_url
+        .unwrap_or_else(|| server.as_ref().unwrap().url());
+    println!(
+        "soaking {base_url} for {:?} with {} workers",
+        options.duration, options.concurrency
+    );
+
+    let client = IsEvenApiClient::builder()
+        .base_url(base_url)
+        .cache(ResponseCache::new(1000).with_ttl(Duration::from_secs(30)))
+        .build()
+        .expect("failed to build client");
+    let counters = Arc::new(Counters::default());
+    let start = Instant::now();
+    let deadline = start + options.duration;
+    let workers: Vec<_> = (0..options.concurrency)
+        .map(|i| tokio::spawn(worker(client.clone(), counters.clone(), i, deadline)))
+        .collect();
+
+    let mut samples = Vec::new();
+    let mut interval =
+        tokio::time::interval_at((start + options.interval).into(), options.interval);
+    while Instant::now() < deadline {
+        interval.tick().await;
+        let ok = counters.ok.swap(0, Ordering::Relaxed);
+        let rejected = counters.rejected.swap(0, Ordering::Relaxed);
+        let failed = counters.failed.swap(0, Ordering::Relaxed);
+        let total = ok + rejected + failed;
+        let sample = Sample {
+            error_rate: failed as f64 / total.max(1) as f64,
+            rss_kib: rss_kib(),
+            fds: open_fds(),
+        };
+        println!(
+            "{:>6.0?} {:>8.0} req/s  ok {ok:>8}  rejected {rejected:>8}  failed {failed:>6} ({:.2}%)  rss {}  fds {}",
+            start.elapsed(),
+            total as f64 / options.interval.as_secs_f64(),
+            sample.error_rate * 100.0,
+            sample.rss_kib.map_or("?".to_string(), |kib| format!("{kib} KiB")),
+            sample.fds.map_or("?".to_string(), |fds| fds.to_string()),
+        );
+        samples.push(sample);
+    }
+    for worker in workers {
+        worker.await.expect("worker panicked");
+    }
+
+    let (Some(first), Some(last)) = (samples.first(), samples.last()) else {
+        println!("no intervals completed, increase --duration");
+        return ExitCode::SUCCESS;
+    };
+    println!("\ndrift from the first interval:");
+    if let (Some(first), Some(last)) = (first.rss_kib, last.rss_kib) {
+        println!("  memory      {:+} KiB", last as i64 - first as i64);
+    }
+    if let (Some(first), Some(last)) = (first.fds, last.fds) {
+        println!("  fds         {:+}", last as i64 - first as i64);
+    }
+    println!(
+        "  error rate  {:+.2} percentage points",
+        (last.error_rate - first.error_rate) * 100.0
+    );
+    ExitCode::SUCCESS
+}
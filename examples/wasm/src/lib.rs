@@ -0,0 +1,6 @@
+//! Example of using isEven API from JavaScript through WebAssembly.
+//!
+//! Build with `wasm-pack build --target web`, then serve this directory and open `index.html`.
+
+// re-exported so that the client's bindings are part of this module
+pub use iseven_api::wasm::{WasmIsEvenClient, WasmIsEvenResponse};
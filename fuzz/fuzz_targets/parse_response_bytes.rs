@@ -0,0 +1,15 @@
+//! Feeds arbitrary bytes with an arbitrary status code into `parse_response`.
+
+#![no_main]
+
+use iseven_api::{sansio, IsEvenApiError, StatusCode};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: (u16, &[u8])| {
+    let (status, body) = input;
+    let status = StatusCode::from_u16(100 + status % 900).unwrap();
+    match sansio::parse_response(status, body) {
+        Ok(_) | Err(IsEvenApiError::Api(_)) | Err(IsEvenApiError::Protocol(_)) => {}
+        Err(e) => panic!("unexpected error variant: {e:?}"),
+    }
+});
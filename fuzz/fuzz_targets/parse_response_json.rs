@@ -0,0 +1,65 @@
+//! Feeds arbitrary JSON, biased towards the shapes the API returns, into `parse_response`.
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use iseven_api::{sansio, IsEvenApiError, StatusCode};
+use libfuzzer_sys::fuzz_target;
+use serde_json::{Map, Number, Value};
+
+/// A JSON value whose object keys are usually ones the API uses, so that the fuzzer reaches the response types.
+#[derive(Debug, Arbitrary)]
+enum Json {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(Key, Json)>),
+}
+
+#[derive(Debug, Arbitrary)]
+enum Key {
+    Ad,
+    IsEven,
+    Error,
+    Other(String),
+}
+
+impl From<Json> for Value {
+    fn from(json: Json) -> Self {
+        match json {
+            Json::Null => Value::Null,
+            Json::Bool(b) => Value::Bool(b),
+            Json::Int(n) => Value::Number(n.into()),
+            Json::Float(n) => Number::from_f64(n).map_or(Value::Null, Value::Number),
+            Json::String(s) => Value::String(s),
+            Json::Array(values) => Value::Array(values.into_iter().map(Value::from).collect()),
+            Json::Object(entries) => Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| {
+                        let key = match key {
+                            Key::Ad => "ad".to_string(),
+                            Key::IsEven => "iseven".to_string(),
+                            Key::Error => "error".to_string(),
+                            Key::Other(key) => key,
+                        };
+                        (key, Value::from(value))
+                    })
+                    .collect::<Map<_, _>>(),
+            ),
+        }
+    }
+}
+
+fuzz_target!(|input: (u16, Json)| {
+    let (status, json) = input;
+    let status = StatusCode::from_u16(100 + status % 900).unwrap();
+    let body = serde_json::to_vec(&Value::from(json)).unwrap();
+    match sansio::parse_response(status, &body) {
+        Ok(_) | Err(IsEvenApiError::Api(_)) | Err(IsEvenApiError::Protocol(_)) => {}
+        Err(e) => panic!("unexpected error variant: {e:?}"),
+    }
+});
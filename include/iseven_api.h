@@ -0,0 +1,67 @@
+/* isEven API client. Generated with cbindgen, do not edit by hand. */
+
+#ifndef ISEVEN_API_H
+#define ISEVEN_API_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Outcome of [`iseven_check`].
+typedef enum IsEvenStatus {
+  // The number was checked, and `iseven` and `ad` are set
+  IS_EVEN_STATUS_OK = 0,
+  // The API rejected the number as invalid
+  IS_EVEN_STATUS_INVALID_NUMBER = 1,
+  // The number is outside the range allowed by the plan
+  IS_EVEN_STATUS_NUMBER_OUT_OF_RANGE = 2,
+  // The API answered with an unexpected error response
+  IS_EVEN_STATUS_ERROR_RESPONSE = 3,
+  // The API could not be reached, or its response could not be read
+  IS_EVEN_STATUS_NETWORK_ERROR = 4,
+  // `number` or `out` is null, or `number` is not valid UTF-8
+  IS_EVEN_STATUS_INVALID_ARGUMENT = 5,
+  // `number` cannot be a valid number, and was rejected without making a request
+  IS_EVEN_STATUS_INVALID_INPUT = 6,
+} IsEvenStatus;
+
+// Result of [`iseven_check`]. Strings are owned by the library and freed with [`iseven_result_free`].
+typedef struct IsEvenResult {
+  // Whether the number is even, if the check succeeded
+  bool iseven;
+  // Advertisement in the response if the check succeeded, otherwise null
+  char *ad;
+  // Description of the error if the check failed, otherwise null
+  char *error;
+} IsEvenResult;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Checks whether `number`, given in decimal, is even, storing the result in `out`.
+//
+// `out` is always overwritten unless the status is `InvalidArgument`, and must be passed to
+// [`iseven_result_free`] afterwards. This function blocks until the API answers, and may be called from any thread.
+//
+// # Safety
+//
+// `number` must be null or point to a nul-terminated string, and `out` must be null or point to memory valid for
+// writing an `IsEvenResult`.
+IsEvenStatus iseven_check(const char *number, IsEvenResult *out);
+
+// Frees the strings in a result filled in by [`iseven_check`], leaving them null. Freeing a result twice is
+// harmless.
+//
+// # Safety
+//
+// `result` must be null or point to a result filled in by `iseven_check`, or freed with this function.
+void iseven_result_free(IsEvenResult *result);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* ISEVEN_API_H */
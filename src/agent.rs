@@ -0,0 +1,499 @@
+//! Minimal blocking HTTP/1.1 client, used by [`IsEvenApiBlockingClient`](crate::IsEvenApiBlockingClient) when the
+//! `client` feature is disabled so that blocking-only builds depend on neither reqwest nor tokio.
+//!
+//! It only does what the client needs: `GET` requests over TCP or a Unix domain socket, with or without TLS, answered
+//! with a body delimited by `Content-Length`, chunked encoding or the end of the connection. Each request uses a new
+//! connection. The size of response heads and bodies is limited, so that a broken or hostile server cannot make it
+//! allocate without bound.
+
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    net::TcpStream,
+    time::{Duration, Instant},
+};
+#[cfg(unix)]
+use std::{os::unix::net::UnixStream, path::PathBuf};
+
+use http::{HeaderMap, HeaderName, HeaderValue, StatusCode, Version};
+use native_tls::{TlsConnector, TlsStream};
+use url::{Position, Url};
+
+/// Timeout used unless another one is set, matching the default of `reqwest::blocking`.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Largest response body accepted, far more than any answer of the API needs.
+const MAX_BODY_LEN: u64 = 1024 * 1024;
+
+/// Longest status line, header line or chunk size line accepted, including its line ending.
+const MAX_LINE_LEN: u64 = 8 * 1024;
+
+/// Largest number of header lines accepted in a response head.
+const MAX_HEADERS: usize = 100;
+
+/// Blocking HTTP client.
+#[derive(Debug, Clone)]
+pub(crate) struct Agent {
+    tls: TlsConnector,
+    timeout: Duration,
+    /// Socket to connect to instead of the host of the URL
+    #[cfg(unix)]
+    unix_socket: Option<PathBuf>,
+}
+
+/// Response with its body read in full.
+#[derive(Debug)]
+pub(crate) struct Response {
+    status: StatusCode,
+    version: Version,
+    headers: HeaderMap,
+    body: Vec<u8>,
+}
+
+impl Agent {
+    /// Creates an agent whose requests time out after `timeout`, or 30 seconds if it is not given.
+    pub(crate) fn new(timeout: Option<Duration>) -> io::Result<Self> {
+        Ok(Self {
+            tls: TlsConnector::new().map_err(io::Error::other)?,
+            timeout: timeout.unwrap_or(DEFAULT_TIMEOUT),
+            #[cfg(unix)]
+            unix_socket: None,
+        })
+    }
+
+    /// Makes the agent connect to the Unix domain socket at `path`, if it is given, instead of over TCP.
+    #[cfg(unix)]
+    pub(crate) fn unix_socket(self, path: Option<PathBuf>) -> Self {
+        Self {
+            unix_socket: path,
+            ..self
+        }
+    }
+
+    /// Sends a `GET` request for `url` with `headers`, and `authorization` as the `Authorization` header if it is
+    /// given.
+    pub(crate) fn get(
+        &self,
+        url: &str,
+        headers: &HeaderMap,
+        authorization: Option<&str>,
+    ) -> io::Result<Response> {
+        let url = Url::parse(url).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        let host = url
+            .host_str()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "URL has no host"))?;
+        let deadline = Instant::now() + self.timeout;
+        let socket = self.connect(&url, deadline)?;
+        let stream = match url.scheme() {
+            "http" => Stream::Plain(socket),
+            "https" => Stream::Tls(Box::new(self.tls.connect(host, socket).map_err(|e| {
+                io::Error::new(io::ErrorKind::ConnectionAborted, e.to_string())
+            })?)),
+            scheme => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("unsupported URL scheme {}", scheme),
+                ))
+            }
+        };
+        let mut connection = Connection { stream, deadline };
+
+        let mut request = format!(
+            "GET {} HTTP/1.1\r\nHost: {}\r\nAccept: application/json\r\nUser-Agent: {}/{}\r\nConnection: close\r\n",
+            &url[Position::BeforePath..Position::AfterQuery],
+            &url[Position::BeforeHost..Position::AfterPort],
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION"),
+        );
+        for (name, value) in headers {
+            request.push_str(&format!(
+                "{}: {}\r\n",
+                name,
+                String::from_utf8_lossy(value.as_bytes())
+            ));
+        }
+        if let Some(authorization) = authorization {
+            request.push_str(&format!("Authorization: {}\r\n", authorization));
+        }
+        request.push_str("\r\n");
+        connection.write_all(request.as_bytes())?;
+        connection.flush()?;
+
+        read_response(&mut BufReader::new(connection))
+    }
+
+    fn connect(&self, url: &Url, deadline: Instant) -> io::Result<Socket> {
+        #[cfg(unix)]
+        if let Some(path) = &self.unix_socket {
+            return UnixStream::connect(path).map(Socket::Unix);
+        }
+        let mut last_error = None;
+        for addr in url.socket_addrs(|| None)? {
+            let remaining = remaining(deadline)?;
+            match TcpStream::connect_timeout(&addr, remaining) {
+                Ok(tcp) => return Ok(Socket::Tcp(tcp)),
+                Err(e) => last_error = Some(e),
+            }
+        }
+        Err(last_error
+            .unwrap_or_else(|| io::Error::new(io::ErrorKind::NotFound, "host has no addresses")))
+    }
+}
+
+impl Response {
+    /// Returns the status code.
+    pub(crate) fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// Returns the HTTP version.
+    pub(crate) fn version(&self) -> Version {
+        self.version
+    }
+
+    /// Returns the headers.
+    pub(crate) fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// Returns the body.
+    pub(crate) fn body(&self) -> &[u8] {
+        &self.body
+    }
+
+    /// Returns the body, consuming the response.
+    pub(crate) fn bytes(self) -> io::Result<Vec<u8>> {
+        Ok(self.body)
+    }
+
+    /// Returns the body as text.
+    pub(crate) fn text(self) -> io::Result<String> {
+        String::from_utf8(self.body).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+/// Returns the time left until `deadline`, or a timeout error if it has passed.
+fn remaining(deadline: Instant) -> io::Result<Duration> {
+    match deadline.checked_duration_since(Instant::now()) {
+        Some(remaining) if !remaining.is_zero() => Ok(remaining),
+        _ => Err(io::Error::new(io::ErrorKind::TimedOut, "request timed out")),
+    }
+}
+
+#[derive(Debug)]
+enum Socket {
+    Tcp(TcpStream),
+    #[cfg(unix)]
+    Unix(UnixStream),
+}
+
+impl Socket {
+    fn set_read_timeout(&self, timeout: Duration) -> io::Result<()> {
+        match self {
+            Socket::Tcp(tcp) => tcp.set_read_timeout(Some(timeout)),
+            #[cfg(unix)]
+            Socket::Unix(unix) => unix.set_read_timeout(Some(timeout)),
+        }
+    }
+
+    fn set_write_timeout(&self, timeout: Duration) -> io::Result<()> {
+        match self {
+            Socket::Tcp(tcp) => tcp.set_write_timeout(Some(timeout)),
+            #[cfg(unix)]
+            Socket::Unix(unix) => unix.set_write_timeout(Some(timeout)),
+        }
+    }
+}
+
+impl Read for Socket {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Socket::Tcp(tcp) => tcp.read(buf),
+            #[cfg(unix)]
+            Socket::Unix(unix) => unix.read(buf),
+        }
+    }
+}
+
+impl Write for Socket {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Socket::Tcp(tcp) => tcp.write(buf),
+            #[cfg(unix)]
+            Socket::Unix(unix) => unix.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Socket::Tcp(tcp) => tcp.flush(),
+            #[cfg(unix)]
+            Socket::Unix(unix) => unix.flush(),
+        }
+    }
+}
+
+enum Stream {
+    Plain(Socket),
+    Tls(Box<TlsStream<Socket>>),
+}
+
+impl Stream {
+    fn socket(&self) -> &Socket {
+        match self {
+            Stream::Plain(socket) => socket,
+            Stream::Tls(tls) => tls.get_ref(),
+        }
+    }
+}
+
+/// Connection which fails with a timeout error once `deadline` has passed.
+struct Connection {
+    stream: Stream,
+    deadline: Instant,
+}
+
+impl Read for Connection {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.stream
+            .socket()
+            .set_read_timeout(remaining(self.deadline)?)?;
+        match &mut self.stream {
+            Stream::Plain(socket) => socket.read(buf),
+            Stream::Tls(tls) => tls.read(buf),
+        }
+    }
+}
+
+impl Write for Connection {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.stream
+            .socket()
+            .set_write_timeout(remaining(self.deadline)?)?;
+        match &mut self.stream {
+            Stream::Plain(socket) => socket.write(buf),
+            Stream::Tls(tls) => tls.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.stream {
+            Stream::Plain(socket) => socket.flush(),
+            Stream::Tls(tls) => tls.flush(),
+        }
+    }
+}
+
+fn invalid_data<E: Into<Box<dyn std::error::Error + Send + Sync>>>(error: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+/// Reads a line without its line ending, failing if the connection is closed first or the line is longer than
+/// [`MAX_LINE_LEN`].
+fn read_line<R: BufRead>(reader: &mut R) -> io::Result<String> {
+    let mut line = String::new();
+    if reader.by_ref().take(MAX_LINE_LEN).read_line(&mut line)? == 0 {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    if !line.ends_with('\n') && line.len() as u64 == MAX_LINE_LEN {
+        return Err(invalid_data("line too long"));
+    }
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// Appends `len` bytes from `reader` to `body`, failing if the connection is closed first or the body would be longer
+/// than [`MAX_BODY_LEN`].
+fn read_body<R: Read>(reader: &mut R, body: &mut Vec<u8>, len: u64) -> io::Result<()> {
+    let end = (body.len() as u64)
+        .checked_add(len)
+        .filter(|&end| end <= MAX_BODY_LEN)
+        .ok_or_else(|| invalid_data("response body too large"))?;
+    reader.by_ref().take(len).read_to_end(body)?;
+    if (body.len() as u64) < end {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(())
+}
+
+fn read_response<R: BufRead>(reader: &mut R) -> io::Result<Response> {
+    // informational responses such as 100 Continue are followed by the actual response
+    let (version, status, headers) = loop {
+        let head = read_head(reader)?;
+        if !head.1.is_informational() {
+            break head;
+        }
+    };
+    let body = if status == StatusCode::NO_CONTENT || status == StatusCode::NOT_MODIFIED {
+        Vec::new()
+    } else if headers
+        .get_all(http::header::TRANSFER_ENCODING)
+        .iter()
+        .any(|v| v.as_bytes().eq_ignore_ascii_case(b"chunked"))
+    {
+        read_chunked(reader)?
+    } else if let Some(len) = headers.get(http::header::CONTENT_LENGTH) {
+        let len = len
+            .to_str()
+            .ok()
+            .and_then(|len| len.parse().ok())
+            .ok_or_else(|| invalid_data("invalid Content-Length"))?;
+        let mut body = Vec::new();
+        read_body(reader, &mut body, len)?;
+        body
+    } else {
+        let mut body = Vec::new();
+        reader
+            .by_ref()
+            .take(MAX_BODY_LEN + 1)
+            .read_to_end(&mut body)?;
+        if body.len() as u64 > MAX_BODY_LEN {
+            return Err(invalid_data("response body too large"));
+        }
+        body
+    };
+    Ok(Response {
+        status,
+        version,
+        headers,
+        body,
+    })
+}
+
+fn read_head<R: BufRead>(reader: &mut R) -> io::Result<(Version, StatusCode, HeaderMap)> {
+    let status_line = read_line(reader)?;
+    let mut parts = status_line.splitn(3, ' ');
+    let version = match parts.next() {
+        Some("HTTP/1.1") => Version::HTTP_11,
+        Some("HTTP/1.0") => Version::HTTP_10,
+        _ => {
+            return Err(invalid_data(format!(
+                "invalid status line {:?}",
+                status_line
+            )))
+        }
+    };
+    let status = parts
+        .next()
+        .and_then(|code| StatusCode::from_bytes(code.as_bytes()).ok())
+        .ok_or_else(|| invalid_data(format!("invalid status line {:?}", status_line)))?;
+
+    let mut headers = HeaderMap::new();
+    loop {
+        let line = read_line(reader)?;
+        if line.is_empty() {
+            return Ok((version, status, headers));
+        }
+        if headers.len() == MAX_HEADERS {
+            return Err(invalid_data("too many headers"));
+        }
+        let (name, value) = line
+            .split_once(':')
+            .ok_or_else(|| invalid_data(format!("invalid header {:?}", line)))?;
+        headers.append(
+            HeaderName::from_bytes(name.trim().as_bytes()).map_err(invalid_data)?,
+            HeaderValue::from_str(value.trim()).map_err(invalid_data)?,
+        );
+    }
+}
+
+fn read_chunked<R: BufRead>(reader: &mut R) -> io::Result<Vec<u8>> {
+    let mut body = Vec::new();
+    loop {
+        let line = read_line(reader)?;
+        // chunk extensions after `;` are ignored
+        let size = line.split(';').next().unwrap_or_default().trim();
+        let size = u64::from_str_radix(size, 16)
+            .map_err(|_| invalid_data(format!("invalid chunk size {:?}", size)))?;
+        if size == 0 {
+            // skip the trailers
+            while !read_line(reader)?.is_empty() {}
+            return Ok(body);
+        }
+        read_body(reader, &mut body, size)?;
+        if !read_line(reader)?.is_empty() {
+            return Err(invalid_data("missing line ending after chunk"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::TcpListener, thread};
+
+    use super::*;
+
+    #[test]
+    fn test_get() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(&stream);
+            let mut request = Vec::new();
+            loop {
+                let line = read_line(&mut reader).unwrap();
+                if line.is_empty() {
+                    break;
+                }
+                request.push(line);
+            }
+            (&stream)
+                .write_all(
+                    b"HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n\
+                      6\r\n{\"ad\":\r\n1b;ext=1\r\n \"Buy now\", \"iseven\": true}\r\n0\r\n\r\n",
+                )
+                .unwrap();
+            request
+        });
+
+        let agent = Agent::new(None).unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert("x-request", HeaderValue::from_static("1"));
+        let response = agent
+            .get(
+                &format!("http://{}/api/iseven/42", addr),
+                &headers,
+                Some("Bearer key"),
+            )
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.text().unwrap(),
+            "{\"ad\": \"Buy now\", \"iseven\": true}"
+        );
+        let request = server.join().unwrap();
+        assert_eq!(request[0], "GET /api/iseven/42 HTTP/1.1");
+        assert!(request.contains(&format!("Host: {}", addr)));
+        assert!(request.contains(&"x-request: 1".to_string()));
+        assert!(request.contains(&"Authorization: Bearer key".to_string()));
+    }
+
+    #[test]
+    fn test_limits() {
+        let read = |response: &[u8]| read_response(&mut &response[..]).map(|r| r.body);
+        let too_large =
+            |response: &[u8]| read(response).is_err_and(|e| e.kind() == io::ErrorKind::InvalidData);
+        assert!(too_large(
+            b"HTTP/1.1 200 OK\r\nContent-Length: 18446744073709551615\r\n\r\n"
+        ));
+        assert!(too_large(
+            b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n2\r\n{}\r\nffffffffffffffff\r\n"
+        ));
+        let mut long_header = b"HTTP/1.1 200 OK\r\nX-Padding: ".to_vec();
+        long_header.resize(long_header.len() + MAX_LINE_LEN as usize, b'a');
+        assert!(too_large(&long_header));
+        let mut long_body = b"HTTP/1.1 200 OK\r\n\r\n".to_vec();
+        long_body.resize(long_body.len() + MAX_BODY_LEN as usize + 1, b' ');
+        assert!(too_large(&long_body));
+
+        assert_eq!(
+            read(b"HTTP/1.1 200 OK\r\nContent-Length: 4\r\n\r\n{}")
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+        assert_eq!(
+            read(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\n{}").unwrap(),
+            b"{}"
+        );
+    }
+}
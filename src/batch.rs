@@ -0,0 +1,438 @@
+//! Checking many numbers at once with [`IsEvenApiClient::get_many`] and [`IsEvenApiClient::get_stream`].
+
+use std::{
+    cmp::Ordering,
+    fmt::{self, Debug, Display, Formatter},
+    future::ready,
+};
+
+use futures_util::{future::Either, stream, Stream, StreamExt};
+
+use crate::{request::CacheMode, ApiNumber, IsEvenApiClient, IsEvenApiError, IsEvenApiResponse};
+
+/// Order in which [`IsEvenApiClient::get_many`] yields results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum BatchOrder {
+    /// Results are yielded in the order the numbers were given, holding back results which arrive early.
+    #[default]
+    Input,
+    /// Results are yielded as soon as they arrive, which may differ from the order the numbers were given.
+    Completion,
+}
+
+/// Number of numbers a batch takes from its input at a time, unless set with [`GetStream::chunk_size`].
+pub const DEFAULT_CHUNK_SIZE: usize = 1024;
+
+/// Function reporting the progress of a batch, set with [`GetStream::on_chunk`].
+type OnChunk<'a> = Box<dyn FnMut(&ChunkProgress) + Send + 'a>;
+
+/// Stream of numbers to check, returned by [`IsEvenApiClient::get_stream`]. Choose the order of the results with
+/// [`Self::ordered`] or [`Self::unordered`].
+///
+/// Numbers are taken from the input in chunks of up to [`Self::chunk_size`] numbers, and the next chunk is only taken
+/// once every result of the current one has been yielded. However long the input is, only the numbers of one chunk are
+/// held at a time, and [`Self::on_chunk`] can report progress after each chunk.
+#[must_use = "streams do nothing unless polled, so call `ordered` or `unordered` and poll the result"]
+pub struct GetStream<'a, S: Stream> {
+    client: &'a IsEvenApiClient,
+    numbers: S,
+    /// Converts a number to the text requested for it
+    text: fn(&S::Item) -> String,
+    concurrency: usize,
+    chunk_size: usize,
+    on_chunk: Option<OnChunk<'a>>,
+}
+
+impl<'a, S> GetStream<'a, S>
+where
+    S: Stream + 'a,
+    S::Item: 'a,
+{
+    pub(crate) fn new(
+        client: &'a IsEvenApiClient,
+        numbers: S,
+        text: fn(&S::Item) -> String,
+        concurrency: usize,
+    ) -> Self {
+        assert!(concurrency > 0, "concurrency must be greater than 0");
+        Self {
+            client,
+            numbers,
+            text,
+            concurrency,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            on_chunk: None,
+        }
+    }
+
+    /// Sets the largest number of numbers taken from the input at a time. Defaults to [`DEFAULT_CHUNK_SIZE`].
+    ///
+    /// Numbers which are already available are taken together, so a stream whose numbers arrive slowly gives smaller
+    /// chunks rather than waiting for a chunk to fill.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is 0.
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        assert!(chunk_size > 0, "chunk size must be greater than 0");
+        self.chunk_size = chunk_size;
+        self
+    }
+
+    /// Sets a function which is called with the progress of the batch after every result of a chunk has been yielded.
+    pub fn on_chunk<F: FnMut(&ChunkProgress) + Send + 'a>(mut self, on_chunk: F) -> Self {
+        self.on_chunk = Some(Box::new(on_chunk));
+        self
+    }
+
+    /// Yields results in the order the numbers arrive, like [`StreamExt::buffered`]. A slow request holds back the
+    /// results after it, up to the concurrency limit.
+    pub fn ordered(self) -> impl Stream<Item = BatchItem<S::Item>> + 'a {
+        self.results(BatchOrder::Input)
+    }
+
+    /// Yields results as soon as they complete, like [`StreamExt::buffer_unordered`]. Use [`BatchItem::index`] to
+    /// tell which number each result is for.
+    pub fn unordered(self) -> impl Stream<Item = BatchItem<S::Item>> + 'a {
+        self.results(BatchOrder::Completion)
+    }
+
+    fn results(self, order: BatchOrder) -> impl Stream<Item = BatchItem<S::Item>> + 'a {
+        let Self {
+            client,
+            numbers,
+            text,
+            concurrency,
+            chunk_size,
+            mut on_chunk,
+        } = self;
+        let mut progress = ChunkProgress::default();
+        numbers
+            .enumerate()
+            .ready_chunks(chunk_size)
+            .enumerate()
+            .flat_map(move |(chunk, numbers)| {
+                let len = numbers.len();
+                let requests = stream::iter(numbers).map(move |(index, input)| async move {
+                    let result = client.get_with(text(&input), CacheMode::Use).await;
+                    BatchItem::new(index, input, result)
+                });
+                let results = match order {
+                    BatchOrder::Input => Either::Left(requests.buffered(concurrency)),
+                    BatchOrder::Completion => Either::Right(requests.buffer_unordered(concurrency)),
+                };
+                results
+                    .map(Event::Item)
+                    .chain(stream::once(ready(Event::ChunkDone { chunk, len })))
+            })
+            .filter_map(move |event| {
+                ready(match event {
+                    Event::Item(item) => {
+                        progress.failed += usize::from(item.result.is_err());
+                        Some(item)
+                    }
+                    Event::ChunkDone { chunk, len } => {
+                        progress.chunk = chunk;
+                        progress.len = len;
+                        progress.checked += len;
+                        if let Some(on_chunk) = &mut on_chunk {
+                            on_chunk(&progress);
+                        }
+                        progress.failed = 0;
+                        None
+                    }
+                })
+            })
+    }
+}
+
+impl<S: Stream + Debug> Debug for GetStream<'_, S> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GetStream")
+            .field("client", &self.client)
+            .field("numbers", &self.numbers)
+            .field("concurrency", &self.concurrency)
+            .field("chunk_size", &self.chunk_size)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Results of a [`GetStream`], interleaved with the ends of the chunks they belong to.
+enum Event<T> {
+    Item(BatchItem<T>),
+    ChunkDone { chunk: usize, len: usize },
+}
+
+/// Progress of a batch after a chunk of it is done, passed to [`GetStream::on_chunk`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ChunkProgress {
+    chunk: usize,
+    len: usize,
+    failed: usize,
+    checked: usize,
+}
+
+impl ChunkProgress {
+    /// Returns the position of the chunk among the chunks of the batch, starting from 0.
+    pub fn chunk(&self) -> usize {
+        self.chunk
+    }
+
+    /// Returns the number of numbers in the chunk.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the chunk has no numbers, which never happens for chunks passed to [`GetStream::on_chunk`].
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of numbers in the chunk which failed.
+    pub fn failed(&self) -> usize {
+        self.failed
+    }
+
+    /// Returns the number of numbers checked so far in the batch, including the ones in the chunk.
+    pub fn checked(&self) -> usize {
+        self.checked
+    }
+}
+
+/// Result of checking one number of a batch, tagged with the number it is for.
+///
+/// Each result carries its input and position, so results cannot be attributed to the wrong number even when they
+/// arrive out of order or some numbers fail.
+#[derive(Debug)]
+pub struct BatchItem<T> {
+    index: usize,
+    input: T,
+    result: Result<IsEvenApiResponse, IsEvenApiError>,
+}
+
+impl<T> BatchItem<T> {
+    pub(crate) fn new(
+        index: usize,
+        input: T,
+        result: Result<IsEvenApiResponse, IsEvenApiError>,
+    ) -> Self {
+        Self {
+            index,
+            input,
+            result,
+        }
+    }
+
+    /// Returns the position of the number among the numbers given, starting from 0.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the number as it was given.
+    pub fn input(&self) -> &T {
+        &self.input
+    }
+
+    /// Returns the result of checking the number.
+    pub fn result(&self) -> &Result<IsEvenApiResponse, IsEvenApiError> {
+        &self.result
+    }
+
+    /// Returns the position, number and result.
+    pub fn into_parts(self) -> (usize, T, Result<IsEvenApiResponse, IsEvenApiError>) {
+        (self.index, self.input, self.result)
+    }
+}
+
+impl<T: Display> BatchItem<T> {
+    /// Returns the number which was checked, in canonical form, or `None` if the input is not a valid number.
+    pub fn number(&self) -> Option<ApiNumber> {
+        match &self.result {
+            Ok(response) => response.number().cloned(),
+            Err(_) => self.input.to_string().parse().ok(),
+        }
+    }
+
+    /// Compares the numbers which were checked in numeric order, ignoring the results, like
+    /// [`IsEvenApiResponse::cmp_by_number`]. Items whose input is not a valid number come first.
+    ///
+    /// Equivalent inputs such as `42` and `042` compare equal, so sorting with this and then deduplicating leaves one
+    /// item per number.
+    pub fn cmp_by_number(&self, other: &Self) -> Ordering {
+        self.number().cmp(&other.number())
+    }
+}
+
+#[cfg(all(test, feature = "mock-server"))]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use futures_util::{FutureExt, SinkExt, StreamExt};
+
+    use crate::{mock::MockServer, ApiNumber, BatchOrder, IsEvenApiClient, Plan};
+
+    #[tokio::test]
+    async fn test_cmp_by_number() {
+        let server = MockServer::start("127.0.0.1:0", Plan::Free).await.unwrap();
+        let client = IsEvenApiClient::builder()
+            .base_url(server.url())
+            .build()
+            .unwrap();
+        let mut items: Vec<_> = client
+            .get_many_str(
+                ["10", "042", "abc", "9", "42", "-1"],
+                6,
+                BatchOrder::Completion,
+            )
+            .collect()
+            .await;
+        items.sort_by(|a, b| a.cmp_by_number(b));
+        items.dedup_by(|a, b| a.cmp_by_number(b).is_eq());
+        let numbers: Vec<_> = items
+            .iter()
+            .map(|item| item.number().map(String::from))
+            .collect();
+        let expected = [None, Some("-1"), Some("9"), Some("10"), Some("42")];
+        assert_eq!(numbers, expected.map(|n| n.map(String::from)));
+        assert_eq!(
+            items[4]
+                .result()
+                .as_ref()
+                .unwrap()
+                .number()
+                .unwrap()
+                .as_str(),
+            "42"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_stream() {
+        let server = MockServer::start("127.0.0.1:0", Plan::Free).await.unwrap();
+        let client = IsEvenApiClient::builder()
+            .base_url(server.url())
+            .build()
+            .unwrap();
+        let numbers = || futures_util::stream::iter(1..=20);
+
+        let ordered: Vec<_> = client.get_stream(numbers(), 4).ordered().collect().await;
+        assert!(ordered
+            .iter()
+            .enumerate()
+            .all(|(i, item)| item.index() == i && *item.input() == i + 1));
+
+        let mut unordered: Vec<_> = client
+            .get_stream(numbers(), 4)
+            .unordered()
+            .map(|item| (*item.input(), item.result().as_ref().unwrap().iseven()))
+            .collect()
+            .await;
+        unordered.sort();
+        assert_eq!(unordered.len(), 20);
+        assert!(unordered.iter().all(|&(n, iseven)| iseven == (n % 2 == 0)));
+    }
+
+    #[tokio::test]
+    async fn test_chunks() {
+        let server = MockServer::start("127.0.0.1:0", Plan::Free).await.unwrap();
+        let client = IsEvenApiClient::builder()
+            .base_url(server.url())
+            .build()
+            .unwrap();
+        let numbers = ["1", "2", "abc", "4", "5", "6", "x", "8", "9", "10"];
+
+        for ordered in [true, false] {
+            let progress = Arc::new(Mutex::new(Vec::new()));
+            let reported = progress.clone();
+            let stream = client
+                .get_stream_str(futures_util::stream::iter(numbers), 2)
+                .chunk_size(3)
+                .on_chunk(move |p| reported.lock().unwrap().push(*p));
+            let items: Vec<_> = if ordered {
+                stream.ordered().collect().await
+            } else {
+                stream.unordered().collect().await
+            };
+            assert_eq!(items.len(), numbers.len());
+            let progress = progress.lock().unwrap();
+            let summary: Vec<_> = progress
+                .iter()
+                .map(|p| (p.chunk(), p.len(), p.failed(), p.checked()))
+                .collect();
+            assert_eq!(
+                summary,
+                [(0, 3, 1, 3), (1, 3, 0, 6), (2, 3, 1, 9), (3, 1, 0, 10)]
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_checker_sink() {
+        let server = MockServer::start("127.0.0.1:0", Plan::Free).await.unwrap();
+        let client = IsEvenApiClient::builder()
+            .base_url(server.url())
+            .build()
+            .unwrap();
+
+        let (mut sink, results) = client.checker_sink(2);
+        let producer = tokio::spawn(async move {
+            for n in 1..=20 {
+                sink.send(ApiNumber::from(n)).await.unwrap();
+            }
+        });
+        let mut results: Vec<_> = results
+            .map(|item| (item.index(), item.into_parts().2.unwrap()))
+            .collect()
+            .await;
+        producer.await.unwrap();
+        results.sort_by_key(|(index, _)| *index);
+        assert_eq!(results.len(), 20);
+        for (index, response) in results {
+            assert_eq!(response.iseven(), index % 2 == 1);
+        }
+
+        // nothing is checked while the results are not polled, so the sink fills up
+        let (mut sink, results) = client.checker_sink(2);
+        let mut sent = 0;
+        while sink.send(ApiNumber::from(sent)).now_or_never().is_some() {
+            sent += 1;
+        }
+        assert!(sent <= 3, "{} numbers sent", sent);
+        drop(results);
+        assert!(sink.send(ApiNumber::from(0)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_get_many() {
+        let server = MockServer::start("127.0.0.1:0", Plan::Free).await.unwrap();
+        let client = IsEvenApiClient::builder()
+            .base_url(server.url())
+            .build()
+            .unwrap();
+        let numbers = ["4", "abc", "7", "-1", "10"];
+
+        let ordered: Vec<_> = client
+            .get_many_str(numbers, 2, BatchOrder::Input)
+            .collect()
+            .await;
+        for (i, item) in ordered.iter().enumerate() {
+            assert_eq!(item.index(), i);
+            assert_eq!(*item.input(), numbers[i]);
+        }
+        assert!(ordered[0].result().as_ref().unwrap().iseven());
+        assert!(ordered[1].result().is_err());
+        assert!(ordered[2].result().as_ref().unwrap().isodd());
+        assert!(ordered[3].result().is_err());
+
+        let mut completed: Vec<_> = client
+            .get_many_str(numbers, 5, BatchOrder::Completion)
+            .map(|item| item.into_parts())
+            .collect()
+            .await;
+        completed.sort_by_key(|(index, _, _)| *index);
+        for (index, input, result) in completed {
+            assert_eq!(input, numbers[index]);
+            assert_eq!(result.is_ok(), ordered[index].result().is_ok(), "{}", input);
+        }
+    }
+}
@@ -0,0 +1,524 @@
+//! Builder for configuring API clients.
+
+use std::fmt::{Debug, Display, Formatter};
+#[cfg(unix)]
+use std::path::PathBuf;
+#[cfg(feature = "http3")]
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use http::{header::CACHE_CONTROL, HeaderValue};
+use log::debug;
+use url::Url;
+
+#[cfg(feature = "blocking")]
+use crate::IsEvenApiBlockingClient;
+#[cfg(feature = "client")]
+use crate::IsEvenApiClient;
+use crate::{
+    cache::RefreshGuard,
+    input,
+    layer::{self, IsEvenLayer, Layers},
+    metadata::{self, REQUEST_ID_HEADER},
+    request::CacheMode,
+    sansio, Clock, IsEvenApiError, IsEvenApiResponse, RateLimiter, ResponseCache, ResponseHead,
+    RetryBudget, RetryPolicy, Rng, SystemClock, ThreadRng, API_URL,
+};
+
+/// Builder for [`IsEvenApiClient`] and [`IsEvenApiBlockingClient`].
+///
+/// # Examples
+///
+#[cfg_attr(feature = "client", doc = "```")]
+#[cfg_attr(not(feature = "client"), doc = "```ignore")]
+/// # use std::error::Error;
+/// use std::time::Duration;
+/// use iseven_api::{IsEvenApiClient, RetryPolicy};
+///
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// let client = IsEvenApiClient::builder()
+///     .retry_policy(RetryPolicy::new(3))
+///     .timeout(Duration::from_secs(5))
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct IsEvenApiClientBuilder {
+    base_url: Option<Url>,
+    api_key: Option<ApiKey>,
+    retry_policy: RetryPolicy,
+    retry_budget: Option<RetryBudget>,
+    timeout: Option<Duration>,
+    cache: Option<ResponseCache>,
+    rate_limiter: Option<RateLimiter>,
+    layers: Layers,
+    lenient_parsing: bool,
+    clock: Option<Arc<dyn Clock>>,
+    rng: Option<Arc<dyn Rng>>,
+    #[cfg(feature = "http3")]
+    http3: bool,
+    #[cfg(unix)]
+    unix_socket: Option<PathBuf>,
+}
+
+impl IsEvenApiClientBuilder {
+    /// Creates a new builder with the default configuration.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the base URL of the API, to which the number being checked is appended. Useful for pointing the client at
+    /// a mirror or a mock server.
+    ///
+    /// Defaults to `https://api.isevenapi.xyz/api/iseven/`.
+    pub fn base_url(mut self, url: Url) -> Self {
+        self.base_url = Some(url);
+        self
+    }
+
+    /// Sets the API key for paid plans. It is sent as a bearer token in the `Authorization` header of each request,
+    /// and is never included in [`Debug`] output.
+    pub fn api_key<S: Into<String>>(mut self, key: S) -> Self {
+        self.api_key = Some(ApiKey(key.into()));
+        self
+    }
+
+    /// Sets the policy for retrying failed requests. By default, requests are not retried.
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = policy;
+        self
+    }
+
+    /// Sets a budget limiting how many retries the client makes over time, across all requests and clones of the
+    /// client. The budget may be shared with other clients. By default, retries are only limited by the retry policy.
+    pub fn retry_budget(mut self, budget: RetryBudget) -> Self {
+        self.retry_budget = Some(budget);
+        self
+    }
+
+    /// Sets a timeout for each request, from connecting until the response body has been read. Each retry gets a new
+    /// timeout.
+    ///
+    /// By default, the async client has no timeout and the blocking client uses the [`reqwest::blocking`] default of
+    /// 30 seconds.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets a cache for successful responses. The cache may be shared with other clients.
+    pub fn cache(mut self, cache: ResponseCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Sets a rate limiter which all requests, including retries, must pass through. The limiter may be shared with
+    /// other clients.
+    pub fn rate_limiter(mut self, limiter: RateLimiter) -> Self {
+        self.rate_limiter = Some(limiter);
+        self
+    }
+
+    /// Adds a [layer](crate::layer) which every request passes through. Layers see requests in the order they were
+    /// added, and their outcomes in the reverse order.
+    pub fn layer<L: IsEvenLayer + 'static>(mut self, layer: L) -> Self {
+        self.layers.push(Arc::new(layer));
+        self
+    }
+
+    /// Accepts decimals and scientific notation which denote integers, such as `42.0` and `4.20e1`, when `enabled`,
+    /// converting them before making a request as described in [`input::parse_lenient`](crate::input::parse_lenient).
+    /// Otherwise, the clients reject them with [`IsEvenApiError::InvalidInput`]. Disabled by default.
+    pub fn lenient_parsing(mut self, enabled: bool) -> Self {
+        self.lenient_parsing = enabled;
+        self
+    }
+
+    /// Sets the clock used to wait before retries and for the rate limiter, e.g. a `MockClock` from the `test-util`
+    /// feature so that tests do not have to wait. Defaults to [`SystemClock`].
+    ///
+    /// The cache and rate limiter read the time from their own clocks, which are set separately.
+    pub fn clock<C: Clock + 'static>(mut self, clock: C) -> Self {
+        self.clock = Some(Arc::new(clock));
+        self
+    }
+
+    /// Sets the source of randomness for [jitter](RetryPolicy::with_jitter) in retry delays and for request IDs, e.g.
+    /// a [`SeededRng`](crate::SeededRng) to make them the same on every run. Defaults to [`ThreadRng`].
+    pub fn rng<R: Rng + 'static>(mut self, rng: R) -> Self {
+        self.rng = Some(Arc::new(rng));
+        self
+    }
+
+    /// Makes `https` requests over HTTP/3 (QUIC) when `enabled`. Once a request fails over HTTP/3, e.g. because UDP
+    /// is blocked, it is retried over HTTP/2 or HTTP/1.1, which the client (and its clones) then keep using. Disabled
+    /// by default.
+    ///
+    /// HTTP/3 clients use rustls rather than the platform's TLS library. As reqwest's HTTP/3 support is unstable,
+    /// building with the `http3` feature also requires `RUSTFLAGS='--cfg reqwest_unstable'`.
+    #[cfg(feature = "http3")]
+    pub fn http3(mut self, enabled: bool) -> Self {
+        self.http3 = enabled;
+        self
+    }
+
+    /// Connects to the API through the Unix domain socket at `path` instead of over TCP, e.g. to reach a proxy started
+    /// with `iseven_api serve --socket` or another local sidecar. The base URL is still used for the request path and
+    /// `Host` header, so it should usually be set as well, e.g. to `http://localhost/api/iseven/`.
+    #[cfg(unix)]
+    pub fn unix_socket<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.unix_socket = Some(path.into());
+        self
+    }
+
+    /// Builds an [`IsEvenApiClient`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IsEvenApiError::Transport`] if the underlying HTTP client cannot be initialised.
+    #[cfg(feature = "client")]
+    pub fn build(self) -> Result<IsEvenApiClient, IsEvenApiError> {
+        let mut builder = reqwest::Client::builder();
+        // fetch has no client-wide timeout
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        // reqwest only supports HTTP/3 with rustls
+        #[cfg(feature = "http3")]
+        if self.http3 {
+            builder = builder.use_rustls_tls();
+        }
+        #[cfg(unix)]
+        if let Some(path) = &self.unix_socket {
+            builder = builder.unix_socket(path.clone());
+        }
+        let client = builder.build()?;
+        Ok(IsEvenApiClient {
+            client,
+            config: self.into_config(),
+        })
+    }
+
+    /// Builds an [`IsEvenApiBlockingClient`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IsEvenApiError::Transport`] if the underlying HTTP client, or the TLS backend when the `client`
+    /// feature is disabled, cannot be initialised.
+    #[cfg(feature = "blocking")]
+    pub fn build_blocking(self) -> Result<IsEvenApiBlockingClient, IsEvenApiError> {
+        #[cfg(feature = "client")]
+        let client = {
+            let mut builder = reqwest::blocking::Client::builder();
+            if let Some(timeout) = self.timeout {
+                builder = builder.timeout(timeout);
+            }
+            #[cfg(feature = "http3")]
+            if self.http3 {
+                builder = builder.use_rustls_tls();
+            }
+            #[cfg(unix)]
+            if let Some(path) = &self.unix_socket {
+                builder = builder.unix_socket(path.clone());
+            }
+            builder.build()?
+        };
+        #[cfg(not(feature = "client"))]
+        let client = crate::agent::Agent::new(self.timeout)?;
+        #[cfg(all(unix, not(feature = "client")))]
+        let client = client.unix_socket(self.unix_socket.clone());
+        Ok(IsEvenApiBlockingClient {
+            client,
+            config: self.into_config(),
+        })
+    }
+
+    pub(crate) fn into_config(self) -> ClientConfig {
+        ClientConfig {
+            base_url: self.base_url_string(),
+            api_key: self.api_key,
+            retry_policy: self.retry_policy,
+            retry_budget: self.retry_budget,
+            cache: self.cache,
+            rate_limiter: self.rate_limiter,
+            layers: self.layers,
+            lenient_parsing: self.lenient_parsing,
+            clock: self.clock.unwrap_or_else(|| Arc::new(SystemClock)),
+            rng: self.rng.unwrap_or_else(|| Arc::new(ThreadRng)),
+            #[cfg(feature = "http3")]
+            http3: Arc::new(AtomicBool::new(self.http3)),
+        }
+    }
+
+    /// Returns the base URL with a trailing slash, ready for the number to be appended.
+    fn base_url_string(&self) -> String {
+        match &self.base_url {
+            Some(url) if url.as_str().ends_with('/') => url.to_string(),
+            Some(url) => format!("{}/", url),
+            None => API_URL.to_string(),
+        }
+    }
+}
+
+/// API key which is redacted from [`Debug`] output.
+#[derive(Clone, PartialEq, Eq)]
+pub(crate) struct ApiKey(String);
+
+impl Debug for ApiKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ApiKey(<redacted>)")
+    }
+}
+
+/// Configuration shared by the async and blocking clients.
+#[derive(Debug, Clone)]
+pub(crate) struct ClientConfig {
+    /// Base URL ending with a slash
+    pub(crate) base_url: String,
+    pub(crate) api_key: Option<ApiKey>,
+    pub(crate) retry_policy: RetryPolicy,
+    pub(crate) retry_budget: Option<RetryBudget>,
+    pub(crate) cache: Option<ResponseCache>,
+    pub(crate) rate_limiter: Option<RateLimiter>,
+    pub(crate) layers: Layers,
+    /// Whether numbers are parsed with [`input::parse_lenient`] rather than [`input::parse`]
+    pub(crate) lenient_parsing: bool,
+    /// Clock for waiting before retries and for the rate limiter
+    pub(crate) clock: Arc<dyn Clock>,
+    /// Source of randomness for retry jitter and request IDs
+    pub(crate) rng: Arc<dyn Rng>,
+    /// Whether requests are tried over HTTP/3 first, cleared once one fails
+    #[cfg(feature = "http3")]
+    pub(crate) http3: Arc<AtomicBool>,
+}
+
+impl ClientConfig {
+    /// Converts `number` to the canonical form used for requests and cache keys, failing with
+    /// [`IsEvenApiError::InvalidInput`] if it cannot be a valid number.
+    pub(crate) fn parse<T: Display>(&self, number: T) -> Result<String, IsEvenApiError> {
+        let input = number.to_string();
+        let parsed = if self.lenient_parsing {
+            input::parse_lenient(&input)
+        } else {
+            input::parse(&input)
+        };
+        match parsed {
+            Ok(number) => Ok(number.into_owned()),
+            Err(reason) => Err(IsEvenApiError::InvalidInput { input, reason }),
+        }
+    }
+
+    /// Returns a new ID for a request.
+    pub(crate) fn new_request_id(&self) -> String {
+        metadata::new_request_id(&*self.rng)
+    }
+
+    /// Returns `true` if a request which failed with `error` after `retry` previous retries should be retried, taking a
+    /// token from the retry budget if there is one.
+    pub(crate) fn should_retry(&self, retry: u32, error: &IsEvenApiError) -> bool {
+        if !self.retry_policy.should_retry(retry, error) {
+            return false;
+        }
+        match &self.retry_budget {
+            Some(budget) if !budget.try_acquire() => {
+                debug!("Retry budget exhausted, not retrying ({})", error);
+                false
+            }
+            _ => true,
+        }
+    }
+
+    /// Returns the delay before retry number `retry`, with jitter if the retry policy has it.
+    pub(crate) fn retry_delay(&self, retry: u32) -> Duration {
+        self.retry_policy.jittered_delay_for(retry, &*self.rng)
+    }
+
+    /// Returns the URL for checking `number`, after [normalizing](crate::input::normalize) it.
+    pub(crate) fn request_url<T: Display>(&self, number: T) -> String {
+        sansio::request_url(&self.base_url, number)
+    }
+
+    /// Returns the request with ID `request_id` for checking `number`, after passing it through the layers. With
+    /// [`CacheMode::Refresh`], the request asks caches on the way, such as a proxy, not to answer it from their cache.
+    pub(crate) fn prepare<T: Display>(
+        &self,
+        number: T,
+        request_id: &str,
+        cache_mode: CacheMode,
+    ) -> layer::Request {
+        let number = number.to_string();
+        let mut request =
+            layer::Request::new(&input::normalize(&number), self.request_url(&number));
+        request.headers_mut().insert(
+            REQUEST_ID_HEADER,
+            HeaderValue::from_str(request_id).expect("request IDs are valid header values"),
+        );
+        if cache_mode == CacheMode::Refresh {
+            request
+                .headers_mut()
+                .insert(CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+        }
+        self.layers.on_request(&mut request);
+        request
+    }
+
+    /// Passes the outcome of `request` through the layers, then returns it.
+    pub(crate) fn finish<R: ResponseHead>(
+        &self,
+        request: &layer::Request,
+        result: Result<R, IsEvenApiError>,
+    ) -> Result<R, IsEvenApiError> {
+        if !self.layers.is_empty() {
+            match &result {
+                Ok(response) => self
+                    .layers
+                    .on_response(request, Ok(&layer::Response::from_head(response))),
+                Err(e) => self.layers.on_response(request, Err(e)),
+            }
+        }
+        result
+    }
+
+    /// Returns `true` if a request for `url` should be tried over HTTP/3 first.
+    #[cfg(feature = "http3")]
+    pub(crate) fn try_http3(&self, url: &Url) -> bool {
+        url.scheme() == "https" && self.http3.load(Ordering::Relaxed)
+    }
+
+    /// Stops trying requests over HTTP/3 after one failed with `error`.
+    #[cfg(feature = "http3")]
+    pub(crate) fn http3_failed(&self, error: &reqwest::Error) {
+        if self.http3.swap(false, Ordering::Relaxed) {
+            debug!(
+                "HTTP/3 request failed ({}), falling back to HTTP/2 or HTTP/1.1",
+                error
+            );
+        }
+    }
+
+    /// Returns the cached response for `number`, if any and if `cache_mode` allows it.
+    ///
+    /// A stale response is returned along with a guard if it should be refreshed in the background, which is held
+    /// until the refresh is done.
+    pub(crate) fn cached(
+        &self,
+        number: &str,
+        cache_mode: CacheMode,
+    ) -> Option<(IsEvenApiResponse, Option<RefreshGuard>)> {
+        let cache = self.cache.as_ref()?;
+        match cache_mode {
+            CacheMode::Use => {}
+            CacheMode::NoCache | CacheMode::Refresh => return None,
+        }
+        let (response, refresh) = cache.lookup(number, true)?;
+        match refresh {
+            Some(_) => debug!("Using stale cached response for {}, refreshing it", number),
+            None => debug!("Using cached response for {}", number),
+        }
+        Some((response, refresh))
+    }
+
+    /// Caches `result` if it is a successful response, then returns it.
+    pub(crate) fn store(
+        &self,
+        number: String,
+        result: Result<IsEvenApiResponse, IsEvenApiError>,
+    ) -> Result<IsEvenApiResponse, IsEvenApiError> {
+        let result = result.map(|response| response.for_number(&number));
+        if let (Some(cache), Ok(response)) = (&self.cache, &result) {
+            cache.insert(number, response.clone());
+        }
+        result
+    }
+
+    /// Returns the API key, if one is configured.
+    #[cfg(any(feature = "wasm", not(feature = "client")))]
+    pub(crate) fn api_key(&self) -> Option<&str> {
+        self.api_key.as_ref().map(|ApiKey(key)| key.as_str())
+    }
+
+    /// Adds authentication to an async request, if an API key is configured.
+    #[cfg(feature = "client")]
+    pub(crate) fn authorize(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match &self.api_key {
+            Some(ApiKey(key)) => request.bearer_auth(key),
+            None => request,
+        }
+    }
+
+    /// Adds authentication to a blocking request, if an API key is configured.
+    #[cfg(all(feature = "blocking", feature = "client"))]
+    pub(crate) fn authorize_blocking(
+        &self,
+        request: reqwest::blocking::RequestBuilder,
+    ) -> reqwest::blocking::RequestBuilder {
+        match &self.api_key {
+            Some(ApiKey(key)) => request.bearer_auth(key),
+            None => request,
+        }
+    }
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        Self {
+            base_url: API_URL.to_string(),
+            api_key: None,
+            retry_policy: RetryPolicy::default(),
+            retry_budget: None,
+            cache: None,
+            rate_limiter: None,
+            layers: Layers::default(),
+            lenient_parsing: false,
+            clock: Arc::new(SystemClock),
+            rng: Arc::new(ThreadRng),
+            #[cfg(feature = "http3")]
+            http3: Arc::new(AtomicBool::new(false)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base_url_trailing_slash() {
+        let with_slash = Url::parse("http://localhost:8080/api/iseven/").unwrap();
+        let without_slash = Url::parse("http://localhost:8080/api/iseven").unwrap();
+        for url in [with_slash, without_slash] {
+            assert_eq!(
+                IsEvenApiClientBuilder::new()
+                    .base_url(url)
+                    .base_url_string(),
+                "http://localhost:8080/api/iseven/"
+            );
+        }
+        assert_eq!(IsEvenApiClientBuilder::new().base_url_string(), API_URL);
+    }
+
+    #[test]
+    fn test_api_key_redacted() {
+        let builder = IsEvenApiClientBuilder::new().api_key("hunter2");
+        assert!(!format!("{:?}", builder).contains("hunter2"));
+        assert!(!format!("{:?}", builder.into_config()).contains("hunter2"));
+    }
+
+    #[test]
+    fn test_lenient_parsing() {
+        let strict = IsEvenApiClientBuilder::new().into_config();
+        assert!(matches!(
+            strict.parse("42.0"),
+            Err(IsEvenApiError::InvalidInput { input, .. }) if input == "42.0"
+        ));
+        let lenient = IsEvenApiClientBuilder::new()
+            .lenient_parsing(true)
+            .into_config();
+        assert_eq!(lenient.parse("42.0").unwrap(), "42");
+        assert_eq!(lenient.parse(" 4.20e1").unwrap(), "42");
+        assert!(lenient.parse("4.2").is_err());
+    }
+}
@@ -0,0 +1,935 @@
+//! In-memory caching of API responses.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::{Debug, Formatter},
+    mem::size_of,
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use log::warn;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{store::CacheStore, Clock, IsEvenApiResponse, SystemClock};
+
+/// A thread-safe, in-memory cache of successful API responses, keyed by the number as it appears in the request URL.
+///
+/// Cloning a `ResponseCache` gives another handle to the same cache, so a single cache can be shared between several
+/// clients. When the cache is full, an entry is evicted according to its [`EvictionPolicy`], which by default evicts
+/// the least recently used entry.
+///
+/// Responses can also be kept in a [`CacheStore`] added with [`Self::with_store`], which outlives the process and is
+/// not limited by the capacity of the cache.
+///
+/// # Examples
+///
+#[cfg_attr(feature = "client", doc = "```")]
+#[cfg_attr(not(feature = "client"), doc = "```ignore")]
+/// # use std::error::Error;
+/// use std::time::Duration;
+/// use iseven_api::{EvictionPolicy, IsEvenApiClient, ResponseCache};
+///
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// let cache = ResponseCache::new(1000)
+///     .with_ttl(Duration::from_secs(3600))
+///     .with_eviction(EvictionPolicy::Lfu);
+/// let client = IsEvenApiClient::builder().cache(cache.clone()).build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ResponseCache {
+    inner: Arc<Mutex<CacheInner>>,
+    capacity: usize,
+    ttl: Option<Duration>,
+    max_memory: Option<usize>,
+    stale_while_revalidate: Option<Duration>,
+    store: Option<Store>,
+    clock: Arc<dyn Clock>,
+}
+
+/// [`CacheStore`] which is shown in [`Debug`] output without its contents.
+#[derive(Clone)]
+struct Store(Arc<dyn CacheStore>);
+
+impl Debug for Store {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("CacheStore")
+    }
+}
+
+/// Which entry a full [`ResponseCache`] evicts to make room for a new one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum EvictionPolicy {
+    /// Evicts the least recently used entry.
+    #[default]
+    Lru,
+    /// Evicts the least frequently used entry, or the least recently used of those used equally often. Suits
+    /// workloads where a few numbers are requested far more often than the rest.
+    Lfu,
+    /// Evicts the entry which was cached first.
+    Fifo,
+}
+
+/// Counters describing how a [`ResponseCache`] has been used, returned by [`ResponseCache::stats`].
+///
+/// Counts are shared by all handles to the cache, and are kept from when it was created.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CacheStats {
+    hits: u64,
+    misses: u64,
+    insertions: u64,
+    evictions: u64,
+    expirations: u64,
+    len: usize,
+    memory: usize,
+}
+
+impl CacheStats {
+    /// Returns the number of lookups which found a response, in memory or in the store.
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    /// Returns the number of lookups which found no unexpired response.
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+
+    /// Returns the fraction of lookups which found a response, or `None` if there have been none.
+    pub fn hit_ratio(&self) -> Option<f64> {
+        let lookups = self.hits + self.misses;
+        (lookups > 0).then(|| self.hits as f64 / lookups as f64)
+    }
+
+    /// Returns the number of responses added to memory, including those loaded from the store or with
+    /// [`ResponseCache::extend`].
+    pub fn insertions(&self) -> u64 {
+        self.insertions
+    }
+
+    /// Returns the number of responses evicted from memory to make room for others.
+    pub fn evictions(&self) -> u64 {
+        self.evictions
+    }
+
+    /// Returns the number of expired responses removed from memory.
+    pub fn expirations(&self) -> u64 {
+        self.expirations
+    }
+
+    /// Returns the number of responses in memory, as with [`ResponseCache::len`].
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if there were no responses in memory.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the approximate memory used by responses in memory in bytes, as with [`ResponseCache::memory`].
+    pub fn memory(&self) -> usize {
+        self.memory
+    }
+}
+
+#[derive(Debug, Default)]
+struct CacheInner {
+    entries: HashMap<String, CacheEntry>,
+    /// Keys of all entries by their [`Rank`], so that the next entry to evict is found without scanning them all
+    order: BTreeMap<Rank, String>,
+    eviction: EvictionPolicy,
+    /// Incremented on every access, used to find the least recently used entry
+    tick: u64,
+    /// Sum of the sizes of all entries, in bytes
+    memory: usize,
+    /// Counters, whose `len` and `memory` are filled in when they are returned
+    stats: CacheStats,
+    /// Keys of stale entries being refreshed in the background
+    refreshing: HashSet<String>,
+}
+
+/// Marks a stale entry as being refreshed until dropped, so that only one refresh runs at a time.
+#[derive(Debug)]
+pub(crate) struct RefreshGuard {
+    inner: Arc<Mutex<CacheInner>>,
+    key: String,
+}
+
+impl Drop for RefreshGuard {
+    fn drop(&mut self) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.refreshing.remove(&self.key);
+    }
+}
+
+/// How long ago an entry was cached, relative to the TTL.
+enum Freshness {
+    Fresh,
+    /// Expired, but within the stale-while-revalidate window
+    Stale,
+    Expired,
+}
+
+/// Position of an entry in the order of eviction, the entry with the lowest being evicted first.
+type Rank = (SystemTime, u64, u64);
+
+#[derive(Debug, Clone)]
+struct CacheEntry {
+    response: IsEvenApiResponse,
+    inserted: SystemTime,
+    /// Tick at which the entry was cached, ordering entries cached at the same time
+    added: u64,
+    last_used: u64,
+    /// Number of times the entry has been inserted or returned
+    uses: u64,
+    /// Approximate memory used by the entry and its key, in bytes
+    size: usize,
+}
+
+impl ResponseCache {
+    /// Creates an empty cache holding at most `capacity` responses, which never expire.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(CacheInner::default())),
+            capacity,
+            ttl: None,
+            max_memory: None,
+            stale_while_revalidate: None,
+            store: None,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Sets the time after which cached responses expire.
+    pub fn with_ttl(self, ttl: Duration) -> Self {
+        Self {
+            ttl: Some(ttl),
+            ..self
+        }
+    }
+
+    /// Sets which entry is evicted when the cache is full. The policy is shared by all handles to the cache.
+    pub fn with_eviction(self, eviction: EvictionPolicy) -> Self {
+        self.lock().set_eviction(eviction);
+        self
+    }
+
+    /// Limits the memory used by cached responses to about `bytes`, evicting entries as needed in addition to the
+    /// limit on their number. The size of each entry is estimated from the length of its number and ad along with a
+    /// fixed overhead.
+    pub fn with_max_memory(self, bytes: usize) -> Self {
+        Self {
+            max_memory: Some(bytes),
+            ..self
+        }
+    }
+
+    /// Lets clients serve responses for up to `window` after they expire, while refreshing them in the background.
+    /// This keeps requests for popular numbers fast when their responses expire, at the cost of answers being out of
+    /// date for a short time.
+    ///
+    /// Only clients use stale responses; [`Self::get`] still treats them as expired. Has no effect without a TTL.
+    pub fn with_stale_while_revalidate(self, window: Duration) -> Self {
+        Self {
+            stale_while_revalidate: Some(window),
+            ..self
+        }
+    }
+
+    /// Keeps responses in `store` as well as in memory. Responses are written through to the store as they are cached,
+    /// and responses which are not in memory are looked up in the store.
+    pub fn with_store<S: CacheStore + 'static>(self, store: S) -> Self {
+        Self {
+            store: Some(Store(Arc::new(store))),
+            ..self
+        }
+    }
+
+    /// Reads the time from `clock` instead of the [`SystemClock`], both for timestamping responses and for expiring
+    /// them.
+    pub fn with_clock<C: Clock + 'static>(self, clock: C) -> Self {
+        Self {
+            clock: Arc::new(clock),
+            ..self
+        }
+    }
+
+    /// Returns the maximum number of cached responses.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the time after which cached responses expire, if any.
+    pub fn ttl(&self) -> Option<Duration> {
+        self.ttl
+    }
+
+    /// Returns how long after expiring responses may be served while they are refreshed, if they may be.
+    pub fn stale_while_revalidate(&self) -> Option<Duration> {
+        self.stale_while_revalidate
+    }
+
+    /// Returns which entry is evicted when the cache is full.
+    pub fn eviction(&self) -> EvictionPolicy {
+        self.lock().eviction
+    }
+
+    /// Returns the approximate maximum memory used by cached responses in bytes, if limited.
+    pub fn max_memory(&self) -> Option<usize> {
+        self.max_memory
+    }
+
+    /// Returns the approximate memory used by cached responses in bytes, as counted towards [`Self::max_memory`].
+    pub fn memory(&self) -> usize {
+        self.lock().memory
+    }
+
+    /// Returns counters describing how the cache has been used, e.g. to check whether it is large enough.
+    pub fn stats(&self) -> CacheStats {
+        let inner = self.lock();
+        CacheStats {
+            len: inner.entries.len(),
+            memory: inner.memory,
+            ..inner.stats
+        }
+    }
+
+    /// Returns the cached response for `key`, if present and not expired.
+    pub fn get(&self, key: &str) -> Option<IsEvenApiResponse> {
+        self.lookup(key, false).map(|(response, _)| response)
+    }
+
+    /// Returns the cached response for `key`, which may be stale if `allow_stale` is set.
+    ///
+    /// A stale response comes with a guard if it should be refreshed, which marks it as being refreshed until dropped.
+    /// There is no guard if another refresh is already running.
+    pub(crate) fn lookup(
+        &self,
+        key: &str,
+        allow_stale: bool,
+    ) -> Option<(IsEvenApiResponse, Option<RefreshGuard>)> {
+        {
+            let mut inner = self.lock();
+            inner.tick += 1;
+            let tick = inner.tick;
+            match inner.entries.get(key).map(|entry| self.freshness(entry)) {
+                Some(Freshness::Fresh) => {
+                    return inner.hit(key, tick).map(|response| (response, None))
+                }
+                Some(Freshness::Stale) if allow_stale => {
+                    let response = inner.hit(key, tick)?;
+                    let guard = inner
+                        .refreshing
+                        .insert(key.to_string())
+                        .then(|| RefreshGuard {
+                            inner: self.inner.clone(),
+                            key: key.to_string(),
+                        });
+                    return Some((response, guard));
+                }
+                Some(Freshness::Expired) => {
+                    inner.remove(key);
+                    inner.stats.expirations += 1;
+                }
+                Some(Freshness::Stale) | None => {}
+            }
+        }
+        let response = self.get_stored(key);
+        let mut inner = self.lock();
+        match response {
+            Some(_) => inner.stats.hits += 1,
+            None => inner.stats.misses += 1,
+        }
+        response.map(|response| (response.cached(), None))
+    }
+
+    /// Returns the response for `key` from the store, if any, keeping it in memory for next time.
+    fn get_stored(&self, key: &str) -> Option<IsEvenApiResponse> {
+        let Store(store) = self.store.as_ref()?;
+        let entry = store.get(key).unwrap_or_else(|e| {
+            warn!("Unable to read {} from the cache store: {}", key, e);
+            None
+        })?;
+        if self.ttl.is_some_and(|ttl| self.age(entry.cached_at) >= ttl) {
+            if let Err(e) = store.remove(key) {
+                warn!("Unable to remove {} from the cache store: {}", key, e);
+            }
+            return None;
+        }
+        let response = entry.response.for_number(&entry.number);
+        self.insert_at(entry.number, response.clone(), entry.cached_at);
+        Some(response)
+    }
+
+    /// Caches `response` for `key`, evicting entries according to the [`EvictionPolicy`] if the cache is full.
+    pub fn insert(&self, key: String, response: IsEvenApiResponse) {
+        let cached_at = self.clock.system_time();
+        if let Some(Store(store)) = &self.store {
+            let entry = CachedResponse::new(key.clone(), response.clone(), cached_at);
+            if let Err(e) = store.put(&entry) {
+                warn!("Unable to write {} to the cache store: {}", key, e);
+            }
+        }
+        self.insert_at(key, response, cached_at);
+    }
+
+    /// Removes the cached response for `key`, including from the store, if any.
+    pub fn remove(&self, key: &str) {
+        self.lock().remove(key);
+        if let Some(Store(store)) = &self.store {
+            if let Err(e) = store.remove(key) {
+                warn!("Unable to remove {} from the cache store: {}", key, e);
+            }
+        }
+    }
+
+    /// Removes all cached responses, including those in the store.
+    pub fn clear(&self) {
+        {
+            let mut inner = self.lock();
+            inner.entries.clear();
+            inner.order.clear();
+            inner.memory = 0;
+        }
+        if let Some(Store(store)) = &self.store {
+            let removed = store.entries().and_then(|entries| {
+                entries
+                    .iter()
+                    .try_for_each(|entry| store.remove(entry.number()))
+            });
+            if let Err(e) = removed {
+                warn!("Unable to clear the cache store: {}", e);
+            }
+        }
+    }
+
+    /// Returns an iterator over a snapshot of all unexpired cached responses held in memory, oldest first.
+    pub fn iter(&self) -> std::vec::IntoIter<CachedResponse> {
+        self.entries().into_iter()
+    }
+
+    /// Returns a snapshot of all unexpired cached responses held in memory, e.g. for saving the cache to disk.
+    pub fn entries(&self) -> Vec<CachedResponse> {
+        let inner = self.lock();
+        let mut entries = inner
+            .entries
+            .iter()
+            .filter(|(_, entry)| !self.is_expired(entry))
+            .map(|(number, entry)| CachedResponse {
+                number: number.clone(),
+                response: entry.response.clone(),
+                cached_at: entry.inserted,
+            })
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|entry| entry.cached_at);
+        entries
+    }
+
+    /// Adds previously cached responses to memory, e.g. when loading the cache from disk. Entries keep their original
+    /// caching time, so they expire as if they had never left the cache. They are not written to the store.
+    pub fn extend<I: IntoIterator<Item = CachedResponse>>(&self, entries: I) {
+        for entry in entries {
+            let response = entry.response.for_number(&entry.number);
+            self.insert_at(entry.number, response, entry.cached_at);
+        }
+    }
+
+    /// Returns the number of cached responses in memory, including any which have expired but not yet been removed.
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    /// Returns `true` if the cache holds no responses.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn insert_at(&self, key: String, response: IsEvenApiResponse, inserted: SystemTime) {
+        if self.capacity == 0
+            || self.ttl.is_some_and(|ttl| {
+                let window = self.stale_while_revalidate.unwrap_or_default();
+                self.age(inserted) >= ttl + window
+            })
+        {
+            return;
+        }
+        let size = entry_size(&key, &response);
+        if self.max_memory.is_some_and(|max| size > max) {
+            return;
+        }
+        let mut inner = self.lock();
+        inner.tick += 1;
+        let tick = inner.tick;
+        let uses = inner.remove(&key).map_or(0, |entry| entry.uses);
+        while inner.entries.len() >= self.capacity
+            || self.max_memory.is_some_and(|max| inner.memory + size > max)
+        {
+            let Some((_, victim)) = inner.order.pop_first() else {
+                break;
+            };
+            inner.remove(&victim);
+            inner.stats.evictions += 1;
+        }
+        inner.stats.insertions += 1;
+        inner.insert(
+            key,
+            CacheEntry {
+                response,
+                inserted,
+                added: tick,
+                last_used: tick,
+                uses: uses + 1,
+                size,
+            },
+        );
+    }
+
+    fn freshness(&self, entry: &CacheEntry) -> Freshness {
+        let Some(ttl) = self.ttl else {
+            return Freshness::Fresh;
+        };
+        let age = self.age(entry.inserted);
+        if age < ttl {
+            Freshness::Fresh
+        } else if age < ttl + self.stale_while_revalidate.unwrap_or_default() {
+            Freshness::Stale
+        } else {
+            Freshness::Expired
+        }
+    }
+
+    fn is_expired(&self, entry: &CacheEntry) -> bool {
+        self.ttl.is_some_and(|ttl| self.age(entry.inserted) >= ttl)
+    }
+
+    /// Returns the time since `time` according to the clock, or zero if it is in the future.
+    fn age(&self, time: SystemTime) -> Duration {
+        self.clock
+            .system_time()
+            .duration_since(time)
+            .unwrap_or_default()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, CacheInner> {
+        // no operation panics while the entries and their eviction order disagree, so a poisoned cache is still usable
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl CacheInner {
+    /// Records a use of the entry for `key` at `tick` and returns its response.
+    fn hit(&mut self, key: &str, tick: u64) -> Option<IsEvenApiResponse> {
+        let entry = self.entries.get_mut(key)?;
+        let key = self
+            .order
+            .remove(&self.eviction.rank(entry))
+            .unwrap_or_else(|| key.to_string());
+        entry.last_used = tick;
+        entry.uses += 1;
+        let response = entry.response.clone().cached();
+        self.order.insert(self.eviction.rank(entry), key);
+        self.stats.hits += 1;
+        Some(response)
+    }
+
+    fn insert(&mut self, key: String, entry: CacheEntry) {
+        self.memory += entry.size;
+        self.order.insert(self.eviction.rank(&entry), key.clone());
+        self.entries.insert(key, entry);
+    }
+
+    fn remove(&mut self, key: &str) -> Option<CacheEntry> {
+        let entry = self.entries.remove(key)?;
+        self.order.remove(&self.eviction.rank(&entry));
+        self.memory -= entry.size;
+        Some(entry)
+    }
+
+    /// Changes the eviction policy, reordering the entries for it.
+    fn set_eviction(&mut self, eviction: EvictionPolicy) {
+        self.eviction = eviction;
+        self.order = self
+            .entries
+            .iter()
+            .map(|(key, entry)| (eviction.rank(entry), key.clone()))
+            .collect();
+    }
+}
+
+impl EvictionPolicy {
+    /// Returns the rank of `entry` under this policy. Ranks are unique, as every entry is cached and used at a
+    /// different tick.
+    fn rank(self, entry: &CacheEntry) -> Rank {
+        match self {
+            EvictionPolicy::Lru => (UNIX_EPOCH, entry.last_used, 0),
+            EvictionPolicy::Lfu => (UNIX_EPOCH, entry.uses, entry.last_used),
+            EvictionPolicy::Fifo => (entry.inserted, entry.added, 0),
+        }
+    }
+}
+
+/// Returns the approximate memory used by an entry for `response` to `key`, in bytes.
+fn entry_size(key: &str, response: &IsEvenApiResponse) -> usize {
+    size_of::<(String, CacheEntry)>() + key.len() + response.ad().len()
+}
+
+/// A response stored in a [`ResponseCache`], along with the number it was requested for and when it was cached.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CachedResponse {
+    number: String,
+    #[serde(flatten)]
+    response: IsEvenApiResponse,
+    /// Serialized as seconds since the Unix epoch
+    #[serde(
+        serialize_with = "serialize_unix_secs",
+        deserialize_with = "deserialize_unix_secs"
+    )]
+    cached_at: SystemTime,
+}
+
+impl CachedResponse {
+    /// Creates an entry for `response` to `number`, cached at `cached_at`.
+    pub fn new(number: String, response: IsEvenApiResponse, cached_at: SystemTime) -> Self {
+        Self {
+            number,
+            response,
+            cached_at,
+        }
+    }
+
+    /// Returns the number, as it appears in the request URL.
+    pub fn number(&self) -> &str {
+        &self.number
+    }
+
+    /// Returns the cached response.
+    pub fn response(&self) -> &IsEvenApiResponse {
+        &self.response
+    }
+
+    /// Returns when the response was cached.
+    pub fn cached_at(&self) -> SystemTime {
+        self.cached_at
+    }
+}
+
+fn serialize_unix_secs<S: Serializer>(time: &SystemTime, serializer: S) -> Result<S::Ok, S::Error> {
+    let secs = time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    serializer.serialize_u64(secs)
+}
+
+fn deserialize_unix_secs<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<SystemTime, D::Error> {
+    let secs = u64::deserialize(deserializer)?;
+    Ok(UNIX_EPOCH + Duration::from_secs(secs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ApiNumber;
+
+    fn response(iseven: bool) -> IsEvenApiResponse {
+        IsEvenApiResponse {
+            number: None,
+            ad: "ad".to_string(),
+            iseven,
+            metadata: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_lru_eviction() {
+        let cache = ResponseCache::new(2);
+        cache.insert("1".to_string(), response(false));
+        cache.insert("2".to_string(), response(true));
+        assert!(cache.get("1").unwrap().metadata().from_cache());
+        cache.insert("3".to_string(), response(false));
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get("1").is_some());
+        assert!(cache.get("2").is_none());
+        assert!(cache.get("3").is_some());
+    }
+
+    #[test]
+    fn test_lfu_eviction() {
+        let cache = ResponseCache::new(2).with_eviction(EvictionPolicy::Lfu);
+        cache.insert("1".to_string(), response(false));
+        cache.insert("2".to_string(), response(true));
+        for _ in 0..3 {
+            assert!(cache.get("1").is_some());
+        }
+        assert!(cache.get("2").is_some());
+        cache.insert("3".to_string(), response(false));
+        assert!(cache.get("2").is_none());
+        cache.insert("4".to_string(), response(true));
+        assert!(cache.get("1").is_some());
+        assert!(cache.get("3").is_none());
+    }
+
+    #[test]
+    fn test_fifo_eviction() {
+        let cache = ResponseCache::new(2).with_eviction(EvictionPolicy::Fifo);
+        cache.insert("1".to_string(), response(false));
+        cache.insert("2".to_string(), response(true));
+        assert!(cache.get("1").is_some());
+        cache.insert("3".to_string(), response(false));
+        assert!(cache.get("1").is_none());
+        assert!(cache.get("2").is_some());
+    }
+
+    #[test]
+    fn test_change_eviction() {
+        let cache = ResponseCache::new(2);
+        cache.insert("1".to_string(), response(false));
+        cache.insert("2".to_string(), response(true));
+        assert!(cache.get("1").is_some());
+        // the entries already cached are reordered for the new policy
+        let cache = cache.with_eviction(EvictionPolicy::Fifo);
+        cache.insert("3".to_string(), response(false));
+        assert!(cache.get("1").is_none());
+        assert!(cache.get("2").is_some());
+
+        cache.remove("2");
+        cache.insert("4".to_string(), response(true));
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.lock().order.len(), 2);
+        cache.clear();
+        assert!(cache.lock().order.is_empty());
+    }
+
+    #[test]
+    fn test_max_memory() {
+        let size = entry_size("1", &response(false));
+        let cache = ResponseCache::new(10).with_max_memory(size * 2);
+        for key in ["1", "2", "3"] {
+            cache.insert(key.to_string(), response(false));
+        }
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.memory(), size * 2);
+        assert!(cache.get("1").is_none());
+        cache.clear();
+        assert_eq!(cache.memory(), 0);
+
+        let tiny = ResponseCache::new(10).with_max_memory(size - 1);
+        tiny.insert("1".to_string(), response(false));
+        assert!(tiny.is_empty());
+    }
+
+    /// Store keeping entries in memory, standing in for a database.
+    #[derive(Default)]
+    struct MapStore(Mutex<HashMap<String, CachedResponse>>);
+
+    impl CacheStore for MapStore {
+        fn get(&self, number: &str) -> std::io::Result<Option<CachedResponse>> {
+            Ok(self.0.lock().unwrap().get(number).cloned())
+        }
+
+        fn put(&self, entry: &CachedResponse) -> std::io::Result<()> {
+            let mut map = self.0.lock().unwrap();
+            map.insert(entry.number().to_string(), entry.clone());
+            Ok(())
+        }
+
+        fn remove(&self, number: &str) -> std::io::Result<()> {
+            self.0.lock().unwrap().remove(number);
+            Ok(())
+        }
+
+        fn entries(&self) -> std::io::Result<Vec<CachedResponse>> {
+            Ok(self.0.lock().unwrap().values().cloned().collect())
+        }
+    }
+
+    #[test]
+    fn test_store() {
+        let store = Arc::new(MapStore::default());
+        let cache = ResponseCache::new(1)
+            .with_ttl(Duration::from_secs(60))
+            .with_store(store.clone());
+        cache.insert("1".to_string(), response(false));
+        cache.insert("2".to_string(), response(true));
+        assert_eq!(cache.len(), 1);
+        assert_eq!(store.entries().unwrap().len(), 2);
+        // evicted from memory, but still in the store
+        assert!(cache.get("1").unwrap().isodd());
+
+        let expired = CachedResponse::new(
+            "3".to_string(),
+            response(false),
+            SystemTime::now() - Duration::from_secs(120),
+        );
+        store.put(&expired).unwrap();
+        assert!(cache.get("3").is_none());
+        assert!(store.get("3").unwrap().is_none());
+
+        cache.clear();
+        assert!(cache.is_empty());
+        assert!(store.entries().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_stats() {
+        let cache = ResponseCache::new(2).with_ttl(Duration::from_secs(60));
+        assert_eq!(cache.stats().hit_ratio(), None);
+        cache.insert("1".to_string(), response(false));
+        cache.insert("2".to_string(), response(true));
+        cache.insert("3".to_string(), response(false));
+        cache.insert_at(
+            "4".to_string(),
+            response(true),
+            SystemTime::now() - Duration::from_secs(30),
+        );
+        assert!(cache.get("3").is_some());
+        assert!(cache.get("1").is_none());
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits(), stats.misses()), (1, 1));
+        assert_eq!(stats.hit_ratio(), Some(0.5));
+        assert_eq!((stats.insertions(), stats.evictions()), (4, 2));
+        assert_eq!((stats.len(), stats.memory()), (2, cache.memory()));
+        let numbers: Vec<_> = cache
+            .iter()
+            .map(|entry| entry.number().to_string())
+            .collect();
+        assert_eq!(numbers, ["4", "3"]);
+    }
+
+    #[test]
+    fn test_ttl() {
+        let cache = ResponseCache::new(10).with_ttl(Duration::from_secs(60));
+        cache.insert_at(
+            "1".to_string(),
+            response(false),
+            SystemTime::now() - Duration::from_secs(120),
+        );
+        cache.insert("2".to_string(), response(true));
+        assert!(cache.get("1").is_none());
+        assert!(cache.get("2").is_some());
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[cfg(feature = "test-util")]
+    #[test]
+    fn test_ttl_with_clock() {
+        use crate::test_util::MockClock;
+
+        let clock = MockClock::new();
+        let cache = ResponseCache::new(10)
+            .with_ttl(Duration::from_secs(60))
+            .with_clock(clock.clone());
+        cache.insert("1".to_string(), response(false));
+        assert_eq!(cache.entries()[0].cached_at(), clock.system_time());
+        clock.advance(Duration::from_secs(59));
+        assert!(cache.get("1").is_some());
+        clock.advance(Duration::from_secs(1));
+        assert!(cache.get("1").is_none());
+        assert_eq!(cache.stats().expirations(), 1);
+    }
+
+    #[cfg(feature = "test-util")]
+    #[test]
+    fn test_insert_with_clock_in_past() {
+        use crate::test_util::MockClock;
+
+        let clock = MockClock::at(UNIX_EPOCH + Duration::from_secs(1_000_000));
+        let cache = ResponseCache::new(10)
+            .with_ttl(Duration::from_secs(60))
+            .with_clock(clock.clone());
+        cache.insert("1".to_string(), response(false));
+        assert!(cache.get("1").unwrap().isodd());
+        cache.extend([CachedResponse::new(
+            "2".to_string(),
+            response(true),
+            clock.system_time() - Duration::from_secs(30),
+        )]);
+        assert!(cache.get("2").unwrap().iseven());
+        clock.advance(Duration::from_secs(60));
+        assert!(cache.get("1").is_none());
+        assert!(cache.get("2").is_none());
+    }
+
+    #[test]
+    fn test_stale_while_revalidate() {
+        let cache = ResponseCache::new(10)
+            .with_ttl(Duration::from_secs(60))
+            .with_stale_while_revalidate(Duration::from_secs(60));
+        let cached_at = SystemTime::now() - Duration::from_secs(90);
+        cache.insert_at("1".to_string(), response(false), cached_at);
+        assert!(cache.get("1").is_none());
+
+        let Some((_, Some(guard))) = cache.lookup("1", true) else {
+            panic!("expected a stale response with a refresh guard");
+        };
+        assert!(matches!(cache.lookup("1", true), Some((_, None))));
+        drop(guard);
+        assert!(matches!(cache.lookup("1", true), Some((_, Some(_)))));
+
+        cache.insert_at(
+            "2".to_string(),
+            response(false),
+            SystemTime::now() - Duration::from_secs(150),
+        );
+        assert!(cache.lookup("2", true).is_none());
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[cfg(feature = "mock-server")]
+    #[tokio::test]
+    async fn test_client_refreshes_stale_response() {
+        use crate::{mock::MockServer, IsEvenApiClient, Plan};
+
+        let server = MockServer::start("127.0.0.1:0", Plan::Free).await.unwrap();
+        let cache = ResponseCache::new(10)
+            .with_ttl(Duration::from_secs(60))
+            .with_stale_while_revalidate(Duration::from_secs(60));
+        cache.insert_at(
+            "42".to_string(),
+            response(true),
+            SystemTime::now() - Duration::from_secs(90),
+        );
+        let client = IsEvenApiClient::builder()
+            .base_url(server.url())
+            .cache(cache.clone())
+            .build()
+            .unwrap();
+
+        let stale = client.get(42).await.unwrap();
+        assert_eq!(stale.metadata().request_id(), None);
+        for _ in 0..100 {
+            if cache.get("42").is_some() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let fresh = client.get(42).await.unwrap();
+        assert!(fresh.metadata().request_id().is_some());
+    }
+
+    #[test]
+    fn test_entries_roundtrip() {
+        let cache = ResponseCache::new(10).with_ttl(Duration::from_secs(60));
+        cache.insert("1".to_string(), response(false));
+        cache.insert("2".to_string(), response(true));
+        let entries = cache.entries();
+        assert_eq!(entries.len(), 2);
+
+        let restored = ResponseCache::new(10).with_ttl(Duration::from_secs(60));
+        let expired = CachedResponse::new(
+            "3".to_string(),
+            response(false),
+            SystemTime::now() - Duration::from_secs(120),
+        );
+        restored.extend(entries.into_iter().chain([expired]));
+        assert_eq!(restored.len(), 2);
+        assert!(restored.get("2").unwrap().iseven());
+        assert_eq!(
+            restored.get("2").unwrap().number().map(ApiNumber::as_str),
+            Some("2")
+        );
+    }
+}
@@ -0,0 +1,52 @@
+//! Tallying of advertisements (`--ads-only --unique`).
+
+use std::io::{self, Write};
+
+/// Counts of each distinct advertisement, in the order they were first seen.
+#[derive(Debug, Default)]
+pub struct AdTally {
+    counts: Vec<(String, usize)>,
+}
+
+impl AdTally {
+    /// Counts an occurrence of `ad`.
+    pub fn record(&mut self, ad: &str) {
+        match self.counts.iter_mut().find(|(seen, _)| seen == ad) {
+            Some((_, count)) => *count += 1,
+            None => self.counts.push((ad.to_string(), 1)),
+        }
+    }
+
+    /// Writes each advertisement with its count, like `uniq -c`, most frequent first.
+    pub fn write(mut self, out: &mut impl Write) -> io::Result<()> {
+        // stable, so ties keep the order in which they were first seen
+        self.counts
+            .sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+        let width = self
+            .counts
+            .first()
+            .map_or(0, |(_, count)| count.to_string().len());
+        for (ad, count) in &self.counts {
+            writeln!(out, "{:>width$} {}", count, ad, width = width)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tally() {
+        let mut tally = AdTally::default();
+        for ad in [
+            "a", "b", "c", "b", "c", "c", "b", "b", "b", "b", "b", "b", "b", "b",
+        ] {
+            tally.record(ad);
+        }
+        let mut out = Vec::new();
+        tally.write(&mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "10 b\n 3 c\n 1 a\n");
+    }
+}
@@ -0,0 +1,186 @@
+//! Upstream latency benchmark (`bench` subcommand).
+
+use std::{
+    error::Error,
+    fmt::{Display, Formatter},
+    num::NonZeroUsize,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use clap::Args;
+use iseven_api::{IsEvenApiClient, IsEvenApiClientBuilder};
+use serde::Serialize;
+
+/// Arguments for the `bench` subcommand.
+#[derive(Debug, Args)]
+pub struct BenchArgs {
+    /// Total number of requests to make
+    #[arg(long, default_value_t = 100)]
+    requests: usize,
+
+    /// Number of requests in flight at once
+    #[arg(long, default_value_t = NonZeroUsize::new(8).unwrap())]
+    concurrency: NonZeroUsize,
+
+    /// Number to check in every request
+    #[arg(long, default_value = "42")]
+    number: String,
+
+    /// Print the report as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+impl BenchArgs {
+    /// Builds a client from `builder`, runs the benchmark and prints the report.
+    pub async fn run(&self, builder: IsEvenApiClientBuilder) -> Result<(), Box<dyn Error>> {
+        let client = builder.build()?;
+        let report = self.bench(client).await?;
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        } else {
+            println!("{}", report);
+        }
+        Ok(())
+    }
+
+    async fn bench(&self, client: IsEvenApiClient) -> Result<Report, tokio::task::JoinError> {
+        let remaining = Arc::new(AtomicUsize::new(self.requests));
+        let started = Instant::now();
+        let workers = (0..self.concurrency.get())
+            .map(|_| {
+                let client = client.clone();
+                let remaining = remaining.clone();
+                let number = self.number.clone();
+                tokio::spawn(async move {
+                    let mut samples = Vec::new();
+                    // each worker claims requests until none are left
+                    while remaining
+                        .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1))
+                        .is_ok()
+                    {
+                        let start = Instant::now();
+                        let ok = client.get_str(&number).await.is_ok();
+                        samples.push((start.elapsed(), ok));
+                    }
+                    samples
+                })
+            })
+            .collect::<Vec<_>>();
+        let mut samples = Vec::with_capacity(self.requests);
+        for worker in workers {
+            samples.extend(worker.await?);
+        }
+        Ok(Report::new(
+            self.concurrency.get(),
+            &samples,
+            started.elapsed(),
+        ))
+    }
+}
+
+/// Results of a benchmark run.
+#[derive(Debug, Serialize)]
+struct Report {
+    requests: usize,
+    concurrency: usize,
+    errors: usize,
+    error_rate: f64,
+    /// Wall clock time of the whole run, in milliseconds
+    total_ms: f64,
+    /// Requests per second
+    throughput: f64,
+    latency_ms: Option<Latency>,
+}
+
+/// Latency distribution, in milliseconds.
+#[derive(Debug, Serialize)]
+struct Latency {
+    min: f64,
+    p50: f64,
+    p95: f64,
+    max: f64,
+}
+
+impl Report {
+    /// Summarises `samples` of request latency and success, collected over `elapsed`.
+    fn new(concurrency: usize, samples: &[(Duration, bool)], elapsed: Duration) -> Self {
+        let requests = samples.len();
+        let errors = samples.iter().filter(|(_, ok)| !ok).count();
+        let mut latencies = samples.iter().map(|(l, _)| *l).collect::<Vec<_>>();
+        latencies.sort();
+        let latency_ms = (!latencies.is_empty()).then(|| Latency {
+            min: millis(latencies[0]),
+            p50: millis(percentile(&latencies, 50)),
+            p95: millis(percentile(&latencies, 95)),
+            max: millis(latencies[latencies.len() - 1]),
+        });
+        Self {
+            requests,
+            concurrency,
+            errors,
+            error_rate: if requests == 0 {
+                0.0
+            } else {
+                errors as f64 / requests as f64
+            },
+            total_ms: millis(elapsed),
+            throughput: requests as f64 / elapsed.as_secs_f64().max(f64::EPSILON),
+            latency_ms,
+        }
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Requests: {}", self.requests)?;
+        writeln!(f, "Concurrency: {}", self.concurrency)?;
+        writeln!(
+            f,
+            "Errors: {} ({:.1}%)",
+            self.errors,
+            self.error_rate * 100.0
+        )?;
+        writeln!(f, "Total time: {:.0}ms", self.total_ms)?;
+        write!(f, "Throughput: {:.1} requests/s", self.throughput)?;
+        if let Some(latency) = &self.latency_ms {
+            write!(
+                f,
+                "\nLatency: min {:.1}ms, p50 {:.1}ms, p95 {:.1}ms, max {:.1}ms",
+                latency.min, latency.p50, latency.p95, latency.max
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the `p`th percentile of `sorted`, which must be non-empty, using the nearest-rank method.
+fn percentile(sorted: &[Duration], p: usize) -> Duration {
+    let rank = (p * sorted.len()).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentile() {
+        let values = (1..=20).map(Duration::from_millis).collect::<Vec<_>>();
+        assert_eq!(percentile(&values, 50), Duration::from_millis(10));
+        assert_eq!(percentile(&values, 95), Duration::from_millis(19));
+        assert_eq!(percentile(&values, 100), Duration::from_millis(20));
+        assert_eq!(
+            percentile(&[Duration::from_millis(7)], 50),
+            Duration::from_millis(7)
+        );
+    }
+}
@@ -0,0 +1,144 @@
+//! Persistent response cache (`--cache` and the `cache` subcommand).
+//!
+//! The cache is stored as a JSON array of cached responses, by default in `cache.json` in the `iseven_api` directory
+//! under the user's cache directory (e.g. `~/.cache/iseven_api/cache.json` on Linux).
+
+use std::{
+    error::Error,
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use clap::{Args, Subcommand};
+use iseven_api::{CachedResponse, ResponseCache};
+
+/// Maximum number of responses kept in the persistent cache.
+pub const CAPACITY: usize = 100_000;
+
+/// Returns the default location of the persistent cache, if the cache directory can be determined.
+pub fn default_path() -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join("iseven_api").join("cache.json"))
+}
+
+/// Creates an empty cache with the persistent cache's capacity and the given TTL.
+pub fn new_cache(ttl: Option<Duration>) -> ResponseCache {
+    let cache = ResponseCache::new(CAPACITY);
+    match ttl {
+        Some(ttl) => cache.with_ttl(ttl),
+        None => cache,
+    }
+}
+
+/// Reads cached responses from `path`. A missing file is treated as an empty cache.
+pub fn read_entries(path: &Path) -> io::Result<Vec<CachedResponse>> {
+    match File::open(path) {
+        Ok(file) => Ok(serde_json::from_reader(BufReader::new(file))?),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Loads the cached responses in `path` into `cache`.
+pub fn load(path: &Path, cache: &ResponseCache) -> io::Result<()> {
+    cache.extend(read_entries(path)?);
+    Ok(())
+}
+
+/// Writes the contents of `cache` to `path`, creating parent directories as needed. The file is replaced atomically,
+/// so concurrent readers never see a partially written cache.
+pub fn save(path: &Path, cache: &ResponseCache) -> io::Result<()> {
+    write_entries(path, &cache.entries())
+}
+
+fn write_entries(path: &Path, entries: &[CachedResponse]) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let tmp_path = path.with_extension("json.tmp");
+    let mut writer = BufWriter::new(File::create(&tmp_path)?);
+    serde_json::to_writer(&mut writer, entries)?;
+    writer.flush()?;
+    fs::rename(tmp_path, path)
+}
+
+/// Arguments for the `cache` subcommand.
+#[derive(Debug, Args)]
+pub struct CacheArgs {
+    #[command(subcommand)]
+    command: CacheCommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum CacheCommand {
+    /// Print statistics about the cached responses
+    Stats,
+    /// Remove all cached responses
+    Clear,
+    /// Write the cached responses as JSON to a file, or to stdout if no file is given
+    Export {
+        /// File to write to
+        file: Option<PathBuf>,
+    },
+    /// Add the cached responses in a file previously written by `export`
+    Import {
+        /// File to read from
+        file: PathBuf,
+    },
+    /// Print the location of the cache file
+    Path,
+}
+
+impl CacheArgs {
+    /// Runs the subcommand on the cache at `path`, whose responses expire after `ttl`.
+    pub fn run(&self, path: &Path, ttl: Option<Duration>) -> Result<(), Box<dyn Error>> {
+        match &self.command {
+            CacheCommand::Path => println!("{}", path.display()),
+            CacheCommand::Clear => match fs::remove_file(path) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            },
+            CacheCommand::Stats => {
+                let cache = new_cache(ttl);
+                let stored = read_entries(path)?;
+                let stored_len = stored.len();
+                cache.extend(stored);
+                let entries = cache.entries();
+                let even = entries.iter().filter(|e| e.response().iseven()).count();
+                println!("Path: {}", path.display());
+                println!("Entries: {}", entries.len());
+                println!("Expired: {}", stored_len - entries.len());
+                println!("Even: {}", even);
+                println!("Odd: {}", entries.len() - even);
+                if let (Some(oldest), Some(newest)) = (entries.first(), entries.last()) {
+                    println!("Oldest: {}", format_time(oldest.cached_at()));
+                    println!("Newest: {}", format_time(newest.cached_at()));
+                }
+            }
+            CacheCommand::Export { file } => {
+                let cache = new_cache(ttl);
+                load(path, &cache)?;
+                let json = serde_json::to_string_pretty(&cache.entries())?;
+                match file {
+                    Some(file) => fs::write(file, json + "\n")?,
+                    None => println!("{}", json),
+                }
+            }
+            CacheCommand::Import { file } => {
+                let cache = new_cache(ttl);
+                load(path, &cache)?;
+                let imported = read_entries(file)?;
+                let count = imported.len();
+                cache.extend(imported);
+                save(path, &cache)?;
+                eprintln!("Imported {} cached responses", count);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn format_time(time: SystemTime) -> String {
+    humantime::format_rfc3339_seconds(time).to_string()
+}
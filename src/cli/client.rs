@@ -0,0 +1,65 @@
+//! The client used for checking numbers, which is async unless `--blocking` is given, or talks to a daemon with
+//! `--via-daemon`.
+
+use iseven_api::{
+    IsEvenApiBlockingClient, IsEvenApiClient, IsEvenApiClientBuilder, IsEvenApiError,
+    IsEvenApiResponse,
+};
+use tokio::task::block_in_place;
+
+#[cfg(unix)]
+use super::daemon::DaemonClient;
+
+/// Either of the library's clients, with an async interface.
+///
+/// The blocking client is kept as an escape hatch for debugging, e.g. to rule out problems with the async runtime.
+/// Its requests run with [`block_in_place`], so the runtime must be multi-threaded.
+#[derive(Debug)]
+pub enum Client {
+    Async(IsEvenApiClient),
+    Blocking(IsEvenApiBlockingClient),
+    /// Requests are answered by a running `daemon`, using its connection and cache
+    #[cfg(unix)]
+    Daemon(DaemonClient),
+}
+
+impl Client {
+    /// Builds a client from `builder`, blocking if `blocking` is set.
+    pub fn build(builder: IsEvenApiClientBuilder, blocking: bool) -> Result<Self, IsEvenApiError> {
+        if blocking {
+            block_in_place(|| builder.build_blocking()).map(Client::Blocking)
+        } else {
+            builder.build().map(Client::Async)
+        }
+    }
+
+    /// Checks whether `number` is even.
+    pub async fn get(&self, number: &str) -> Result<IsEvenApiResponse, IsEvenApiError> {
+        match self {
+            Client::Async(client) => client.get_str(number).await,
+            Client::Blocking(client) => block_in_place(|| client.get_str(number)),
+            #[cfg(unix)]
+            Client::Daemon(client) => client.get(number).await,
+        }
+    }
+
+    /// Returns the raw JSON response for `number`.
+    pub async fn get_json(&self, number: &str) -> Result<String, IsEvenApiError> {
+        match self {
+            Client::Async(client) => client.get_json_str(number).await,
+            Client::Blocking(client) => block_in_place(|| client.get_json_str(number)),
+            #[cfg(unix)]
+            Client::Daemon(client) => client.get_json(number).await,
+        }
+    }
+
+    /// Returns the URL requested for `number`.
+    pub fn request_url(&self, number: &str) -> String {
+        match self {
+            Client::Async(client) => client.request_url_str(number),
+            Client::Blocking(client) => client.request_url_str(number),
+            #[cfg(unix)]
+            Client::Daemon(client) => client.request_url(number),
+        }
+    }
+}
@@ -0,0 +1,70 @@
+//! Reading numbers from the system clipboard (`--clipboard`).
+//!
+//! The clipboard is read with the platform's own command line tools: `pbpaste` on macOS, PowerShell on Windows, and
+//! whichever of `wl-paste`, `xclip` or `xsel` is installed elsewhere.
+//!
+//! These are used rather than a clipboard library such as arboard, which on Linux would add an X11 client, and a
+//! Wayland one to support Wayland, to the app's dependencies for a flag which only reads text once. The tools are
+//! listed in the help of `--clipboard`, and [`ClipboardError::NoTool`] names them when none is installed.
+
+use std::{io, process::Command};
+
+/// Commands which print the clipboard contents to stdout, in order of preference.
+#[cfg(target_os = "macos")]
+const COMMANDS: &[(&str, &[&str])] = &[("pbpaste", &[])];
+#[cfg(windows)]
+const COMMANDS: &[(&str, &[&str])] =
+    &[("powershell", &["-NoProfile", "-Command", "Get-Clipboard"])];
+#[cfg(not(any(target_os = "macos", windows)))]
+const COMMANDS: &[(&str, &[&str])] = &[
+    ("wl-paste", &["--no-newline"]),
+    ("xclip", &["-selection", "clipboard", "-out"]),
+    ("xsel", &["--clipboard", "--output"]),
+];
+
+/// Errors from reading the clipboard.
+#[derive(thiserror::Error, Debug)]
+pub enum ClipboardError {
+    #[error("no clipboard tool found, install one of: {}", tool_names())]
+    NoTool,
+    #[error("{command} failed: {message}")]
+    Failed {
+        command: &'static str,
+        message: String,
+    },
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+fn tool_names() -> String {
+    COMMANDS
+        .iter()
+        .map(|(command, _)| *command)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Returns the text on the clipboard. Each tool is tried in turn, so that e.g. `xclip` is used if `wl-paste` is
+/// installed but not running under Wayland.
+pub fn read() -> Result<String, ClipboardError> {
+    let mut error = ClipboardError::NoTool;
+    for &(command, args) in COMMANDS {
+        let output = match Command::new(command).args(args).output() {
+            Ok(output) => output,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e.into()),
+        };
+        if output.status.success() {
+            return Ok(String::from_utf8_lossy(&output.stdout).into_owned());
+        }
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        error = ClipboardError::Failed {
+            command,
+            message: match stderr.trim() {
+                "" => output.status.to_string(),
+                message => message.to_string(),
+            },
+        };
+    }
+    Err(error)
+}
@@ -0,0 +1,79 @@
+//! Terminal colors (`--color`), honoring the [`NO_COLOR`](https://no-color.org/) convention.
+
+use std::{env, fmt::Display, io::IsTerminal};
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+const RED: &str = "\x1b[31m";
+const RESET: &str = "\x1b[0m";
+
+/// When to use colored output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorChoice {
+    /// Use colors if the output is a terminal and `NO_COLOR` is not set
+    #[default]
+    Auto,
+    /// Always use colors
+    Always,
+    /// Never use colors
+    Never,
+}
+
+impl ColorChoice {
+    /// Resolves the choice into a [`Colors`] palette for the given output stream.
+    pub fn for_stream<S: IsTerminal>(self, stream: &S) -> Colors {
+        let enabled = match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                env::var_os("NO_COLOR").is_none_or(|v| v.is_empty()) && stream.is_terminal()
+            }
+        };
+        Colors { enabled }
+    }
+}
+
+/// Palette used to colorize output; all methods return the input unchanged when colors are disabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Colors {
+    enabled: bool,
+}
+
+impl Colors {
+    /// Colors "even" green and "odd" yellow.
+    pub fn parity(&self, iseven: bool) -> String {
+        self.parity_word(iseven, if iseven { "even" } else { "odd" })
+    }
+
+    /// Colors a word for the given parity, e.g. a translation of "even" or "odd", like [`Self::parity`].
+    pub fn parity_word(&self, iseven: bool, word: &str) -> String {
+        self.paint(if iseven { GREEN } else { YELLOW }, word)
+    }
+
+    /// Colors a success message green.
+    pub fn success<T: Display>(&self, msg: T) -> String {
+        self.paint(GREEN, msg)
+    }
+
+    /// Colors a warning yellow.
+    pub fn warning<T: Display>(&self, msg: T) -> String {
+        self.paint(YELLOW, msg)
+    }
+
+    /// Colors an error message red.
+    pub fn error<T: Display>(&self, msg: T) -> String {
+        self.paint(RED, msg)
+    }
+
+    fn paint<T: Display>(&self, color: &str, text: T) -> String {
+        if self.enabled {
+            format!("{}{}{}", color, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+}
@@ -0,0 +1,322 @@
+//! Configuration file support.
+//!
+//! Defaults are read from `config.toml` in the `iseven_api` directory under the user's configuration directory (e.g.
+//! `~/.config/iseven_api/config.toml` on Linux). Values given on the command line take precedence over the file.
+//!
+//! The file may also contain named profiles in `[profiles.<name>]` tables, selected with `--profile`. A profile named
+//! `default` is used when no profile is selected. Values in the selected profile take precedence over the top-level
+//! values.
+
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use iseven_api::{Plan, Url};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::{color::ColorChoice, exit_codes::ExitCodes, output::Format};
+
+/// Settings which can be given in the configuration file, on the command line, or both.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    /// Base URL of the API
+    #[serde(
+        serialize_with = "serialize_url",
+        deserialize_with = "deserialize_url",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub base_url: Option<Url>,
+    /// API key for paid plans
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+    /// Timeout for each request
+    #[serde(
+        serialize_with = "serialize_duration",
+        deserialize_with = "deserialize_duration",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub timeout: Option<Duration>,
+    /// Unix domain socket to connect to the API through, instead of over TCP
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unix_socket: Option<PathBuf>,
+    /// Number of numbers checked at a time
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jobs: Option<NonZeroUsize>,
+    /// Output format
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<Format>,
+    /// When to use colored output
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<ColorChoice>,
+    /// Pricing plan of the API key
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plan: Option<Plan>,
+    /// Whether to use the persistent response cache
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache: Option<bool>,
+    /// Time after which cached responses expire
+    #[serde(
+        serialize_with = "serialize_duration",
+        deserialize_with = "deserialize_duration",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub cache_ttl: Option<Duration>,
+    /// Location of the persistent response cache
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache_path: Option<PathBuf>,
+    /// Whether to record checks in the history
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub history: Option<bool>,
+    /// Location of the history
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub history_path: Option<PathBuf>,
+    /// Exit statuses for the outcomes of a run
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_codes: Option<ExitCodes>,
+    /// Named profiles
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+/// A named set of connection settings in the configuration file.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Profile {
+    /// Base URL of the API
+    #[serde(
+        serialize_with = "serialize_url",
+        deserialize_with = "deserialize_url",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub base_url: Option<Url>,
+    /// API key for paid plans
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+    /// Pricing plan of the API key
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plan: Option<Plan>,
+}
+
+impl Config {
+    /// Returns the default location of the configuration file, if the configuration directory can be determined.
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("iseven_api").join("config.toml"))
+    }
+
+    /// Loads the configuration file at `path`. A missing file is treated as an empty configuration.
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        match fs::read_to_string(path) {
+            Ok(contents) => toml::from_str(&contents).map_err(ConfigError::Parse),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(ConfigError::Io(e)),
+        }
+    }
+
+    /// Returns the number of numbers checked at a time, which defaults to one.
+    pub fn jobs(&self) -> NonZeroUsize {
+        self.jobs.unwrap_or(NonZeroUsize::MIN)
+    }
+
+    /// Returns the location of the persistent response cache, if it can be determined.
+    pub fn cache_path(&self) -> Option<PathBuf> {
+        self.cache_path.clone().or_else(super::cache::default_path)
+    }
+
+    /// Returns the location of the history, if it can be determined.
+    pub fn history_path(&self) -> Option<PathBuf> {
+        self.history_path
+            .clone()
+            .or_else(super::history::default_path)
+    }
+
+    /// Applies the profile called `name`, or the `default` profile if `name` is `None`. The returned configuration
+    /// contains no profiles.
+    ///
+    /// Selecting a profile which does not exist is an error, but a missing `default` profile is not.
+    pub fn select_profile(mut self, name: Option<&str>) -> Result<Self, ConfigError> {
+        let profile = match name {
+            Some(name) => Some(
+                self.profiles
+                    .remove(name)
+                    .ok_or_else(|| ConfigError::UnknownProfile(name.to_string()))?,
+            ),
+            None => self.profiles.remove("default"),
+        };
+        self.profiles.clear();
+        Ok(match profile {
+            Some(profile) => Self {
+                base_url: profile.base_url.or(self.base_url),
+                api_key: profile.api_key.or(self.api_key),
+                plan: profile.plan.or(self.plan),
+                ..self
+            },
+            None => self,
+        })
+    }
+
+    /// Returns a configuration where values set in `overrides` replace the values in `self`.
+    pub fn merge(self, overrides: Config) -> Self {
+        let mut profiles = self.profiles;
+        profiles.extend(overrides.profiles);
+        Self {
+            base_url: overrides.base_url.or(self.base_url),
+            api_key: overrides.api_key.or(self.api_key),
+            timeout: overrides.timeout.or(self.timeout),
+            unix_socket: overrides.unix_socket.or(self.unix_socket),
+            jobs: overrides.jobs.or(self.jobs),
+            format: overrides.format.or(self.format),
+            color: overrides.color.or(self.color),
+            plan: overrides.plan.or(self.plan),
+            cache: overrides.cache.or(self.cache),
+            cache_ttl: overrides.cache_ttl.or(self.cache_ttl),
+            cache_path: overrides.cache_path.or(self.cache_path),
+            history: overrides.history.or(self.history),
+            history_path: overrides.history_path.or(self.history_path),
+            exit_codes: match (self.exit_codes, overrides.exit_codes) {
+                (Some(codes), Some(overrides)) => Some(codes.merge(overrides)),
+                (codes, overrides) => overrides.or(codes),
+            },
+            profiles,
+        }
+    }
+
+    /// Returns the configuration with defaults filled in and the API key redacted, for display to the user.
+    pub fn effective(&self) -> Self {
+        Self {
+            api_key: self.api_key.as_ref().map(|_| "<redacted>".to_string()),
+            jobs: Some(self.jobs()),
+            format: Some(self.format.unwrap_or(Format::Human)),
+            color: Some(self.color.unwrap_or_default()),
+            plan: Some(self.plan.unwrap_or_default()),
+            cache: Some(self.cache.unwrap_or_default()),
+            cache_path: self.cache_path(),
+            history: Some(self.history.unwrap_or_default()),
+            history_path: self.history_path(),
+            ..self.clone()
+        }
+    }
+}
+
+/// Error loading the configuration file.
+#[derive(thiserror::Error, Debug)]
+pub enum ConfigError {
+    /// The file could not be read
+    #[error("unable to read configuration file: {0}")]
+    Io(io::Error),
+    /// The file is not a valid configuration
+    #[error("invalid configuration file: {0}")]
+    Parse(toml::de::Error),
+    /// The selected profile does not exist
+    #[error("no profile named `{0}` in configuration file")]
+    UnknownProfile(String),
+}
+
+fn serialize_url<S: Serializer>(url: &Option<Url>, serializer: S) -> Result<S::Ok, S::Error> {
+    match url {
+        Some(url) => serializer.serialize_str(url.as_str()),
+        None => serializer.serialize_none(),
+    }
+}
+
+fn deserialize_url<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Url>, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    Url::parse(&s).map(Some).map_err(serde::de::Error::custom)
+}
+
+fn serialize_duration<S: Serializer>(
+    duration: &Option<Duration>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match duration {
+        Some(d) => serializer.serialize_str(&humantime::format_duration(*d).to_string()),
+        None => serializer.serialize_none(),
+    }
+}
+
+fn deserialize_duration<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Duration>, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    humantime::parse_duration(&s)
+        .map(Some)
+        .map_err(serde::de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_merge() {
+        let file: Config = toml::from_str(
+            r#"
+            base-url = "http://localhost:8080/api/iseven/"
+            api-key = "secret"
+            timeout = "3s"
+            jobs = 4
+            format = "jsonl"
+            color = "never"
+
+            [exit-codes]
+            odd = 3
+            error = 4
+            "#,
+        )
+        .unwrap();
+        assert_eq!(file.timeout, Some(Duration::from_secs(3)));
+        assert_eq!(file.format, Some(Format::Jsonl));
+        assert_eq!(file.jobs(), NonZeroUsize::new(4).unwrap());
+        assert_eq!(Config::default().jobs(), NonZeroUsize::MIN);
+
+        let overrides = Config {
+            format: Some(Format::Yaml),
+            exit_codes: Some("odd=5".parse().unwrap()),
+            ..Config::default()
+        };
+        let merged = file.clone().merge(overrides);
+        assert_eq!(merged.format, Some(Format::Yaml));
+        assert_eq!(merged.exit_codes, Some("odd=5,error=4".parse().unwrap()));
+        assert_eq!(merged.color, Some(ColorChoice::Never));
+        assert_eq!(merged.base_url, file.base_url);
+    }
+
+    #[test]
+    fn test_select_profile() {
+        let file: Config = toml::from_str(
+            r#"
+            base-url = "http://localhost:8080/"
+            format = "jsonl"
+
+            [profiles.default]
+            api-key = "default-key"
+
+            [profiles.enterprise]
+            api-key = "enterprise-key"
+            plan = "enterprise"
+            "#,
+        )
+        .unwrap();
+
+        let default = file.clone().select_profile(None).unwrap();
+        assert_eq!(default.api_key.as_deref(), Some("default-key"));
+        assert_eq!(default.plan, None);
+        assert!(default.profiles.is_empty());
+
+        let enterprise = file.clone().select_profile(Some("enterprise")).unwrap();
+        assert_eq!(enterprise.api_key.as_deref(), Some("enterprise-key"));
+        assert_eq!(enterprise.plan, Some(Plan::Enterprise));
+        assert_eq!(enterprise.base_url, file.base_url);
+
+        assert!(file.select_profile(Some("staging")).is_err());
+    }
+
+    #[test]
+    fn test_unknown_key() {
+        assert!(toml::from_str::<Config>("colour = \"never\"").is_err());
+    }
+}
@@ -0,0 +1,253 @@
+//! Reading numbers from a column of a CSV file (`--csv` and `--column`).
+
+use std::{
+    fs,
+    io::{self, Read},
+    path::Path,
+    str::FromStr,
+};
+
+/// Column holding the numbers, by header name or 1-based index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Column {
+    Name(String),
+    Index(usize),
+}
+
+impl FromStr for Column {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.parse::<usize>() {
+            Ok(0) => Err("column indices start at 1".to_string()),
+            Ok(index) => Ok(Column::Index(index)),
+            Err(_) if s.is_empty() => Err("column name must not be empty".to_string()),
+            Err(_) => Ok(Column::Name(s.to_string())),
+        }
+    }
+}
+
+/// Errors from reading a CSV file.
+#[derive(thiserror::Error, Debug)]
+pub enum CsvError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("line {0}: unterminated quoted field")]
+    UnterminatedQuote(usize),
+    #[error("no column named \"{0}\" in the header")]
+    UnknownColumn(String),
+    #[error("line {line}: no column {column}")]
+    MissingColumn { line: usize, column: usize },
+}
+
+/// Rows of a CSV file, with the position of the column holding the numbers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Table {
+    /// Header row, if the file has one
+    pub header: Option<Vec<String>>,
+    /// Data rows
+    pub rows: Vec<Vec<String>>,
+    /// Line on which each data row starts
+    lines: Vec<usize>,
+    /// 0-based position of the number column
+    column: usize,
+}
+
+impl Table {
+    /// Reads the CSV file at `path`, or stdin if it is `-`.
+    ///
+    /// The first row is a header if `column` is given by name. For a column given by index, the first row is taken as
+    /// a header if its value in that column is not a number according to `is_number`.
+    pub fn read(
+        path: &Path,
+        column: &Column,
+        is_number: impl Fn(&str) -> bool,
+    ) -> Result<Self, CsvError> {
+        let text = if path == Path::new("-") {
+            let mut text = String::new();
+            io::stdin().read_to_string(&mut text)?;
+            text
+        } else {
+            fs::read_to_string(path)?
+        };
+        Self::parse(&text, column, is_number)
+    }
+
+    fn parse(
+        text: &str,
+        column: &Column,
+        is_number: impl Fn(&str) -> bool,
+    ) -> Result<Self, CsvError> {
+        let (mut lines, mut rows): (Vec<_>, Vec<_>) = parse(text)?.into_iter().unzip();
+        let (column, has_header) = match column {
+            Column::Name(name) => {
+                let position = rows
+                    .first()
+                    .and_then(|header| header.iter().position(|field| field == name))
+                    .ok_or_else(|| CsvError::UnknownColumn(name.clone()))?;
+                (position, true)
+            }
+            Column::Index(index) => {
+                let position = index - 1;
+                let has_header = rows
+                    .first()
+                    .and_then(|row| row.get(position))
+                    .is_some_and(|value| !is_number(value));
+                (position, has_header)
+            }
+        };
+        let header = has_header.then(|| {
+            lines.remove(0);
+            rows.remove(0)
+        });
+        if let Some(index) = rows.iter().position(|row| row.len() <= column) {
+            return Err(CsvError::MissingColumn {
+                line: lines[index],
+                column: column + 1,
+            });
+        }
+        Ok(Self {
+            header,
+            rows,
+            lines,
+            column,
+        })
+    }
+
+    /// Returns the line on which the data row at `index` starts.
+    pub fn line(&self, index: usize) -> usize {
+        self.lines[index]
+    }
+
+    /// Returns the value of the number column in each data row.
+    pub fn values(&self) -> impl Iterator<Item = &str> {
+        self.rows.iter().map(|row| row[self.column].as_str())
+    }
+}
+
+/// Splits CSV `text` into rows of fields, following RFC 4180, each with the line on which it starts. Blank lines are
+/// skipped.
+fn parse(text: &str) -> Result<Vec<(usize, Vec<String>)>, CsvError> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut line = 1;
+    let mut row_line = 1;
+    let mut quote_line = 0;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    in_quotes = false;
+                }
+            }
+            '"' if field.is_empty() => {
+                in_quotes = true;
+                quote_line = line;
+            }
+            ',' if !in_quotes => row.push(std::mem::take(&mut field)),
+            '\r' if !in_quotes && chars.peek() == Some(&'\n') => {}
+            '\n' if !in_quotes => {
+                row.push(std::mem::take(&mut field));
+                if row.len() > 1 || !row[0].is_empty() {
+                    rows.push((row_line, std::mem::take(&mut row)));
+                } else {
+                    row.clear();
+                }
+                line += 1;
+                row_line = line;
+            }
+            c => {
+                if c == '\n' {
+                    line += 1;
+                }
+                field.push(c);
+            }
+        }
+    }
+    if in_quotes {
+        return Err(CsvError::UnterminatedQuote(quote_line));
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push((row_line, row));
+    }
+    Ok(rows)
+}
+
+/// Formats fields as a CSV record, quoting fields which contain separators, quotes or line breaks.
+pub fn format_record<'a>(fields: impl IntoIterator<Item = &'a str>) -> String {
+    fields
+        .into_iter()
+        .map(|field| {
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_number(value: &str) -> bool {
+        value.parse::<i64>().is_ok()
+    }
+
+    #[test]
+    fn test_parse() {
+        let rows = parse("a,\"b, \"\"c\"\"\"\r\n\n1,\"2\n3\"\n4,").unwrap();
+        assert_eq!(
+            rows,
+            [
+                (1, vec!["a".to_string(), "b, \"c\"".to_string()]),
+                (3, vec!["1".to_string(), "2\n3".to_string()]),
+                (5, vec!["4".to_string(), String::new()])
+            ]
+        );
+        assert!(matches!(
+            parse("a\n\"b,c\n"),
+            Err(CsvError::UnterminatedQuote(2))
+        ));
+        let record = format_record(["1", "b, \"c\"", "even"]);
+        assert_eq!(record, "1,\"b, \"\"c\"\"\",even");
+        assert_eq!(parse(&record).unwrap()[0].1, ["1", "b, \"c\"", "even"]);
+    }
+
+    #[test]
+    fn test_columns() {
+        let text = "id,value\n1,42\n2,7\n";
+        let table = Table::parse(text, &"value".parse().unwrap(), is_number).unwrap();
+        assert_eq!(
+            table.header.as_deref(),
+            Some(&["id", "value"].map(String::from)[..])
+        );
+        assert_eq!(table.values().collect::<Vec<_>>(), ["42", "7"]);
+        assert_eq!(table.line(1), 3);
+
+        let table = Table::parse(text, &"2".parse().unwrap(), is_number).unwrap();
+        assert!(table.header.is_some());
+        let table = Table::parse("1,42\n2,7\n", &"2".parse().unwrap(), is_number).unwrap();
+        assert!(table.header.is_none());
+        assert_eq!(table.values().collect::<Vec<_>>(), ["42", "7"]);
+
+        assert!(matches!(
+            Table::parse(text, &"missing".parse().unwrap(), is_number),
+            Err(CsvError::UnknownColumn(_))
+        ));
+        assert!(matches!(
+            Table::parse("1,42\n\n2\n", &"2".parse().unwrap(), is_number),
+            Err(CsvError::MissingColumn { line: 3, column: 2 })
+        ));
+        assert!("0".parse::<Column>().is_err());
+    }
+}
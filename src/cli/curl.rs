@@ -0,0 +1,46 @@
+//! Equivalent `curl` command lines for requests (`--curl`).
+
+use std::time::Duration;
+
+/// Returns a `curl` command line making the same request as the client does for `url`.
+///
+/// The API key is never included; if one is configured, the command reads it from the `ISEVEN_API_KEY` environment
+/// variable instead, so the output can be shared in bug reports.
+pub fn command(url: &str, has_api_key: bool, timeout: Option<Duration>) -> String {
+    let mut command = "curl -sS".to_string();
+    if let Some(timeout) = timeout {
+        command.push_str(&format!(" --max-time {}", timeout.as_secs_f64()));
+    }
+    if has_api_key {
+        command.push_str(" -H \"Authorization: Bearer $ISEVEN_API_KEY\"");
+    }
+    command.push(' ');
+    command.push_str(&shell_quote(url));
+    command
+}
+
+/// Quotes `s` for POSIX shells.
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command() {
+        assert_eq!(
+            command("https://api.isevenapi.xyz/api/iseven/42", false, None),
+            "curl -sS 'https://api.isevenapi.xyz/api/iseven/42'"
+        );
+        assert_eq!(
+            command(
+                "http://localhost/api/iseven/it's",
+                true,
+                Some(Duration::from_millis(1500))
+            ),
+            "curl -sS --max-time 1.5 -H \"Authorization: Bearer $ISEVEN_API_KEY\" 'http://localhost/api/iseven/it'\\''s'"
+        );
+    }
+}
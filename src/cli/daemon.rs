@@ -0,0 +1,363 @@
+//! Long-running daemon sharing one client over a Unix domain socket (`daemon` subcommand and `--via-daemon`).
+//!
+//! Short-lived invocations of the command line app pay for a new connection to the API and start with an empty
+//! cache. The daemon keeps both warm, and answers requests from any number of clients on its socket.
+//!
+//! The protocol is a sequence of frames, each a 4-byte big-endian length followed by that many bytes. A client sends
+//! the number to check as UTF-8, and the daemon answers with a JSON object holding the HTTP `status` and `body` of
+//! the API's answer. Failures to reach the API are reported with status 502 and an `error` body, like the proxy
+//! server does. A connection can be used for any number of requests.
+
+use std::{
+    error::Error,
+    io,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::Duration,
+};
+
+use clap::Args;
+use http::StatusCode;
+use iseven_api::{
+    ApiError, IsEvenApiClient, IsEvenApiClientBuilder, IsEvenApiError, IsEvenApiErrorResponse,
+    IsEvenApiResponse, ProtocolError,
+};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{UnixListener, UnixStream},
+};
+
+use super::cache;
+
+/// Largest frame accepted, far larger than any number or response.
+const MAX_FRAME_LEN: u32 = 64 * 1024;
+
+/// Arguments for the `daemon` subcommand.
+#[derive(Debug, Args)]
+pub struct DaemonArgs {
+    /// Path of the socket to listen on [default: iseven_api.sock in the runtime or temporary directory]
+    #[arg(long)]
+    socket: Option<PathBuf>,
+
+    /// Maximum number of cached responses
+    #[arg(long, default_value_t = 10_000)]
+    cache_size: usize,
+}
+
+/// Answer to a request, as sent over the socket.
+#[derive(Debug, Serialize, Deserialize)]
+struct Reply {
+    status: u16,
+    body: Value,
+}
+
+/// Returns the socket used if none is given.
+pub fn default_socket() -> PathBuf {
+    dirs::runtime_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("iseven_api.sock")
+}
+
+impl DaemonArgs {
+    /// Builds the client from `builder` and answers requests on the socket until interrupted.
+    ///
+    /// Cached responses expire after `cache_ttl`. If `persist` is given, the cache is loaded from that file on startup
+    /// and saved back to it on shutdown.
+    pub async fn run(
+        &self,
+        builder: IsEvenApiClientBuilder,
+        cache_ttl: Option<Duration>,
+        persist: Option<&Path>,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut cache = iseven_api::ResponseCache::new(self.cache_size);
+        if let Some(ttl) = cache_ttl {
+            cache = cache.with_ttl(ttl);
+        }
+        if let Some(path) = persist {
+            cache::load(path, &cache)?;
+        }
+        let client = builder.cache(cache.clone()).build()?;
+        let socket = self.socket.clone().unwrap_or_else(default_socket);
+        let listener = bind(&socket).await?;
+        eprintln!("Listening on {}", socket.display());
+        let result = tokio::select! {
+            result = accept(listener, client) => result,
+            result = tokio::signal::ctrl_c() => result,
+        };
+        if let Err(e) = std::fs::remove_file(&socket) {
+            warn!("Unable to remove {}: {}", socket.display(), e);
+        }
+        result?;
+        if let Some(path) = persist {
+            cache::save(path, &cache)?;
+        }
+        Ok(())
+    }
+}
+
+/// Binds `socket`, replacing a socket file left behind by a server which is no longer running.
+pub async fn bind(socket: &Path) -> io::Result<UnixListener> {
+    match UnixListener::bind(socket) {
+        Err(e) if e.kind() == io::ErrorKind::AddrInUse => {
+            if UnixStream::connect(socket).await.is_ok() {
+                return Err(io::Error::new(
+                    io::ErrorKind::AddrInUse,
+                    format!(
+                        "another server is already listening on {}",
+                        socket.display()
+                    ),
+                ));
+            }
+            std::fs::remove_file(socket)?;
+            UnixListener::bind(socket)
+        }
+        result => result,
+    }
+}
+
+async fn accept(listener: UnixListener, client: IsEvenApiClient) -> io::Result<()> {
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let client = client.clone();
+        tokio::spawn(async move {
+            if let Err(e) = serve_connection(stream, &client).await {
+                debug!("Connection closed: {}", e);
+            }
+        });
+    }
+}
+
+/// Answers requests on `stream` until the client disconnects.
+async fn serve_connection(mut stream: UnixStream, client: &IsEvenApiClient) -> io::Result<()> {
+    while let Some(request) = read_frame(&mut stream).await? {
+        let number = String::from_utf8_lossy(&request);
+        debug!("Checking {} for a daemon client", number);
+        let reply = match client.get_str(&number).await {
+            Ok(response) => Reply {
+                status: 200,
+                body: json!(response),
+            },
+            Err(e) => error_reply(&e),
+        };
+        let reply = serde_json::to_vec(&reply).expect("Unable to serialize reply");
+        write_frame(&mut stream, &reply).await?;
+    }
+    Ok(())
+}
+
+fn error_reply(error: &IsEvenApiError) -> Reply {
+    match error {
+        IsEvenApiError::Api(e) => Reply {
+            status: e.status().as_u16(),
+            body: json!(e.response()),
+        },
+        IsEvenApiError::InvalidInput { .. } => Reply {
+            status: 400,
+            body: json!({ "error": error.to_string() }),
+        },
+        IsEvenApiError::Protocol(ProtocolError::UnexpectedStatus(e, status)) => Reply {
+            status: status.as_u16(),
+            body: json!(e),
+        },
+        IsEvenApiError::Protocol(ProtocolError::UnexpectedBody(_, status, _)) => Reply {
+            status: status.as_u16(),
+            body: json!({ "error": error.to_string() }),
+        },
+        _ => Reply {
+            status: 502,
+            body: json!({ "error": error.to_string() }),
+        },
+    }
+}
+
+/// Reads a frame, returning `None` if the peer closed the connection between frames.
+async fn read_frame(stream: &mut UnixStream) -> io::Result<Option<Vec<u8>>> {
+    let mut len = [0; 4];
+    match stream.read_exact(&mut len).await {
+        Ok(_) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    let len = u32::from_be_bytes(len);
+    if len > MAX_FRAME_LEN {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("frame of {} bytes is too large", len),
+        ));
+    }
+    let mut frame = vec![0; len as usize];
+    stream.read_exact(&mut frame).await?;
+    Ok(Some(frame))
+}
+
+async fn write_frame(stream: &mut UnixStream, frame: &[u8]) -> io::Result<()> {
+    let len = u32::try_from(frame.len())
+        .ok()
+        .filter(|&len| len <= MAX_FRAME_LEN)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "frame is too large"))?;
+    stream.write_all(&len.to_be_bytes()).await?;
+    stream.write_all(frame).await?;
+    stream.flush().await
+}
+
+/// Connections to a running daemon, for `--via-daemon`.
+///
+/// Each request takes a connection of its own, reusing an idle one if there is any, so that concurrent requests
+/// (`--jobs`) are answered concurrently. A connection is only reused after a complete exchange; one whose request
+/// failed or was cancelled may hold part of a frame, so it is closed instead.
+#[derive(Debug)]
+pub struct DaemonClient {
+    socket: PathBuf,
+    idle: Mutex<Vec<UnixStream>>,
+}
+
+impl DaemonClient {
+    /// Connects to the daemon listening on `socket`.
+    pub async fn connect(socket: PathBuf) -> io::Result<Self> {
+        let stream = Self::open(&socket).await?;
+        Ok(Self {
+            socket,
+            idle: Mutex::new(vec![stream]),
+        })
+    }
+
+    async fn open(socket: &Path) -> io::Result<UnixStream> {
+        UnixStream::connect(socket).await.map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                format!(
+                    "unable to connect to the daemon on {}: {}",
+                    socket.display(),
+                    e
+                ),
+            )
+        })
+    }
+
+    /// Checks whether `number` is even.
+    pub async fn get(&self, number: &str) -> Result<IsEvenApiResponse, IsEvenApiError> {
+        let reply = self.request(number).await;
+        let invalid =
+            |e: serde_json::Error| error_response(&format!("invalid reply from the daemon: {}", e));
+        if reply.status == 200 {
+            return serde_json::from_value(reply.body).map_err(|e| {
+                ProtocolError::UnexpectedStatus(invalid(e), StatusCode::BAD_GATEWAY).into()
+            });
+        }
+        let error = serde_json::from_value(reply.body).unwrap_or_else(invalid);
+        let status = StatusCode::from_u16(reply.status).unwrap_or(StatusCode::BAD_GATEWAY);
+        Err(match ApiError::from_status(status, error) {
+            Ok(e) => e.into(),
+            Err(e) => ProtocolError::UnexpectedStatus(e, status).into(),
+        })
+    }
+
+    /// Returns the body of the API's answer for `number` as JSON.
+    pub async fn get_json(&self, number: &str) -> Result<String, IsEvenApiError> {
+        Ok(self.request(number).await.body.to_string())
+    }
+
+    /// Returns a description of where requests for `number` are sent.
+    pub fn request_url(&self, number: &str) -> String {
+        format!("unix:{} {}", self.socket.display(), number)
+    }
+
+    /// Sends a request, turning failures to talk to the daemon into a 502 reply.
+    async fn request(&self, number: &str) -> Reply {
+        let idle = self.idle.lock().unwrap_or_else(|e| e.into_inner()).pop();
+        let result = async {
+            let mut stream = match idle {
+                Some(stream) => stream,
+                None => Self::open(&self.socket).await?,
+            };
+            write_frame(&mut stream, number.as_bytes()).await?;
+            let frame = read_frame(&mut stream).await?.ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "the daemon closed the connection",
+                )
+            })?;
+            // the exchange is complete, so the connection can be reused
+            self.idle
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .push(stream);
+            serde_json::from_slice::<Reply>(&frame).map_err(io::Error::from)
+        }
+        .await;
+        result.unwrap_or_else(|e| Reply {
+            status: 502,
+            body: json!({ "error": format!("daemon connection failed: {}", e) }),
+        })
+    }
+}
+
+fn error_response(message: &str) -> IsEvenApiErrorResponse {
+    serde_json::from_value(json!({ "error": message }))
+        .expect("error responses have a single field")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_round_trip() {
+        let (client_end, mut daemon_end) = UnixStream::pair().unwrap();
+        let client = DaemonClient {
+            socket: PathBuf::from("test.sock"),
+            idle: Mutex::new(vec![client_end]),
+        };
+        tokio::spawn(async move {
+            while let Some(request) = read_frame(&mut daemon_end).await.unwrap() {
+                let reply = match &request[..] {
+                    b"42" => json!({ "status": 200, "body": { "ad": "", "iseven": true } }),
+                    _ => json!({ "status": 400, "body": { "error": "Invalid number." } }),
+                };
+                write_frame(&mut daemon_end, reply.to_string().as_bytes())
+                    .await
+                    .unwrap();
+            }
+        });
+        assert!(client.get("42").await.unwrap().iseven());
+        assert!(matches!(
+            client.get("abc").await,
+            Err(IsEvenApiError::Api(ApiError::InvalidNumber(_)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_reconnect() {
+        let socket =
+            std::env::temp_dir().join(format!("iseven_api-test-{}.sock", std::process::id()));
+        let listener = bind(&socket).await.unwrap();
+        tokio::spawn(async move {
+            // the first connection is closed before answering, later ones are answered
+            drop(listener.accept().await.unwrap());
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                tokio::spawn(async move {
+                    while read_frame(&mut stream).await.unwrap().is_some() {
+                        let reply = json!({ "status": 200, "body": { "ad": "", "iseven": true } });
+                        write_frame(&mut stream, reply.to_string().as_bytes())
+                            .await
+                            .unwrap();
+                    }
+                });
+            }
+        });
+
+        let client = DaemonClient::connect(socket.clone()).await.unwrap();
+        assert!(client.get("42").await.is_err());
+        assert!(client.idle.lock().unwrap().is_empty());
+        assert!(client.get("42").await.unwrap().iseven());
+        // concurrent requests use connections of their own
+        let (first, second) = tokio::join!(client.get("42"), client.get("7"));
+        assert!(first.is_ok() && second.is_ok());
+        assert_eq!(client.idle.lock().unwrap().len(), 2);
+        std::fs::remove_file(&socket).unwrap();
+    }
+}
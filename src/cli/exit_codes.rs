@@ -0,0 +1,134 @@
+//! Custom exit statuses for the outcomes of a run (`--exit-code-map` and the `[exit-codes]` configuration section).
+
+use std::str::FromStr;
+
+use iseven_api::IsEvenApiError;
+use serde::{Deserialize, Serialize};
+
+/// Exit statuses chosen by the user. Outcomes which are not mapped keep their default status.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ExitCodes {
+    /// Status if any number is odd and nothing failed [default: 0, or 1 with `--exit-code`]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub odd: Option<u8>,
+    /// Status if any number failed to be checked [default: 1, or 2 with `--exit-code`]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<u8>,
+    /// Status if any request was rejected by rate limiting (HTTP 429), taking precedence over `error`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rate_limited: Option<u8>,
+}
+
+impl ExitCodes {
+    /// Returns the codes where values set in `overrides` replace the values in `self`.
+    pub fn merge(self, overrides: ExitCodes) -> Self {
+        Self {
+            odd: overrides.odd.or(self.odd),
+            error: overrides.error.or(self.error),
+            rate_limited: overrides.rate_limited.or(self.rate_limited),
+        }
+    }
+
+    /// Returns the status when numbers failed to be checked, where `exit_code` is whether `--exit-code` was given.
+    pub fn failure(&self, exit_code: bool, rate_limited: bool) -> i32 {
+        let default = if exit_code { 2 } else { 1 };
+        let code = if rate_limited {
+            self.rate_limited.or(self.error)
+        } else {
+            self.error
+        };
+        code.map_or(default, i32::from)
+    }
+
+    /// Returns the status when all numbers were checked and some are odd, where `exit_code` is whether `--exit-code`
+    /// was given.
+    pub fn odd(&self, exit_code: bool) -> i32 {
+        self.odd.map_or(i32::from(exit_code), i32::from)
+    }
+}
+
+/// Returns `true` if `error` is the API rejecting a request because of rate limiting.
+pub fn is_rate_limited(error: &IsEvenApiError) -> bool {
+    error.status().is_some_and(|s| s.as_u16() == 429)
+}
+
+/// Parses a list such as `odd=3,error=4,rate-limited=5`.
+impl FromStr for ExitCodes {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut codes = ExitCodes::default();
+        for entry in s.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (outcome, code) = entry
+                .split_once('=')
+                .ok_or_else(|| format!("expected OUTCOME=CODE, found \"{}\"", entry))?;
+            let code = code.trim().parse::<u8>().map_err(|_| {
+                format!("exit status must be between 0 and 255, found \"{}\"", code)
+            })?;
+            let slot = match outcome.trim() {
+                "odd" => &mut codes.odd,
+                "error" => &mut codes.error,
+                "rate-limited" => &mut codes.rate_limited,
+                other => {
+                    return Err(format!(
+                        "unknown outcome \"{}\", expected odd, error or rate-limited",
+                        other
+                    ))
+                }
+            };
+            *slot = Some(code);
+        }
+        Ok(codes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let codes = "odd=3, rate-limited=75".parse::<ExitCodes>().unwrap();
+        assert_eq!(
+            codes,
+            ExitCodes {
+                odd: Some(3),
+                error: None,
+                rate_limited: Some(75)
+            }
+        );
+        assert!("even=1".parse::<ExitCodes>().is_err());
+        assert!("odd=256".parse::<ExitCodes>().is_err());
+        assert!("odd".parse::<ExitCodes>().is_err());
+
+        let file = ExitCodes {
+            odd: Some(1),
+            error: Some(9),
+            rate_limited: None,
+        };
+        assert_eq!(
+            file.merge(codes),
+            ExitCodes {
+                odd: Some(3),
+                error: Some(9),
+                rate_limited: Some(75)
+            }
+        );
+    }
+
+    #[test]
+    fn test_defaults() {
+        let codes = ExitCodes::default();
+        assert_eq!(codes.failure(false, true), 1);
+        assert_eq!(codes.failure(true, false), 2);
+        assert_eq!(codes.odd(false), 0);
+        assert_eq!(codes.odd(true), 1);
+
+        let codes = "error=4".parse::<ExitCodes>().unwrap();
+        assert_eq!(codes.failure(true, true), 4);
+        let codes = "error=4,rate-limited=75".parse::<ExitCodes>().unwrap();
+        assert_eq!(codes.failure(false, true), 75);
+        assert_eq!(codes.failure(false, false), 4);
+    }
+}
@@ -0,0 +1,88 @@
+//! Reporting of numbers which could not be checked (`--errors`).
+
+use std::fmt::{Display, Formatter};
+
+use clap::ValueEnum;
+use iseven_api::IsEvenApiError;
+use serde::Serialize;
+
+use super::{
+    input::{Input, InputError},
+    source::Location,
+    stats::error_kind,
+};
+
+/// Formats of failure reports on stderr, as accepted by `--errors`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ErrorFormat {
+    /// Human-readable error messages
+    #[default]
+    Text,
+    /// One JSON object per failure, with the number, error kind, message and HTTP status
+    Json,
+}
+
+/// A number which could not be checked.
+#[derive(Debug, Serialize)]
+pub struct Failure {
+    /// Number as requested, or the input as given if it could not be converted
+    pub number: String,
+    /// Original input, if it was converted to decimal
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input: Option<String>,
+    /// Kind of error, as counted in `--summary-json`
+    pub kind: &'static str,
+    pub message: String,
+    /// HTTP status of the response, if the API answered
+    pub status: Option<u16>,
+    /// Where the input came from, reported by `--strict`
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    pub location: Option<Location>,
+}
+
+impl Failure {
+    /// Describes an error from checking `input` with the API.
+    pub fn api(input: &Input, error: &IsEvenApiError) -> Self {
+        Self {
+            number: input.number.clone(),
+            input: input.converted_from().map(str::to_string),
+            kind: error_kind(error),
+            message: error.to_string(),
+            status: error.status().map(|s| s.as_u16()),
+            location: None,
+        }
+    }
+
+    /// Describes an `input` which could not be converted to a number.
+    pub fn invalid_input(input: &str, error: &InputError) -> Self {
+        Self {
+            number: input.to_string(),
+            input: None,
+            kind: "invalid_input",
+            message: error.to_string(),
+            status: None,
+            location: None,
+        }
+    }
+
+    /// Adds where the input came from.
+    pub fn at(self, location: Location) -> Self {
+        Self {
+            location: Some(location),
+            ..self
+        }
+    }
+}
+
+/// Displays the input as it appears in human-readable error messages, preceded by its location if known.
+impl Display for Failure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if let Some(location) = self.location {
+            write!(f, "{}: ", location)?;
+        }
+        match &self.input {
+            Some(input) => write!(f, "{} ({})", input, self.number),
+            None => f.write_str(&self.number),
+        }
+    }
+}
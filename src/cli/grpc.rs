@@ -0,0 +1,225 @@
+//! gRPC facade of the proxy server (`serve --grpc`).
+//!
+//! Implements the `iseven.v1.Parity` service defined in `proto/iseven.proto`. The service has a single unary method
+//! with two tiny messages, so they are encoded and decoded by hand rather than with tonic and prost-build: the method
+//! is served by the proxy's axum router on the same port as its HTTP routes, which a separate tonic server would not
+//! share, and code generation would require `protoc` to build the app. `tests/grpc.rs` calls the method over HTTP/2,
+//! and CI calls it with `grpcurl` from the `.proto` file, to check the hand-written encoding against real clients.
+
+use std::convert::Infallible;
+
+use axum::{
+    body::{Body, Bytes},
+    extract::State,
+    http::{header::CONTENT_TYPE, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use http_body_util::{BodyExt, Full};
+use iseven_api::{ApiError, IsEvenApiError, IsEvenApiResponse};
+use log::debug;
+
+use super::serve::ProxyState;
+
+/// Route of the `CheckParity` method.
+pub const CHECK_PARITY_PATH: &str = "/iseven.v1.Parity/CheckParity";
+
+/// gRPC status codes, as defined in <https://grpc.github.io/grpc/core/md_doc_statuscodes.html>.
+mod code {
+    pub const OK: u8 = 0;
+    pub const UNKNOWN: u8 = 2;
+    pub const INVALID_ARGUMENT: u8 = 3;
+    pub const PERMISSION_DENIED: u8 = 7;
+    pub const RESOURCE_EXHAUSTED: u8 = 8;
+    pub const OUT_OF_RANGE: u8 = 11;
+    pub const UNIMPLEMENTED: u8 = 12;
+    pub const INTERNAL: u8 = 13;
+    pub const UNAVAILABLE: u8 = 14;
+}
+
+/// Handles a `CheckParity` call.
+pub async fn check_parity(
+    State(state): State<ProxyState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let is_grpc = headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/grpc"));
+    if !is_grpc {
+        return StatusCode::UNSUPPORTED_MEDIA_TYPE.into_response();
+    }
+    let number = match unframe(&body).and_then(decode_request) {
+        Ok(number) => number,
+        Err((code, message)) => return reply(None, code, message),
+    };
+    debug!("Checking {} for a gRPC client", number);
+    match state.check(&number, false).await {
+        Ok(response) => reply(Some(encode_reply(&response)), code::OK, String::new()),
+        Err(e) => reply(None, status_code(&e), e.to_string()),
+    }
+}
+
+/// Returns the gRPC status code for an error from the API.
+fn status_code(error: &IsEvenApiError) -> u8 {
+    match error {
+        IsEvenApiError::Api(ApiError::InvalidNumber(_)) | IsEvenApiError::InvalidInput { .. } => {
+            code::INVALID_ARGUMENT
+        }
+        IsEvenApiError::Api(ApiError::NumberOutOfRange(_)) => code::OUT_OF_RANGE,
+        IsEvenApiError::Api(ApiError::Unauthorized(_)) => code::PERMISSION_DENIED,
+        _ if error.status().is_some_and(|s| s.as_u16() == 429) => code::RESOURCE_EXHAUSTED,
+        IsEvenApiError::Transport(_) => code::UNAVAILABLE,
+        _ => code::UNKNOWN,
+    }
+}
+
+/// Builds a response holding `message`, if any, with the status in the trailers.
+fn reply(message: Option<Vec<u8>>, status: u8, status_message: String) -> Response {
+    let mut body = Vec::new();
+    if let Some(message) = message {
+        body.push(0);
+        body.extend_from_slice(&(message.len() as u32).to_be_bytes());
+        body.extend_from_slice(&message);
+    }
+    let mut trailers = HeaderMap::new();
+    trailers.insert("grpc-status", HeaderValue::from(u16::from(status)));
+    if !status_message.is_empty() {
+        let encoded = percent_encode(&status_message);
+        trailers.insert(
+            "grpc-message",
+            HeaderValue::from_str(&encoded).expect("percent-encoded message is a valid header"),
+        );
+    }
+    let body = Full::new(Bytes::from(body))
+        .with_trailers(std::future::ready(Some(Ok::<_, Infallible>(trailers))));
+    (
+        [(CONTENT_TYPE, HeaderValue::from_static("application/grpc"))],
+        Body::new(body),
+    )
+        .into_response()
+}
+
+/// Returns the single message in a request body, which is prefixed by a compression flag and its length.
+fn unframe(body: &[u8]) -> Result<&[u8], (u8, String)> {
+    let malformed = || (code::INTERNAL, "malformed request message".to_string());
+    let (&compressed, rest) = body.split_first().ok_or_else(malformed)?;
+    if compressed != 0 {
+        return Err((
+            code::UNIMPLEMENTED,
+            "compression is not supported".to_string(),
+        ));
+    }
+    let (length, message) = rest.split_first_chunk::<4>().ok_or_else(malformed)?;
+    if message.len() != u32::from_be_bytes(*length) as usize {
+        return Err(malformed());
+    }
+    Ok(message)
+}
+
+/// Decodes a `CheckParityRequest`, returning its number.
+fn decode_request(mut message: &[u8]) -> Result<String, (u8, String)> {
+    let malformed = || (code::INTERNAL, "malformed CheckParityRequest".to_string());
+    let mut number = String::new();
+    while !message.is_empty() {
+        let key = read_varint(&mut message).ok_or_else(malformed)?;
+        let skip = match key & 0x7 {
+            0 => {
+                read_varint(&mut message).ok_or_else(malformed)?;
+                0
+            }
+            1 => 8,
+            2 => {
+                let len = read_varint(&mut message).ok_or_else(malformed)? as usize;
+                let field = message.get(..len).ok_or_else(malformed)?;
+                if key >> 3 == 1 {
+                    number = String::from_utf8(field.to_vec()).map_err(|_| malformed())?;
+                }
+                len
+            }
+            5 => 4,
+            _ => return Err(malformed()),
+        };
+        message = message.get(skip..).ok_or_else(malformed)?;
+    }
+    Ok(number)
+}
+
+/// Encodes a `ParityReply`. Fields with default values are omitted, as in proto3.
+fn encode_reply(response: &IsEvenApiResponse) -> Vec<u8> {
+    let mut message = Vec::new();
+    if response.iseven() {
+        message.extend_from_slice(&[0x08, 0x01]);
+    }
+    if !response.ad().is_empty() {
+        message.push(0x12);
+        write_varint(&mut message, response.ad().len() as u64);
+        message.extend_from_slice(response.ad().as_bytes());
+    }
+    message
+}
+
+fn read_varint(input: &mut &[u8]) -> Option<u64> {
+    let mut value = 0;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = input.split_first()?;
+        *input = rest;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+fn write_varint(output: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        output.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    output.push(value as u8);
+}
+
+/// Percent-encodes a status message as required for the `grpc-message` trailer.
+fn percent_encode(message: &str) -> String {
+    message
+        .bytes()
+        .map(|b| match b {
+            b' '..=b'~' if b != b'%' => (b as char).to_string(),
+            b => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_request() {
+        // number = "300", preceded by an unknown varint field 2 = 150
+        let message = [0x10, 0x96, 0x01, 0x0a, 0x03, b'3', b'0', b'0'];
+        let mut body = vec![0, 0, 0, 0, message.len() as u8];
+        body.extend_from_slice(&message);
+        assert_eq!(unframe(&body).and_then(decode_request).unwrap(), "300");
+        assert_eq!(decode_request(&[]).unwrap(), "");
+        assert!(decode_request(&[0x0a, 0x05, b'1']).is_err());
+        assert_eq!(
+            unframe(&[1, 0, 0, 0, 0]).unwrap_err().0,
+            code::UNIMPLEMENTED
+        );
+        assert_eq!(unframe(&[0, 0, 0, 0, 2, 0]).unwrap_err().0, code::INTERNAL);
+    }
+
+    #[test]
+    fn test_varint_and_percent_encode() {
+        let mut output = Vec::new();
+        write_varint(&mut output, 300);
+        assert_eq!(output, [0xac, 0x02]);
+        assert_eq!(read_varint(&mut &output[..]), Some(300));
+        assert_eq!(
+            percent_encode("50% off: ünïcode"),
+            "50%25 off: %C3%BCn%C3%AFcode"
+        );
+    }
+}
@@ -0,0 +1,178 @@
+//! History of checked numbers (`--history` and the `history` subcommand).
+//!
+//! Every successful check is appended as a JSON object on its own line, by default to `history.jsonl` in the
+//! `iseven_api` directory under the user's data directory (e.g. `~/.local/share/iseven_api/history.jsonl` on Linux).
+
+use std::{
+    error::Error,
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use clap::{Args, Subcommand};
+use serde::{Deserialize, Serialize};
+
+/// Returns the default location of the history, if the data directory can be determined.
+pub fn default_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("iseven_api").join("history.jsonl"))
+}
+
+/// A recorded check.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    /// Number as sent to the API
+    pub number: String,
+    /// `even` or `odd`
+    pub parity: String,
+    /// When the number was checked, in RFC 3339 format
+    pub checked_at: String,
+    /// Where the number came from: `argument`, `stdin`, `csv`, `clipboard` or `random`
+    pub source: String,
+}
+
+impl Entry {
+    /// Creates an entry for a check of `number` made now.
+    pub fn new(number: &str, iseven: bool, source: &str) -> Self {
+        Self {
+            number: number.to_string(),
+            parity: if iseven { "even" } else { "odd" }.to_string(),
+            checked_at: humantime::format_rfc3339_seconds(SystemTime::now()).to_string(),
+            source: source.to_string(),
+        }
+    }
+}
+
+/// The history file, opened for appending.
+#[derive(Debug)]
+pub struct History {
+    file: File,
+}
+
+impl History {
+    /// Opens the history at `path` for appending, creating it and its parent directories as needed.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self { file })
+    }
+
+    /// Appends `entry`. Each entry is written at once, so concurrent runs never interleave their lines.
+    pub fn record(&mut self, entry: &Entry) -> io::Result<()> {
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+        self.file.write_all(line.as_bytes())
+    }
+}
+
+/// Reads the entries in the history at `path`, oldest first. A missing file is treated as an empty history.
+pub fn read_entries(path: &Path) -> io::Result<Vec<Entry>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut entries = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        if !line.trim().is_empty() {
+            entries.push(serde_json::from_str(&line)?);
+        }
+    }
+    Ok(entries)
+}
+
+/// Arguments for the `history` subcommand.
+#[derive(Debug, Args)]
+pub struct HistoryArgs {
+    #[command(subcommand)]
+    command: HistoryCommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum HistoryCommand {
+    /// Print the recorded checks, oldest first
+    List {
+        /// Only print this many of the most recent checks
+        #[arg(short = 'n', long)]
+        limit: Option<usize>,
+    },
+    /// Print the recorded checks of the given numbers, exiting with status 1 if there are none
+    Search {
+        /// Numbers to look for
+        #[arg(required = true, allow_negative_numbers = true)]
+        numbers: Vec<String>,
+    },
+    /// Remove all recorded checks
+    Clear,
+    /// Print the location of the history file
+    Path,
+}
+
+impl HistoryArgs {
+    /// Runs the subcommand on the history at `path`. Returns `false` if a search found nothing.
+    pub fn run(&self, path: &Path) -> Result<bool, Box<dyn Error>> {
+        match &self.command {
+            HistoryCommand::Path => println!("{}", path.display()),
+            HistoryCommand::Clear => match fs::remove_file(path) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            },
+            HistoryCommand::List { limit } => {
+                let entries = read_entries(path)?;
+                let skip = limit.map_or(0, |limit| entries.len().saturating_sub(limit));
+                entries.iter().skip(skip).for_each(print_entry);
+            }
+            HistoryCommand::Search { numbers } => {
+                let found = search(read_entries(path)?, numbers);
+                found.iter().for_each(print_entry);
+                return Ok(!found.is_empty());
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// Returns the entries for any of `numbers`.
+fn search(entries: Vec<Entry>, numbers: &[String]) -> Vec<Entry> {
+    entries
+        .into_iter()
+        .filter(|entry| numbers.contains(&entry.number))
+        .collect()
+}
+
+fn print_entry(entry: &Entry) {
+    println!(
+        "{}  {}  {}  ({})",
+        entry.checked_at, entry.number, entry.parity, entry.source
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_and_search() {
+        let path =
+            std::env::temp_dir().join(format!("iseven_api_history_{}.jsonl", std::process::id()));
+        let mut history = History::open(&path).unwrap();
+        history.record(&Entry::new("42", true, "argument")).unwrap();
+        history.record(&Entry::new("7", false, "stdin")).unwrap();
+        history.record(&Entry::new("42", true, "csv")).unwrap();
+        let entries = read_entries(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[1].parity, "odd");
+        let found = search(entries, &["42".to_string()]);
+        assert_eq!(
+            found.iter().map(|e| e.source.as_str()).collect::<Vec<_>>(),
+            ["argument", "csv"]
+        );
+        assert!(read_entries(&path).unwrap().is_empty());
+    }
+}
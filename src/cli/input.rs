@@ -0,0 +1,274 @@
+//! Conversion of command line inputs into the decimal numbers the API accepts.
+
+use std::fmt::{Display, Formatter};
+
+use iseven_api::input::ParseError;
+
+use super::words;
+
+/// A number given on the command line, along with the decimal form sent to the API.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Input {
+    /// The input as given
+    pub original: String,
+    /// The number to request, in canonical decimal form
+    pub number: String,
+}
+
+impl Input {
+    /// Returns the original input if it was converted, or `None` if it is sent to the API as is.
+    pub fn converted_from(&self) -> Option<&str> {
+        (self.original != self.number).then_some(self.original.as_str())
+    }
+}
+
+/// Displays the original input, followed by the decimal number in parentheses if it was converted.
+impl Display for Input {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.converted_from() {
+            Some(original) => write!(f, "{} ({})", original, self.number),
+            None => f.write_str(&self.number),
+        }
+    }
+}
+
+/// Errors from converting an input before any request is made.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum InputError {
+    /// Not a valid number in the requested radix
+    #[error("not a valid base {0} number")]
+    InvalidDigits(u32),
+    /// Too large to convert to decimal
+    #[error("number is too large to convert")]
+    TooLarge,
+    /// Not a valid roman numeral (`--roman`)
+    #[error("not a valid roman numeral")]
+    InvalidRoman,
+    /// Not a number in English words (`--words`)
+    #[error("not a number in words")]
+    InvalidWords,
+    /// Not a decimal integer, nor in any of the other accepted notations
+    #[error(transparent)]
+    Invalid(#[from] ParseError),
+}
+
+/// Values of roman numerals, including subtractive pairs, from largest to smallest.
+const ROMAN_NUMERALS: [(&str, u32); 13] = [
+    ("M", 1000),
+    ("CM", 900),
+    ("D", 500),
+    ("CD", 400),
+    ("C", 100),
+    ("XC", 90),
+    ("L", 50),
+    ("XL", 40),
+    ("X", 10),
+    ("IX", 9),
+    ("V", 5),
+    ("IV", 4),
+    ("I", 1),
+];
+
+/// Converts inputs according to the input options.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct InputParser {
+    /// Radix of all inputs (`--radix`), overriding literal prefixes
+    pub radix: Option<u32>,
+    /// Inputs are roman numerals (`--roman`)
+    pub roman: bool,
+    /// Inputs are English words (`--words`)
+    pub words: bool,
+}
+
+impl InputParser {
+    /// Converts `input` to decimal if it is a hexadecimal (`0x`), octal (`0o`) or binary (`0b`) literal, or if a
+    /// radix is set, in which case a prefix matching the radix is allowed. Decimals and scientific notation such as
+    /// `42.0` and `4.2e1` are converted if they denote an integer, and digit separators are stripped.
+    ///
+    /// If the inputs are roman numerals or English words, they are always converted. Other inputs must be decimal
+    /// integers, or decimals denoting integers, which are [parsed](iseven_api::input::parse_lenient) into their
+    /// canonical form.
+    pub fn parse(&self, original: &str) -> Result<Input, InputError> {
+        if self.roman || self.words {
+            let number = if self.roman {
+                roman(original)?.to_string()
+            } else {
+                words::parse(original)
+                    .ok_or(InputError::InvalidWords)?
+                    .to_string()
+            };
+            return Ok(Input {
+                original: original.to_string(),
+                number,
+            });
+        }
+        let input = &*iseven_api::input::normalize(original);
+        let (negative, unsigned) = match input.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, input.strip_prefix('+').unwrap_or(input)),
+        };
+        let converted = match self.radix {
+            Some(radix) => {
+                let digits = match literal_radix(unsigned) {
+                    Some((prefix_radix, digits)) if prefix_radix == radix => digits,
+                    _ => unsigned,
+                };
+                Some(convert(digits, radix, negative)?)
+            }
+            None => literal_radix(unsigned)
+                .map(|(radix, digits)| convert(digits, radix, negative))
+                .transpose()?,
+        };
+        Ok(Input {
+            original: original.to_string(),
+            number: match converted {
+                Some(number) => number,
+                None => iseven_api::input::parse_lenient(input)?.into_owned(),
+            },
+        })
+    }
+}
+
+/// Splits a radix prefix such as `0x` from `s`, returning the radix and the remaining digits.
+fn literal_radix(s: &str) -> Option<(u32, &str)> {
+    let prefix = s.get(..2)?;
+    let radix = match prefix.to_ascii_lowercase().as_str() {
+        "0x" => 16,
+        "0o" => 8,
+        "0b" => 2,
+        _ => return None,
+    };
+    Some((radix, &s[2..]))
+}
+
+/// Converts unsigned `digits` in `radix` to a decimal string.
+fn convert(digits: &str, radix: u32, negative: bool) -> Result<String, InputError> {
+    // `from_str_radix` would accept a second sign
+    if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+        return Err(InputError::InvalidDigits(radix));
+    }
+    let value = u128::from_str_radix(digits, radix).map_err(|_| InputError::TooLarge)?;
+    Ok(signed(value.to_string(), negative))
+}
+
+/// Converts a roman numeral in standard form, such as `XLII`, to an integer. Lowercase numerals are accepted.
+fn roman(numeral: &str) -> Result<u32, InputError> {
+    let mut rest = numeral.to_ascii_uppercase();
+    let mut value = 0;
+    for (symbol, symbol_value) in ROMAN_NUMERALS {
+        while let Some(remaining) = rest.strip_prefix(symbol) {
+            value += symbol_value;
+            rest = remaining.to_string();
+        }
+    }
+    // reject non-standard forms such as `IIII` or `IC` by converting back
+    if numeral.is_empty() || !rest.is_empty() || to_roman(value) != numeral.to_ascii_uppercase() {
+        return Err(InputError::InvalidRoman);
+    }
+    Ok(value)
+}
+
+/// Converts `value` to a roman numeral in standard form.
+fn to_roman(mut value: u32) -> String {
+    let mut numeral = String::new();
+    for (symbol, symbol_value) in ROMAN_NUMERALS {
+        while value >= symbol_value {
+            numeral.push_str(symbol);
+            value -= symbol_value;
+        }
+    }
+    numeral
+}
+
+/// Adds a minus sign to the non-negative decimal `digits` if `negative`, unless they are zero.
+fn signed(digits: String, negative: bool) -> String {
+    if negative && digits != "0" {
+        format!("-{}", digits)
+    } else {
+        digits
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn number(parser: InputParser, input: &str) -> Result<String, InputError> {
+        parser.parse(input).map(|i| i.number)
+    }
+
+    #[test]
+    fn test_literals() {
+        let parser = InputParser::default();
+        assert_eq!(number(parser, "0x2A").unwrap(), "42");
+        assert_eq!(number(parser, "0o52").unwrap(), "42");
+        assert_eq!(number(parser, "0B101010").unwrap(), "42");
+        assert_eq!(number(parser, "-0x2a").unwrap(), "-42");
+        assert_eq!(number(parser, "42").unwrap(), "42");
+        assert_eq!(
+            number(parser, "abc"),
+            Err(InputError::Invalid(ParseError::InvalidCharacter {
+                character: 'a',
+                position: 1
+            }))
+        );
+        assert_eq!(number(parser, " 007").unwrap(), "7");
+        assert_eq!(number(parser, "1,000,000").unwrap(), "1000000");
+        assert_eq!(number(parser, "0xZZ"), Err(InputError::InvalidDigits(16)));
+        assert_eq!(number(parser, "0x"), Err(InputError::InvalidDigits(16)));
+    }
+
+    #[test]
+    fn test_scientific() {
+        let parser = InputParser::default();
+        assert_eq!(number(parser, "1e6").unwrap(), "1000000");
+        assert_eq!(number(parser, "4.2e1").unwrap(), "42");
+        assert_eq!(number(parser, "-4.20E+1").unwrap(), "-42");
+        assert_eq!(number(parser, "420e-1").unwrap(), "42");
+        assert_eq!(number(parser, "0.0e5").unwrap(), "0");
+        assert_eq!(number(parser, "42.0").unwrap(), "42");
+        let not_an_integer = Err(InputError::Invalid(ParseError::NotAnInteger));
+        assert_eq!(number(parser, "4.25e1"), not_an_integer);
+        assert_eq!(number(parser, "1e-1"), not_an_integer);
+        assert_eq!(
+            number(parser, "1e99999"),
+            Err(InputError::Invalid(ParseError::TooLarge))
+        );
+        assert!(matches!(number(parser, "1e"), Err(InputError::Invalid(_))));
+        assert!(matches!(number(parser, "e5"), Err(InputError::Invalid(_))));
+    }
+
+    #[test]
+    fn test_roman() {
+        let parser = InputParser {
+            roman: true,
+            ..InputParser::default()
+        };
+        assert_eq!(number(parser, "XLII").unwrap(), "42");
+        assert_eq!(number(parser, "mcmxcix").unwrap(), "1999");
+        assert_eq!(number(parser, "MMMM").unwrap(), "4000");
+        assert_eq!(
+            parser.parse("XLII").unwrap().to_string(),
+            "XLII (42)".to_string()
+        );
+        for invalid in ["IIII", "IC", "VX", "42", "", "XLIIA"] {
+            assert_eq!(number(parser, invalid), Err(InputError::InvalidRoman));
+        }
+    }
+
+    #[test]
+    fn test_radix() {
+        let parser = InputParser {
+            radix: Some(16),
+            ..InputParser::default()
+        };
+        assert_eq!(number(parser, "2A").unwrap(), "42");
+        assert_eq!(number(parser, "0x2A").unwrap(), "42");
+        assert_eq!(number(parser, "0b1").unwrap(), "177");
+        assert_eq!(number(parser, "2G"), Err(InputError::InvalidDigits(16)));
+        assert_eq!(
+            parser.parse("ff").unwrap().to_string(),
+            "ff (255)".to_string()
+        );
+    }
+}
@@ -0,0 +1,88 @@
+//! Graceful handling of Ctrl-C during batch runs.
+
+use std::{future::pending, process::exit, time::Duration};
+
+use log::warn;
+use tokio::sync::watch;
+
+/// Exit status after an interrupted run, following the shell convention of 128 + SIGINT.
+pub const EXIT_CODE: i32 = 130;
+
+/// Time given to the requests in flight to finish after Ctrl-C, before giving up on them.
+pub const GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+/// How far the handling of Ctrl-C has got.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Stage {
+    Running,
+    /// Ctrl-C was pressed, no new requests should be issued
+    Stopping,
+    /// The grace period is over or Ctrl-C was pressed again, requests in flight should be abandoned
+    Aborting,
+}
+
+/// Notification of Ctrl-C being pressed.
+///
+/// After the first Ctrl-C, the batch should stop issuing new requests. If the requests in flight have not finished
+/// within the grace period, or Ctrl-C is pressed again, the batch should abandon them and wrap up, still writing its
+/// summary, cache and output. Only a third Ctrl-C exits the process immediately with [`EXIT_CODE`].
+#[derive(Debug, Clone)]
+pub struct Interrupt {
+    stage: watch::Receiver<Stage>,
+}
+
+impl Interrupt {
+    /// Starts listening for Ctrl-C in a background task on the current Tokio runtime.
+    pub fn install(grace_period: Duration) -> Self {
+        let (sender, stage) = watch::channel(Stage::Running);
+        tokio::spawn(async move {
+            if let Err(e) = tokio::signal::ctrl_c().await {
+                warn!("Unable to listen for Ctrl-C: {}", e);
+                // without a handler, Ctrl-C keeps its default behaviour of killing the process
+                return;
+            }
+            sender.send_replace(Stage::Stopping);
+            eprintln!(
+                "Interrupted, waiting for the requests in flight to finish (press Ctrl-C again to stop waiting)"
+            );
+            tokio::select! {
+                _ = tokio::time::sleep(grace_period) => {}
+                _ = tokio::signal::ctrl_c() => {}
+            }
+            sender.send_replace(Stage::Aborting);
+            if tokio::signal::ctrl_c().await.is_ok() {
+                exit(EXIT_CODE)
+            }
+            // keeps the sender alive, as waiting on a closed channel returns at once
+            pending::<()>().await
+        });
+        Self { stage }
+    }
+
+    /// Returns `true` if Ctrl-C has been pressed.
+    pub fn is_set(&self) -> bool {
+        *self.stage.borrow() >= Stage::Stopping
+    }
+
+    /// Waits until Ctrl-C is pressed, and no new requests should be issued.
+    pub async fn stopped(&self) {
+        self.reached(Stage::Stopping).await
+    }
+
+    /// Waits until the requests in flight should be abandoned.
+    pub async fn aborted(&self) {
+        self.reached(Stage::Aborting).await
+    }
+
+    async fn reached(&self, stage: Stage) {
+        let mut receiver = self.stage.clone();
+        if receiver
+            .wait_for(|current| *current >= stage)
+            .await
+            .is_err()
+        {
+            // Ctrl-C could not be listened for, so it is never reported
+            pending().await
+        }
+    }
+}
@@ -0,0 +1,152 @@
+//! Localized messages for human-readable output (`--lang`).
+
+use std::{env, sync::OnceLock};
+
+/// Messages in one language.
+#[derive(Debug)]
+pub struct Messages {
+    /// Language code, e.g. `en`
+    pub code: &'static str,
+    /// Word for even numbers
+    pub even: &'static str,
+    /// Word for odd numbers
+    pub odd: &'static str,
+    /// Sentence stating the parity of a number, with `{number}` and `{parity}` placeholders
+    pub parity_sentence: &'static str,
+    /// Heading above even numbers (`--group-by parity`)
+    pub even_heading: &'static str,
+    /// Heading above odd numbers (`--group-by parity`)
+    pub odd_heading: &'static str,
+    /// Heading above failures (`--group-by parity`)
+    pub errors_heading: &'static str,
+    /// Label before advertisements
+    pub advertisement: &'static str,
+    /// Marker for results computed offline
+    pub offline: &'static str,
+    /// Prefix of error messages
+    pub error: &'static str,
+}
+
+impl Messages {
+    /// Returns the word for the given parity.
+    pub fn parity(&self, iseven: bool) -> &'static str {
+        if iseven {
+            self.even
+        } else {
+            self.odd
+        }
+    }
+
+    /// Returns the sentence stating that `number` has the (possibly colored) `parity`.
+    pub fn parity_sentence(&self, number: &str, parity: &str) -> String {
+        self.parity_sentence
+            .replace("{number}", number)
+            .replace("{parity}", parity)
+    }
+}
+
+/// English, also used for unsupported languages.
+pub const ENGLISH: Messages = Messages {
+    code: "en",
+    even: "even",
+    odd: "odd",
+    parity_sentence: "{number} is an {parity} number",
+    even_heading: "Even",
+    odd_heading: "Odd",
+    errors_heading: "Errors",
+    advertisement: "Advertisement",
+    offline: "offline",
+    error: "error",
+};
+
+/// Bundled languages.
+pub const LANGUAGES: [&Messages; 4] = [
+    &ENGLISH,
+    &Messages {
+        code: "de",
+        even: "gerade",
+        odd: "ungerade",
+        parity_sentence: "{number} ist eine {parity} Zahl",
+        even_heading: "Gerade",
+        odd_heading: "Ungerade",
+        errors_heading: "Fehler",
+        advertisement: "Werbung",
+        offline: "offline",
+        error: "Fehler",
+    },
+    &Messages {
+        code: "es",
+        even: "par",
+        odd: "impar",
+        parity_sentence: "{number} es un número {parity}",
+        even_heading: "Pares",
+        odd_heading: "Impares",
+        errors_heading: "Errores",
+        advertisement: "Anuncio",
+        offline: "sin conexión",
+        error: "error",
+    },
+    &Messages {
+        code: "fr",
+        even: "pair",
+        odd: "impair",
+        parity_sentence: "{number} est un nombre {parity}",
+        even_heading: "Pairs",
+        odd_heading: "Impairs",
+        errors_heading: "Erreurs",
+        advertisement: "Publicité",
+        offline: "hors ligne",
+        error: "erreur",
+    },
+];
+
+static CURRENT: OnceLock<&'static Messages> = OnceLock::new();
+
+/// Returns the messages for a locale such as `de`, `de_DE` or `de_DE.UTF-8`, if the language is bundled.
+pub fn find(locale: &str) -> Option<&'static Messages> {
+    let code = locale
+        .split(['_', '-', '.', '@'])
+        .next()
+        .unwrap_or_default();
+    LANGUAGES
+        .into_iter()
+        .find(|messages| messages.code.eq_ignore_ascii_case(code))
+}
+
+/// Returns the messages for the language set in the environment (`LC_ALL`, `LC_MESSAGES` or `LANG`), falling back to
+/// English.
+pub fn from_env() -> &'static Messages {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .into_iter()
+        .filter_map(|var| env::var(var).ok().filter(|v| !v.is_empty()))
+        .next()
+        .and_then(|locale| find(&locale))
+        .unwrap_or(&ENGLISH)
+}
+
+/// Sets the messages returned by [`current`]. Only the first call has an effect.
+pub fn set(messages: &'static Messages) {
+    let _ = CURRENT.set(messages);
+}
+
+/// Returns the messages set with [`set`], or English if none were set.
+pub fn current() -> &'static Messages {
+    CURRENT.get().copied().unwrap_or(&ENGLISH)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find() {
+        assert_eq!(find("de_DE.UTF-8").unwrap().code, "de");
+        assert_eq!(find("FR").unwrap().code, "fr");
+        assert_eq!(find("en-GB").unwrap().code, "en");
+        assert!(find("C").is_none());
+        assert_eq!(
+            find("es").unwrap().parity_sentence("42", "par"),
+            "42 es un número par"
+        );
+    }
+}
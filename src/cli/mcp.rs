@@ -0,0 +1,186 @@
+//! [Model Context Protocol](https://modelcontextprotocol.io/) server over stdio (`mcp` subcommand).
+//!
+//! Messages are JSON-RPC 2.0 objects, one per line. The server offers a single `is_even` tool backed by the library
+//! client, so assistants get the same validation, retries and rate limiting as the command line app. Nothing but
+//! protocol messages is written to stdout; logs go to stderr.
+
+use std::error::Error;
+
+use iseven_api::{IsEvenApiClient, IsEvenApiClientBuilder};
+use log::debug;
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+/// Protocol versions understood by the server, newest last.
+const PROTOCOL_VERSIONS: [&str; 3] = ["2024-11-05", "2025-03-26", "2025-06-18"];
+
+/// JSON-RPC error codes.
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// Builds a client from `builder` and answers requests on stdin until it is closed.
+pub async fn run(builder: IsEvenApiClientBuilder) -> Result<(), Box<dyn Error>> {
+    let client = builder.build()?;
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    let mut stdout = tokio::io::stdout();
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str::<Value>(&line) {
+            Ok(message) => handle(&client, message).await,
+            Err(e) => Some(error(Value::Null, PARSE_ERROR, &e.to_string())),
+        };
+        if let Some(response) = response {
+            let mut text = serde_json::to_string(&response)?;
+            text.push('\n');
+            stdout.write_all(text.as_bytes()).await?;
+            stdout.flush().await?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns the response to `message`, or `None` if it is a notification.
+async fn handle(client: &IsEvenApiClient, message: Value) -> Option<Value> {
+    let id = message.get("id").cloned();
+    let Some(method) = message.get("method").and_then(Value::as_str) else {
+        // the server never sends requests, so any responses from the client are ignored
+        if message.get("result").is_some() || message.get("error").is_some() {
+            return None;
+        }
+        return Some(error(
+            id.unwrap_or(Value::Null),
+            INVALID_REQUEST,
+            "missing method",
+        ));
+    };
+    debug!("Received {}", method);
+    // notifications, such as notifications/initialized, need no answer
+    let id = id?;
+    let params = message.get("params").cloned().unwrap_or(Value::Null);
+    let result = match method {
+        "initialize" => initialize(&params),
+        "ping" => json!({}),
+        "tools/list" => json!({ "tools": [is_even_tool()] }),
+        "tools/call" => match call_tool(client, &params).await {
+            Ok(result) => result,
+            Err(message) => return Some(error(id, INVALID_PARAMS, &message)),
+        },
+        _ => {
+            return Some(error(
+                id,
+                METHOD_NOT_FOUND,
+                &format!("unknown method {}", method),
+            ))
+        }
+    };
+    Some(json!({ "jsonrpc": "2.0", "id": id, "result": result }))
+}
+
+fn error(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+/// Answers `initialize` with the client's protocol version if supported, or the newest one otherwise.
+fn initialize(params: &Value) -> Value {
+    let requested = params.get("protocolVersion").and_then(Value::as_str);
+    let version = requested
+        .filter(|version| PROTOCOL_VERSIONS.contains(version))
+        .unwrap_or(PROTOCOL_VERSIONS[PROTOCOL_VERSIONS.len() - 1]);
+    json!({
+        "protocolVersion": version,
+        "capabilities": { "tools": {} },
+        "serverInfo": { "name": "iseven_api", "version": env!("CARGO_PKG_VERSION") },
+    })
+}
+
+fn is_even_tool() -> Value {
+    json!({
+        "name": "is_even",
+        "description": "Checks whether an integer is even using isEven API. The free plan only accepts numbers \
+                        from 0 to 999999.",
+        "inputSchema": {
+            "type": "object",
+            "properties": {
+                "number": {
+                    "type": ["integer", "string"],
+                    "description": "Integer to check, as a JSON number or a decimal string",
+                },
+            },
+            "required": ["number"],
+        },
+    })
+}
+
+/// Runs a tool call. Errors from the API are reported in the result so the assistant can see them; only calls which
+/// do not match the tool's schema are protocol errors.
+async fn call_tool(client: &IsEvenApiClient, params: &Value) -> Result<Value, String> {
+    let name = params.get("name").and_then(Value::as_str);
+    if name != Some("is_even") {
+        return Err(format!("unknown tool {}", name.unwrap_or("(none)")));
+    }
+    let number = match params.get("arguments").and_then(|args| args.get("number")) {
+        Some(Value::Number(n)) if n.is_i64() || n.is_u64() => n.to_string(),
+        Some(Value::String(s)) => s.trim().to_string(),
+        _ => return Err("argument \"number\" must be an integer or a string".to_string()),
+    };
+    Ok(match client.get_str(&number).await {
+        Ok(response) => json!({
+            "content": [{
+                "type": "text",
+                "text": format!("{} is {}", number, if response.iseven() { "even" } else { "odd" }),
+            }],
+            "structuredContent": { "number": number, "iseven": response.iseven(), "ad": response.ad() },
+            "isError": false,
+        }),
+        Err(e) => json!({
+            "content": [{ "type": "text", "text": format!("Unable to check {}: {}", number, e) }],
+            "isError": true,
+        }),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_handle() {
+        let client = IsEvenApiClient::new();
+        let response = handle(
+            &client,
+            json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize",
+                    "params": { "protocolVersion": "2024-11-05" } }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response["result"]["protocolVersion"], "2024-11-05");
+
+        let response = handle(
+            &client,
+            json!({ "jsonrpc": "2.0", "id": 2, "method": "tools/list" }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response["result"]["tools"][0]["name"], "is_even");
+
+        let notification = json!({ "jsonrpc": "2.0", "method": "notifications/initialized" });
+        assert!(handle(&client, notification).await.is_none());
+
+        let response = handle(
+            &client,
+            json!({ "jsonrpc": "2.0", "id": "x", "method": "foo" }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response["error"]["code"], METHOD_NOT_FOUND);
+
+        let call = json!({ "jsonrpc": "2.0", "id": 3, "method": "tools/call",
+                           "params": { "name": "is_even", "arguments": { "number": 1.5 } } });
+        let response = handle(&client, call).await.unwrap();
+        assert_eq!(response["error"]["code"], INVALID_PARAMS);
+    }
+}
@@ -0,0 +1,174 @@
+//! Request counters of the proxy server, exported in the Prometheus text format (`serve` `/metrics` endpoint).
+
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use iseven_api::{IsEvenApiError, ResponseCache};
+
+use super::stats::error_kind;
+
+/// Counters updated by every parity check answered by the proxy.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    inner: Mutex<Counters>,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    /// Checks by outcome: `ok` or the error kind
+    requests: BTreeMap<&'static str, u64>,
+    cache_hits: u64,
+    cache_misses: u64,
+    /// Total time spent waiting for upstream, for checks which missed the cache
+    upstream_seconds: f64,
+    /// Whether upstream was reachable when last contacted, and when that was
+    upstream: Option<(bool, Instant)>,
+}
+
+impl Metrics {
+    /// Records a check which was answered from the cache.
+    pub fn record_hit(&self) {
+        let mut counters = self.inner.lock().unwrap();
+        counters.cache_hits += 1;
+        *counters.requests.entry("ok").or_default() += 1;
+    }
+
+    /// Records a check which was forwarded upstream, taking `latency`.
+    pub fn record_miss<T>(&self, result: &Result<T, IsEvenApiError>, latency: Duration) {
+        let mut counters = self.inner.lock().unwrap();
+        counters.cache_misses += 1;
+        counters.upstream_seconds += latency.as_secs_f64();
+        let outcome = result.as_ref().err().map_or("ok", error_kind);
+        *counters.requests.entry(outcome).or_default() += 1;
+        counters.upstream = Some((reachable(result), Instant::now()));
+    }
+
+    /// Records whether upstream was reachable in a readiness probe.
+    pub fn record_probe(&self, reachable: bool) {
+        self.inner.lock().unwrap().upstream = Some((reachable, Instant::now()));
+    }
+
+    /// Returns whether upstream was reachable when last contacted, if that was at most `max_age` ago.
+    pub fn upstream_status(&self, max_age: Duration) -> Option<bool> {
+        self.inner
+            .lock()
+            .unwrap()
+            .upstream
+            .filter(|(_, at)| at.elapsed() <= max_age)
+            .map(|(reachable, _)| reachable)
+    }
+
+    /// Returns the metrics, along with the state of `cache`, in the Prometheus text exposition format.
+    pub fn render(&self, cache: &ResponseCache) -> String {
+        let counters = self.inner.lock().unwrap();
+        let mut out = String::new();
+        metric(
+            &mut out,
+            "iseven_proxy_requests_total",
+            "counter",
+            "Parity checks answered by the proxy, by outcome.",
+        );
+        for (outcome, count) in &counters.requests {
+            let _ = writeln!(
+                out,
+                "iseven_proxy_requests_total{{outcome=\"{}\"}} {}",
+                outcome, count
+            );
+        }
+        let samples = [
+            (
+                "iseven_proxy_cache_hits_total",
+                "counter",
+                "Checks answered from the cache.",
+                counters.cache_hits as f64,
+            ),
+            (
+                "iseven_proxy_cache_misses_total",
+                "counter",
+                "Checks forwarded to the API.",
+                counters.cache_misses as f64,
+            ),
+            (
+                "iseven_proxy_cache_entries",
+                "gauge",
+                "Responses currently in the cache.",
+                cache.len() as f64,
+            ),
+            (
+                "iseven_proxy_cache_evictions_total",
+                "counter",
+                "Responses evicted from the cache to make room for others.",
+                cache.stats().evictions() as f64,
+            ),
+            (
+                "iseven_proxy_cache_capacity",
+                "gauge",
+                "Maximum number of responses in the cache.",
+                cache.capacity() as f64,
+            ),
+            (
+                "iseven_proxy_upstream_duration_seconds_total",
+                "counter",
+                "Total time spent waiting for the API.",
+                counters.upstream_seconds,
+            ),
+        ];
+        for (name, kind, help, value) in samples {
+            metric(&mut out, name, kind, help);
+            let _ = writeln!(out, "{} {}", name, value);
+        }
+        if let Some((reachable, _)) = counters.upstream {
+            metric(
+                &mut out,
+                "iseven_proxy_upstream_up",
+                "gauge",
+                "Whether the API was reachable when last contacted.",
+            );
+            let _ = writeln!(out, "iseven_proxy_upstream_up {}", u8::from(reachable));
+        }
+        out
+    }
+}
+
+/// Returns `true` unless `result` shows that the API could not be reached or failed with a server error. Other error
+/// responses, such as for invalid numbers, count as reachable.
+pub fn reachable<T>(result: &Result<T, IsEvenApiError>) -> bool {
+    match result {
+        Err(e @ IsEvenApiError::Transport(_)) => e.status().is_some_and(|s| !s.is_server_error()),
+        _ => true,
+    }
+}
+
+fn metric(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let metrics = Metrics::default();
+        let cache = ResponseCache::new(10);
+        assert!(!metrics.render(&cache).contains("upstream_up"));
+
+        metrics.record_hit();
+        metrics.record_miss(&Ok(()), Duration::from_millis(250));
+        metrics.record_miss(&Ok(()), Duration::from_millis(250));
+        let text = metrics.render(&cache);
+        assert!(text.contains("iseven_proxy_requests_total{outcome=\"ok\"} 3\n"));
+        assert!(text.contains("iseven_proxy_cache_hits_total 1\n"));
+        assert!(text.contains("iseven_proxy_cache_misses_total 2\n"));
+        assert!(text.contains("iseven_proxy_cache_evictions_total 0\n"));
+        assert!(text.contains("iseven_proxy_cache_capacity 10\n"));
+        assert!(text.contains("iseven_proxy_upstream_duration_seconds_total 0.5\n"));
+        assert!(text.contains("iseven_proxy_upstream_up 1\n"));
+        assert_eq!(metrics.upstream_status(Duration::from_secs(60)), Some(true));
+    }
+}
@@ -0,0 +1,48 @@
+//! Mock isEven API server (`mock-server` subcommand).
+
+use std::{
+    error::Error,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+};
+
+use clap::{
+    builder::{PossibleValuesParser, TypedValueParser},
+    Args,
+};
+use iseven_api::{mock::MockServer, test_util::AdGenerator, Plan};
+
+/// Arguments for the `mock-server` subcommand.
+#[derive(Debug, Args)]
+pub struct MockServerArgs {
+    /// Port to listen on
+    #[arg(long, default_value_t = 8080)]
+    port: u16,
+
+    /// Address to listen on
+    #[arg(long, default_value_t = IpAddr::V4(Ipv4Addr::LOCALHOST))]
+    bind: IpAddr,
+
+    /// Pricing plan determining which numbers are in range
+    #[arg(
+        long,
+        default_value_t = Plan::Free,
+        value_parser = PossibleValuesParser::new(Plan::ALL.map(|p| p.name())).map(|s| s.parse::<Plan>().unwrap())
+    )]
+    plan: Plan,
+
+    /// Seed for the fake ads, which are the same for every run with the same seed
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+}
+
+impl MockServerArgs {
+    /// Runs the mock server until interrupted with Ctrl-C.
+    pub async fn run(&self) -> Result<(), Box<dyn Error>> {
+        let addr = SocketAddr::new(self.bind, self.port);
+        let server =
+            MockServer::start_with_ads(addr, self.plan, AdGenerator::new(self.seed)).await?;
+        eprintln!("Mock server listening on {}", server.url());
+        tokio::signal::ctrl_c().await?;
+        Ok(())
+    }
+}
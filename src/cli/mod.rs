@@ -0,0 +1,39 @@
+//! Support modules for the command line app.
+
+pub mod ads;
+pub mod bench;
+pub mod cache;
+pub mod client;
+pub mod clipboard;
+pub mod color;
+pub mod config;
+pub mod csv;
+pub mod curl;
+#[cfg(unix)]
+pub mod daemon;
+pub mod doctor;
+pub mod exit_codes;
+pub mod failure;
+pub mod grpc;
+pub mod history;
+pub mod input;
+pub mod interrupt;
+pub mod locale;
+pub mod mcp;
+pub mod metrics;
+pub mod mock_server;
+pub mod offline;
+pub mod output;
+pub mod ping;
+pub mod rate;
+#[cfg(feature = "self-update")]
+pub mod self_update;
+pub mod serve;
+pub mod sort;
+pub mod source;
+pub mod stats;
+pub mod template;
+pub mod tui;
+pub mod verify;
+pub mod watch;
+pub mod words;
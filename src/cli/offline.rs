@@ -0,0 +1,26 @@
+//! Answering and validating checks without network access (`--offline` and `--dry-run`).
+
+use iseven_api::{
+    mock, ApiError, IsEvenApiError, IsEvenApiResponse, Plan, ProtocolError, ResponseCache,
+};
+
+/// Answers a check for `number` from `cache` if possible, or else by computing the response the API would give for
+/// `plan`. Computed responses are not cached, as they never came from the API.
+pub fn check(
+    number: &str,
+    plan: Plan,
+    cache: Option<&ResponseCache>,
+) -> Result<IsEvenApiResponse, IsEvenApiError> {
+    if let Some(response) = cache.and_then(|cache| cache.get(number)) {
+        return Ok(response);
+    }
+    mock::respond(number, plan).map_err(|(status, e)| match ApiError::from_status(status, e) {
+        Ok(e) => e.into(),
+        Err(e) => ProtocolError::UnexpectedStatus(e, status).into(),
+    })
+}
+
+/// Checks that `number` is valid and within the range of `plan`, returning the error the API would give otherwise.
+pub fn validate(number: &str, plan: Plan) -> Result<(), IsEvenApiError> {
+    check(number, plan, None).map(|_| ())
+}
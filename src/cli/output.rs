@@ -0,0 +1,292 @@
+//! Printing of results in the various output formats.
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufWriter, Stdout, Write},
+    path::Path,
+    time::Duration,
+};
+
+use clap::ValueEnum;
+use iseven_api::{IsEvenApiError, IsEvenApiResponse};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    color::{ColorChoice, Colors},
+    csv,
+    input::Input,
+    locale::Messages,
+    template::Template,
+};
+
+/// Output formats supported by the command line app.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    /// Human-readable text
+    Human,
+    /// Raw JSON response from the API
+    Json,
+    /// One JSON object per line, printed as each result completes
+    Jsonl,
+    /// YAML sequence with one entry per number
+    Yaml,
+    /// TOML array of tables with one `[[results]]` entry per number
+    Toml,
+}
+
+/// Successful outcome of checking a number.
+#[derive(Debug, Clone)]
+pub enum Outcome {
+    /// Parsed API response
+    Response(IsEvenApiResponse),
+    /// Raw JSON body as returned by the API (`--format json`), which may also be an API error response
+    Raw {
+        /// Response body
+        body: String,
+        /// Parity, if the body is a successful response
+        iseven: Option<bool>,
+    },
+}
+
+impl Outcome {
+    /// Creates an [`Outcome::Raw`] from a response body.
+    pub fn raw(body: String) -> Self {
+        let iseven = serde_json::from_str::<IsEvenApiResponse>(&body)
+            .ok()
+            .map(|r| r.iseven());
+        Self::Raw { body, iseven }
+    }
+
+    /// Returns the parity of the number, if known.
+    pub fn iseven(&self) -> Option<bool> {
+        match self {
+            Outcome::Response(r) => Some(r.iseven()),
+            Outcome::Raw { iseven, .. } => *iseven,
+        }
+    }
+}
+
+/// A single result in the structured output formats.
+#[derive(Serialize)]
+struct Record<'a> {
+    number: &'a str,
+    /// Original input, if it was converted to decimal
+    #[serde(skip_serializing_if = "Option::is_none")]
+    input: Option<&'a str>,
+    #[serde(flatten)]
+    response: &'a IsEvenApiResponse,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    offline: bool,
+}
+
+/// A response in the JSON format, marked if it was computed offline.
+#[derive(Serialize)]
+struct JsonResponse<'a> {
+    #[serde(flatten)]
+    response: &'a IsEvenApiResponse,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    offline: bool,
+}
+
+/// Wrapper giving TOML output a top-level `results` key, as TOML has no top-level arrays.
+#[derive(Serialize)]
+struct TomlResults<'a> {
+    results: [&'a Record<'a>; 1],
+}
+
+/// Prints results according to the output options.
+#[derive(Debug)]
+pub struct Printer {
+    /// Output format
+    pub format: Format,
+    /// Custom output template, overriding the format
+    pub template: Option<Template>,
+    /// Only print the parity
+    pub quiet: bool,
+    /// Omit advertisements from human-readable output
+    pub no_ad: bool,
+    /// Only print advertisements (`--ads-only`)
+    pub ads_only: bool,
+    /// Palette for the output
+    pub colors: Colors,
+    /// Mark results as computed offline (`--offline`)
+    pub offline: bool,
+    /// Language of human-readable output
+    pub messages: &'static Messages,
+    /// Where results are written
+    pub out: Output,
+}
+
+impl Printer {
+    /// Returns `true` if failures are rendered to the output by [`Self::print`] rather than reported as errors.
+    pub fn prints_errors(&self) -> bool {
+        self.template.is_some()
+    }
+
+    /// Prints the result of checking `input`.
+    pub fn print(
+        &mut self,
+        input: &Input,
+        result: &Result<Outcome, IsEvenApiError>,
+        latency: Duration,
+    ) -> io::Result<()> {
+        if let Some(template) = &self.template {
+            let result = match result {
+                Ok(Outcome::Response(r)) => Ok(r),
+                Ok(Outcome::Raw { .. }) => unreachable!("templates use parsed responses"),
+                Err(e) => Err(e),
+            };
+            let line = template.render(&input.to_string(), result, latency);
+            return writeln!(self.out, "{}", line);
+        }
+        match result {
+            Ok(Outcome::Raw { body, .. }) => writeln!(self.out, "{}", body),
+            Ok(Outcome::Response(response)) => self.print_response(input, response),
+            Err(_) => Ok(()),
+        }
+    }
+
+    /// Prints a heading above a group of results (`--group-by`), separated from the previous group by a blank line
+    /// unless it is the `first`.
+    pub fn print_heading(&mut self, heading: &str, first: bool) -> io::Result<()> {
+        if !first {
+            writeln!(self.out)?;
+        }
+        writeln!(self.out, "{}:", heading)
+    }
+
+    /// Prints a CSV row with `parity` appended as an extra field (`--csv`).
+    pub fn print_csv_row(&mut self, row: &[String], parity: &str) -> io::Result<()> {
+        let record = csv::format_record(row.iter().map(String::as_str).chain([parity]));
+        writeln!(self.out, "{}", record)
+    }
+
+    /// Flushes the output, making sure results written to a file are on disk.
+    pub fn finish(self) -> io::Result<()> {
+        self.out.finish()
+    }
+
+    fn print_response(&mut self, input: &Input, response: &IsEvenApiResponse) -> io::Result<()> {
+        let record = Record {
+            number: &input.number,
+            input: input.converted_from(),
+            response,
+            offline: self.offline,
+        };
+        match self.format {
+            _ if self.ads_only => writeln!(self.out, "{}", response.ad()),
+            Format::Human if self.quiet => {
+                writeln!(self.out, "{}", self.colors.parity(response.iseven()))
+            }
+            Format::Human => {
+                if !self.no_ad {
+                    writeln!(
+                        self.out,
+                        "{}: {}",
+                        self.messages.advertisement,
+                        response.ad()
+                    )?;
+                }
+                let iseven = response.iseven();
+                let parity = self
+                    .colors
+                    .parity_word(iseven, self.messages.parity(iseven));
+                write!(
+                    self.out,
+                    "{}",
+                    self.messages.parity_sentence(&input.to_string(), &parity)
+                )?;
+                if self.offline {
+                    write!(self.out, " ({})", self.messages.offline)?;
+                }
+                writeln!(self.out)
+            }
+            Format::Json => writeln!(
+                self.out,
+                "{}",
+                serde_json::to_string(&JsonResponse {
+                    response,
+                    offline: self.offline
+                })
+                .expect("Unable to serialize response")
+            ),
+            Format::Jsonl => writeln!(
+                self.out,
+                "{}",
+                serde_json::to_string(&record).expect("Unable to serialize response")
+            ),
+            Format::Yaml => writeln!(
+                self.out,
+                "{}",
+                serde_yaml::to_string(&[record])
+                    .expect("Unable to serialize response")
+                    .trim_end()
+            ),
+            Format::Toml => writeln!(
+                self.out,
+                "{}",
+                toml::to_string(&TomlResults { results: [&record] })
+                    .expect("Unable to serialize response")
+            ),
+        }
+    }
+}
+
+/// Destination of the results: stdout, or a file given with `--output`.
+#[derive(Debug)]
+pub enum Output {
+    /// Standard output
+    Stdout(Stdout),
+    /// A file, buffered until the run finishes
+    File(BufWriter<File>),
+}
+
+impl Output {
+    /// Opens `path` for writing results, creating parent directories as needed. The file is truncated unless
+    /// `append` is set.
+    pub fn create(path: &Path, append: bool) -> io::Result<Self> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .append(append)
+            .truncate(!append)
+            .open(path)?;
+        Ok(Self::File(BufWriter::new(file)))
+    }
+
+    /// Returns the palette to use for this output.
+    pub fn colors(&self, choice: ColorChoice) -> Colors {
+        match self {
+            Output::Stdout(stdout) => choice.for_stream(stdout),
+            Output::File(file) => choice.for_stream(file.get_ref()),
+        }
+    }
+
+    /// Flushes the output, and syncs a file to disk.
+    pub fn finish(self) -> io::Result<()> {
+        match self {
+            Output::Stdout(mut stdout) => stdout.flush(),
+            Output::File(file) => file.into_inner()?.sync_all(),
+        }
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Output::Stdout(stdout) => stdout.write(buf),
+            Output::File(file) => file.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Output::Stdout(stdout) => stdout.flush(),
+            Output::File(file) => file.flush(),
+        }
+    }
+}
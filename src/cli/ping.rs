@@ -0,0 +1,121 @@
+//! Repeated latency checks (`ping` subcommand).
+
+use std::{
+    error::Error,
+    fmt::{Display, Formatter},
+    time::{Duration, Instant},
+};
+
+use clap::Args;
+use iseven_api::{IsEvenApiClient, IsEvenApiClientBuilder};
+
+/// Arguments for the `ping` subcommand.
+#[derive(Debug, Args)]
+pub struct PingArgs {
+    /// Stop after this many requests [default: until interrupted]
+    #[arg(short, long)]
+    count: Option<u64>,
+
+    /// Time to wait between requests, e.g. "1s" or "200ms"
+    #[arg(short, long, value_parser = humantime::parse_duration, default_value = "1s")]
+    interval: Duration,
+
+    /// Number to check in every request
+    #[arg(long, default_value = "42")]
+    number: String,
+}
+
+impl PingArgs {
+    /// Builds a client from `builder` and pings the API until the count is reached or Ctrl-C is pressed, then prints
+    /// a summary. Returns `false` if no request succeeded.
+    pub async fn run(&self, builder: IsEvenApiClientBuilder) -> Result<bool, Box<dyn Error>> {
+        let client = builder.build()?;
+        let mut summary = Summary::default();
+        tokio::select! {
+            _ = self.ping(&client, &mut summary) => {}
+            _ = tokio::signal::ctrl_c() => {}
+        }
+        println!();
+        println!("{}", summary);
+        Ok(summary.received > 0)
+    }
+
+    async fn ping(&self, client: &IsEvenApiClient, summary: &mut Summary) {
+        let mut seq = 0;
+        loop {
+            seq += 1;
+            let start = Instant::now();
+            let result = client.get_str(&self.number).await;
+            let latency = start.elapsed();
+            match result {
+                Ok(response) => {
+                    summary.record(Some(latency));
+                    println!(
+                        "{} is {}: seq={} time={:.1} ms",
+                        self.number,
+                        if response.iseven() { "even" } else { "odd" },
+                        seq,
+                        latency.as_secs_f64() * 1000.0
+                    );
+                }
+                Err(e) => {
+                    summary.record(None);
+                    println!("{}: seq={} error: {}", self.number, seq, e);
+                }
+            }
+            if self.count.is_some_and(|count| seq >= count) {
+                break;
+            }
+            tokio::time::sleep(self.interval).await;
+        }
+    }
+}
+
+/// Counters printed when pinging stops.
+#[derive(Debug, Default)]
+struct Summary {
+    sent: u64,
+    received: u64,
+    min: Option<Duration>,
+    max: Option<Duration>,
+    total: Duration,
+}
+
+impl Summary {
+    /// Records a request, with its latency if it succeeded.
+    fn record(&mut self, latency: Option<Duration>) {
+        self.sent += 1;
+        if let Some(latency) = latency {
+            self.received += 1;
+            self.total += latency;
+            self.min = Some(self.min.map_or(latency, |min| min.min(latency)));
+            self.max = Some(self.max.map_or(latency, |max| max.max(latency)));
+        }
+    }
+}
+
+impl Display for Summary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let loss = if self.sent == 0 {
+            0.0
+        } else {
+            (self.sent - self.received) as f64 / self.sent as f64 * 100.0
+        };
+        write!(
+            f,
+            "{} requests, {} succeeded, {:.0}% failed",
+            self.sent, self.received, loss
+        )?;
+        if let (Some(min), Some(max)) = (self.min, self.max) {
+            let avg = self.total.as_secs_f64() / self.received as f64;
+            write!(
+                f,
+                "\nlatency min/avg/max = {:.1}/{:.1}/{:.1} ms",
+                min.as_secs_f64() * 1000.0,
+                avg * 1000.0,
+                max.as_secs_f64() * 1000.0
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,75 @@
+//! Request rates such as `5/s`, given on the command line.
+
+use std::{str::FromStr, time::Duration};
+
+use iseven_api::RateLimiter;
+
+/// A number of requests per time period.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rate {
+    requests: u32,
+    per: Duration,
+}
+
+impl Rate {
+    /// Creates a [`RateLimiter`] enforcing this rate.
+    pub fn limiter(&self) -> RateLimiter {
+        RateLimiter::new(self.requests, self.per)
+    }
+}
+
+impl FromStr for Rate {
+    type Err = String;
+
+    /// Parses a rate in the form `<requests>/<period>`, where the period is `s`, `m` or `h`, or a duration such as
+    /// `10s`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (requests, period) = s
+            .split_once('/')
+            .ok_or_else(|| "expected a rate such as `5/s`".to_string())?;
+        let requests = requests
+            .trim()
+            .parse::<u32>()
+            .map_err(|e| format!("invalid number of requests: {}", e))?;
+        if requests == 0 {
+            return Err("number of requests must be greater than 0".to_string());
+        }
+        let per = match period.trim() {
+            "s" | "sec" => Duration::from_secs(1),
+            "m" | "min" => Duration::from_secs(60),
+            "h" | "hour" => Duration::from_secs(3600),
+            period => humantime::parse_duration(period)
+                .map_err(|e| format!("invalid period `{}`: {}", period, e))?,
+        };
+        if per.is_zero() {
+            return Err("period must be greater than 0".to_string());
+        }
+        Ok(Self { requests, per })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            "5/s".parse(),
+            Ok(Rate {
+                requests: 5,
+                per: Duration::from_secs(1)
+            })
+        );
+        assert_eq!(
+            "100/10m".parse(),
+            Ok(Rate {
+                requests: 100,
+                per: Duration::from_secs(600)
+            })
+        );
+        assert!("5".parse::<Rate>().is_err());
+        assert!("0/s".parse::<Rate>().is_err());
+        assert!("5/0s".parse::<Rate>().is_err());
+    }
+}
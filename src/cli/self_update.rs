@@ -0,0 +1,400 @@
+//! Updating the binary from GitHub releases (`self-update` subcommand).
+//!
+//! Each release is expected to have a prebuilt binary named `iseven_api-<target>` (with `.exe` on Windows) for every
+//! supported target, along with its SHA-256 checksum in `iseven_api-<target>.sha256`, in the format written by
+//! `sha256sum`, and its signature in `iseven_api-<target>.minisig`. These are published by the `release` job of the
+//! build workflow.
+//!
+//! The checksum only guards against a corrupted download, as it comes from the same place as the binary. The
+//! signature is what shows the binary was built by the project: it is made with
+//! [minisign](https://jedisct1.github.io/minisign/) in legacy mode (`minisign -S -l`), which signs the file itself
+//! with Ed25519, and checked against the release signing key built into the binary from `ISEVEN_API_RELEASE_KEY`.
+//! Its trusted comment names the release and asset, so a signed binary cannot be passed off as another release or
+//! another platform's binary. Binaries built without the key can check for updates but not install them.
+
+use std::{
+    cmp::Ordering,
+    env::consts::{ARCH, EXE_SUFFIX, OS},
+    error::Error,
+    fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use clap::Args;
+use ring::{
+    digest::{digest, SHA256},
+    signature::{UnparsedPublicKey, ED25519},
+};
+use serde::Deserialize;
+
+/// GitHub API endpoint of the latest release.
+const LATEST_RELEASE_URL: &str =
+    "https://api.github.com/repos/megascrapper/iseven-api-rust/releases/latest";
+
+/// Version of the running binary.
+const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Public key which release binaries are signed with, as the second line of a minisign public key file. Set by CI
+/// when building release binaries.
+const RELEASE_KEY: Option<&str> = option_env!("ISEVEN_API_RELEASE_KEY");
+
+/// Arguments for the `self-update` subcommand.
+#[derive(Debug, Args)]
+pub struct SelfUpdateArgs {
+    /// Only check whether a newer version is available, without installing it
+    #[arg(long)]
+    check: bool,
+
+    /// Install the latest release even if it is not newer than the running version
+    #[arg(long, conflicts_with = "check")]
+    force: bool,
+
+    /// Timeout for each request, e.g. "30s"
+    #[arg(long, value_parser = humantime::parse_duration, default_value = "60s")]
+    timeout: Duration,
+}
+
+/// Errors specific to updating, in addition to network and I/O errors.
+#[derive(thiserror::Error, Debug)]
+pub enum UpdateError {
+    #[error("no prebuilt binaries are published for {0}-{1}")]
+    UnsupportedPlatform(&'static str, &'static str),
+    #[error("release {release} has no asset named {asset}")]
+    MissingAsset { release: String, asset: String },
+    #[error("release {0} has an invalid version number")]
+    InvalidVersion(String),
+    #[error("checksum file {0} is malformed")]
+    MalformedChecksum(String),
+    #[error("checksum mismatch for {asset}: expected {expected}, got {actual}")]
+    ChecksumMismatch {
+        asset: String,
+        expected: String,
+        actual: String,
+    },
+    #[error(
+        "this binary was built without the release signing key, so updates cannot be verified"
+    )]
+    NoReleaseKey,
+    #[error("signature of {asset} is invalid: {reason}")]
+    InvalidSignature { asset: String, reason: &'static str },
+}
+
+/// A GitHub release, as returned by the releases API.
+#[derive(Debug, Deserialize)]
+struct Release {
+    tag_name: String,
+    assets: Vec<Asset>,
+}
+
+/// A file attached to a release.
+#[derive(Debug, Deserialize)]
+struct Asset {
+    name: String,
+    browser_download_url: String,
+}
+
+impl Release {
+    fn asset(&self, name: &str) -> Result<&Asset, UpdateError> {
+        self.assets
+            .iter()
+            .find(|asset| asset.name == name)
+            .ok_or_else(|| UpdateError::MissingAsset {
+                release: self.tag_name.clone(),
+                asset: name.to_string(),
+            })
+    }
+}
+
+impl SelfUpdateArgs {
+    /// Checks for a newer release and, unless only checking, replaces the running executable with its binary for this
+    /// platform once its checksum and signature have been verified.
+    pub async fn run(&self) -> Result<(), Box<dyn Error>> {
+        let client = reqwest::Client::builder()
+            .user_agent(concat!("iseven_api/", env!("CARGO_PKG_VERSION")))
+            .timeout(self.timeout)
+            .build()?;
+        let release: Release = client
+            .get(LATEST_RELEASE_URL)
+            .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let latest = release.tag_name.trim_start_matches('v');
+        let newer = compare_versions(latest, CURRENT_VERSION)
+            .ok_or_else(|| UpdateError::InvalidVersion(release.tag_name.clone()))?
+            == Ordering::Greater;
+        if !newer && !self.force {
+            println!("iseven_api {} is up to date", CURRENT_VERSION);
+            return Ok(());
+        }
+        if self.check {
+            println!(
+                "iseven_api {} is available (running {})",
+                latest, CURRENT_VERSION
+            );
+            return Ok(());
+        }
+
+        let key = RELEASE_KEY.ok_or(UpdateError::NoReleaseKey)?;
+        let name = asset_name().ok_or(UpdateError::UnsupportedPlatform(ARCH, OS))?;
+        let binary_asset = release.asset(&name)?;
+        let checksum_asset = release.asset(&format!("{}.sha256", name))?;
+        let signature_asset = release.asset(&format!("{}.minisig", name))?;
+        let checksum = download(&client, checksum_asset).await?;
+        let expected = String::from_utf8_lossy(&checksum)
+            .split_whitespace()
+            .next()
+            .filter(|hash| hash.len() == 64 && hash.bytes().all(|b| b.is_ascii_hexdigit()))
+            .map(str::to_ascii_lowercase)
+            .ok_or_else(|| UpdateError::MalformedChecksum(checksum_asset.name.clone()))?;
+        println!("Downloading {} from release {}", name, release.tag_name);
+        let binary = download(&client, binary_asset).await?;
+        let actual = sha256_hex(&binary);
+        if actual != expected {
+            return Err(UpdateError::ChecksumMismatch {
+                asset: name,
+                expected,
+                actual,
+            }
+            .into());
+        }
+        let signature = download(&client, signature_asset).await?;
+        let comment = signed_comment(&release.tag_name, &name);
+        verify_signature(key, &binary, &String::from_utf8_lossy(&signature), &comment).map_err(
+            |reason| UpdateError::InvalidSignature {
+                asset: name.clone(),
+                reason,
+            },
+        )?;
+
+        let path = replace_executable(&binary)?;
+        println!(
+            "Updated {} from {} to {}",
+            path.display(),
+            CURRENT_VERSION,
+            latest
+        );
+        Ok(())
+    }
+}
+
+async fn download(client: &reqwest::Client, asset: &Asset) -> reqwest::Result<Vec<u8>> {
+    let response = client
+        .get(&asset.browser_download_url)
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(response.bytes().await?.to_vec())
+}
+
+/// Returns the name of the release asset holding the binary for this platform, matching the targets built in CI.
+fn asset_name() -> Option<String> {
+    let target = match (ARCH, OS) {
+        ("arm", "linux") => "armv7-unknown-linux-gnueabihf".to_string(),
+        ("x86_64" | "aarch64", "linux") => format!("{}-unknown-linux-gnu", ARCH),
+        ("x86_64" | "aarch64", "macos") => format!("{}-apple-darwin", ARCH),
+        ("x86_64" | "aarch64", "windows") => format!("{}-pc-windows-msvc", ARCH),
+        _ => return None,
+    };
+    Some(format!("iseven_api-{}{}", target, EXE_SUFFIX))
+}
+
+/// Compares dotted version numbers such as `0.7.0` component by component, with missing components counting as 0.
+/// Returns `None` if either is not a version number.
+fn compare_versions(a: &str, b: &str) -> Option<Ordering> {
+    let parse = |version: &str| {
+        version
+            .split('.')
+            .map(|part| part.parse::<u64>().ok())
+            .collect::<Option<Vec<_>>>()
+    };
+    let (a, b) = (parse(a)?, parse(b)?);
+    let len = a.len().max(b.len());
+    let component = |v: &[u64], i: usize| v.get(i).copied().unwrap_or(0);
+    Some(
+        (0..len)
+            .map(|i| component(&a, i).cmp(&component(&b, i)))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal),
+    )
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    digest(&SHA256, data)
+        .as_ref()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Returns the trusted comment which the signature of `asset` in release `tag` carries, as passed to `minisign -t` by
+/// the release job.
+fn signed_comment(tag: &str, asset: &str) -> String {
+    format!("iseven_api release {} {}", tag, asset)
+}
+
+/// Checks that `signature`, the contents of a `.minisig` file, is a legacy minisign signature of `data` made with
+/// `key`, with `comment` as its trusted comment. Returns the reason if it is not.
+fn verify_signature(
+    key: &str,
+    data: &[u8],
+    signature: &str,
+    comment: &str,
+) -> Result<(), &'static str> {
+    // "Ed", then the key ID and the Ed25519 public key
+    let key = STANDARD
+        .decode(key.trim())
+        .ok()
+        .filter(|key| key.len() == 42 && key.starts_with(b"Ed"))
+        .ok_or("the release signing key is malformed")?;
+    let mut lines = signature.lines();
+    let _untrusted_comment = lines.next();
+    // "Ed" for a signature of the file itself, then the key ID and the signature
+    let file_signature = lines
+        .next()
+        .and_then(|line| STANDARD.decode(line.trim()).ok())
+        .filter(|signature| signature.len() == 74)
+        .ok_or("malformed signature")?;
+    match &file_signature[..2] {
+        b"Ed" => {}
+        b"ED" => return Err("prehashed signatures are not supported, sign with `minisign -S -l`"),
+        _ => return Err("unknown signature algorithm"),
+    }
+    if file_signature[2..10] != key[2..10] {
+        return Err("signed with a different key");
+    }
+    let trusted_comment = lines
+        .next()
+        .and_then(|line| line.strip_prefix("trusted comment: "))
+        .ok_or("malformed trusted comment")?;
+    let global_signature = lines
+        .next()
+        .and_then(|line| STANDARD.decode(line.trim()).ok())
+        .ok_or("malformed trusted comment signature")?;
+    let public_key = UnparsedPublicKey::new(&ED25519, &key[10..]);
+    public_key
+        .verify(data, &file_signature[10..])
+        .map_err(|_| "the file does not match its signature")?;
+    // the trusted comment is signed together with the file's signature
+    let signed = [&file_signature[10..], trusted_comment.as_bytes()].concat();
+    public_key
+        .verify(&signed, &global_signature)
+        .map_err(|_| "the trusted comment does not match its signature")?;
+    if trusted_comment != comment {
+        return Err("signed for a different release or asset");
+    }
+    Ok(())
+}
+
+/// Replaces the running executable with `binary`, returning its path.
+///
+/// The new binary is written next to the executable and renamed over it, so the executable is never left half
+/// written. Windows does not allow replacing a running executable, but does allow renaming it, so there it is first
+/// moved aside to a `.old` file, which is removed by the next update.
+fn replace_executable(binary: &[u8]) -> io::Result<PathBuf> {
+    let current = fs::canonicalize(std::env::current_exe()?)?;
+    let new = sibling(&current, "new");
+    fs::write(&new, binary)?;
+    let result = fs::set_permissions(&new, fs::metadata(&current)?.permissions())
+        .and_then(|()| install(&new, &current));
+    if result.is_err() {
+        let _ = fs::remove_file(&new);
+    }
+    result.map(|()| current)
+}
+
+#[cfg(windows)]
+fn install(new: &Path, current: &Path) -> io::Result<()> {
+    let old = sibling(current, "old");
+    match fs::remove_file(&old) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+        _ => {}
+    }
+    fs::rename(current, &old)?;
+    fs::rename(new, current).inspect_err(|_| {
+        let _ = fs::rename(&old, current);
+    })
+}
+
+#[cfg(not(windows))]
+fn install(new: &Path, current: &Path) -> io::Result<()> {
+    fs::rename(new, current)
+}
+
+/// Returns `path` with `extension` appended to its file name.
+fn sibling(path: &Path, extension: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(extension);
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_versions() {
+        assert_eq!(compare_versions("0.8.0", "0.7.0"), Some(Ordering::Greater));
+        assert_eq!(compare_versions("0.7", "0.7.0"), Some(Ordering::Equal));
+        assert_eq!(compare_versions("0.7.0", "0.10.0"), Some(Ordering::Less));
+        assert_eq!(compare_versions("0.8.0-beta", "0.7.0"), None);
+    }
+
+    #[test]
+    fn test_verify_signature() {
+        use ring::{
+            rand::SystemRandom,
+            signature::{Ed25519KeyPair, KeyPair},
+        };
+
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        let pair = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap();
+        let key_id = *b"12345678";
+        let key = STANDARD.encode([&b"Ed"[..], &key_id, pair.public_key().as_ref()].concat());
+        let sign = |data: &[u8], comment: &str| {
+            let signature = pair.sign(data);
+            let global = pair.sign(&[signature.as_ref(), comment.as_bytes()].concat());
+            format!(
+                "untrusted comment: signature from minisign secret key\n{}\ntrusted comment: {}\n{}\n",
+                STANDARD.encode([&b"Ed"[..], &key_id, signature.as_ref()].concat()),
+                comment,
+                STANDARD.encode(global)
+            )
+        };
+        let comment = signed_comment("v0.8.0", "iseven_api-x86_64-unknown-linux-gnu");
+        let signature = sign(b"binary", &comment);
+
+        assert_eq!(
+            verify_signature(&key, b"binary", &signature, &comment),
+            Ok(())
+        );
+        assert!(verify_signature(&key, b"tampered", &signature, &comment).is_err());
+        let other_release = signed_comment("v0.7.0", "iseven_api-x86_64-unknown-linux-gnu");
+        assert!(verify_signature(&key, b"binary", &signature, &other_release).is_err());
+        let forged_comment = signature.replace(&comment, &other_release);
+        assert!(verify_signature(&key, b"binary", &forged_comment, &other_release).is_err());
+        let other_pair = Ed25519KeyPair::from_pkcs8(
+            Ed25519KeyPair::generate_pkcs8(&SystemRandom::new())
+                .unwrap()
+                .as_ref(),
+        )
+        .unwrap();
+        let other_key =
+            STANDARD.encode([&b"Ed"[..], &key_id, other_pair.public_key().as_ref()].concat());
+        assert!(verify_signature(&other_key, b"binary", &signature, &comment).is_err());
+        assert!(verify_signature(&key, b"binary", "not a signature", &comment).is_err());
+    }
+
+    #[test]
+    fn test_sha256_hex() {
+        assert_eq!(
+            sha256_hex(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+}
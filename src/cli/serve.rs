@@ -0,0 +1,357 @@
+//! Local caching proxy server (`serve` subcommand).
+
+#[cfg(unix)]
+use std::path::PathBuf;
+use std::{
+    error::Error,
+    io,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::{Path, State},
+    http::{
+        header::{CACHE_CONTROL, CONTENT_TYPE},
+        HeaderMap, StatusCode,
+    },
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use clap::Args;
+use iseven_api::{
+    IsEvenApiClient, IsEvenApiClientBuilder, IsEvenApiError, IsEvenApiResponse, ProtocolError,
+    ResponseCache,
+};
+use log::{debug, warn};
+use serde::Serialize;
+use tokio::net::TcpListener;
+
+use super::{
+    cache, grpc,
+    metrics::{self, Metrics},
+    rate::Rate,
+};
+
+/// How long the API's reachability, as seen by the last request to it, is trusted by `/readyz` before probing again.
+const READINESS_MAX_AGE: Duration = Duration::from_secs(10);
+
+/// Number requested to probe whether the API is reachable.
+const PROBE_NUMBER: u32 = 0;
+
+/// Arguments for the `serve` subcommand.
+#[derive(Debug, Args)]
+pub struct ServeArgs {
+    /// Port to listen on
+    #[arg(long, default_value_t = 8080)]
+    port: u16,
+
+    /// Address to listen on
+    #[arg(long, default_value_t = IpAddr::V4(Ipv4Addr::LOCALHOST))]
+    bind: IpAddr,
+
+    /// Maximum number of cached responses
+    #[arg(long, default_value_t = 10_000)]
+    cache_size: usize,
+
+    /// Maximum rate of upstream requests, e.g. "5/s" or "100/m"
+    #[arg(long)]
+    rate: Option<Rate>,
+
+    /// Also serve the gRPC service defined in proto/iseven.proto on the same port, over HTTP/2 without TLS
+    #[arg(long)]
+    grpc: bool,
+
+    /// Listen on a Unix domain socket at this path instead of on a TCP port, for clients using `--unix-socket`
+    #[cfg(unix)]
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["port", "bind"])]
+    socket: Option<PathBuf>,
+}
+
+impl ServeArgs {
+    /// Builds the upstream client from `builder` and runs the server until interrupted.
+    ///
+    /// Cached responses expire after `cache_ttl`. If `persist` is given, the cache is loaded from that file on startup
+    /// and saved back to it on shutdown.
+    pub async fn run(
+        &self,
+        builder: IsEvenApiClientBuilder,
+        cache_ttl: Option<Duration>,
+        persist: Option<&std::path::Path>,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut cache = ResponseCache::new(self.cache_size);
+        if let Some(ttl) = cache_ttl {
+            cache = cache.with_ttl(ttl);
+        }
+        if let Some(path) = persist {
+            cache::load(path, &cache)?;
+        }
+        let mut builder = builder;
+        if let Some(rate) = self.rate {
+            builder = builder.rate_limiter(rate.limiter());
+        }
+        // readiness probes must reach the API, so they bypass the cache, but not the rate limiter
+        let probe = builder.clone().build()?;
+        let state = ProxyState {
+            client: builder.cache(cache.clone()).build()?,
+            probe,
+            cache: cache.clone(),
+            metrics: Arc::new(Metrics::default()),
+        };
+        let listen = Listen::Tcp(SocketAddr::new(self.bind, self.port));
+        #[cfg(unix)]
+        let listen = self.socket.clone().map_or(listen, Listen::Unix);
+        serve(state, listen, self.grpc).await?;
+        if let Some(path) = persist {
+            cache::save(path, &cache)?;
+        }
+        Ok(())
+    }
+}
+
+/// State shared by all requests to the proxy.
+#[derive(Debug, Clone)]
+pub struct ProxyState {
+    /// Client for answering checks, using the cache
+    client: IsEvenApiClient,
+    /// Client for readiness probes, bypassing the cache
+    probe: IsEvenApiClient,
+    cache: ResponseCache,
+    metrics: Arc<Metrics>,
+}
+
+impl ProxyState {
+    /// Checks `number` with the shared client, recording the outcome in the metrics. With `refresh`, the cached
+    /// response is not used, and the API is asked for a new one as with [`GetRequest::refresh`].
+    ///
+    /// [`GetRequest::refresh`]: iseven_api::GetRequest::refresh
+    pub async fn check(
+        &self,
+        number: &str,
+        refresh: bool,
+    ) -> Result<IsEvenApiResponse, IsEvenApiError> {
+        let start = Instant::now();
+        let result = match refresh {
+            true => self.client.request_str(number).refresh().send().await,
+            false => self.client.get_str(number).await,
+        };
+        if result
+            .as_ref()
+            .is_ok_and(|response| response.metadata().from_cache())
+        {
+            self.metrics.record_hit();
+        } else {
+            self.metrics.record_miss(&result, start.elapsed());
+        }
+        result
+    }
+
+    /// Returns whether the API is reachable, probing it unless it was contacted recently.
+    async fn upstream_reachable(&self) -> bool {
+        if let Some(reachable) = self.metrics.upstream_status(READINESS_MAX_AGE) {
+            return reachable;
+        }
+        let reachable = metrics::reachable(&self.probe.get(PROBE_NUMBER).await);
+        self.metrics.record_probe(reachable);
+        reachable
+    }
+}
+
+/// Where the proxy server listens.
+#[derive(Debug, Clone)]
+pub enum Listen {
+    /// TCP address
+    Tcp(SocketAddr),
+    /// Path of a Unix domain socket
+    #[cfg(unix)]
+    Unix(PathBuf),
+}
+
+/// Error body for failures which did not come from the API itself.
+#[derive(Serialize)]
+struct ProxyError {
+    error: String,
+}
+
+/// Runs the proxy server on `listen` until interrupted with Ctrl-C.
+///
+/// The server exposes the same `/api/iseven/:number` endpoint as the isEven API, forwarding requests through the
+/// client in `state` so that its cache and rate limiter are shared by everything using the proxy. If `grpc` is set,
+/// the gRPC `CheckParity` method is served as well.
+///
+/// For running as a sidecar, `/healthz` answers as long as the server is running, `/readyz` only while the API is
+/// reachable, and `/metrics` reports request counters and cache statistics in the Prometheus text format.
+pub async fn serve(state: ProxyState, listen: Listen, grpc: bool) -> io::Result<()> {
+    let mut app = Router::new()
+        .route("/api/iseven/:number", get(check))
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .route("/metrics", get(metrics));
+    if grpc {
+        app = app.route(grpc::CHECK_PARITY_PATH, post(grpc::check_parity));
+    }
+    let app = app.with_state(state);
+    match listen {
+        Listen::Tcp(addr) => {
+            let listener = TcpListener::bind(addr).await?;
+            eprintln!("Listening on http://{}", listener.local_addr()?);
+            axum::serve(listener, app)
+                .with_graceful_shutdown(shutdown_signal())
+                .await
+        }
+        #[cfg(unix)]
+        Listen::Unix(path) => serve_unix(&path, app).await,
+    }
+}
+
+/// Serves `app` on a Unix domain socket at `path` until interrupted with Ctrl-C, removing the socket afterwards.
+///
+/// `axum::serve` only accepts TCP listeners, so connections are served with hyper directly.
+#[cfg(unix)]
+async fn serve_unix(path: &std::path::Path, app: Router) -> io::Result<()> {
+    use hyper_util::{
+        rt::{TokioExecutor, TokioIo},
+        server::conn::auto,
+        service::TowerToHyperService,
+    };
+
+    let listener = super::daemon::bind(path).await?;
+    eprintln!("Listening on {}", path.display());
+    let shutdown = shutdown_signal();
+    tokio::pin!(shutdown);
+    let result = loop {
+        let stream = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => stream,
+                Err(e) => break Err(e),
+            },
+            _ = &mut shutdown => break Ok(()),
+        };
+        let service = TowerToHyperService::new(app.clone());
+        tokio::spawn(async move {
+            if let Err(e) = auto::Builder::new(TokioExecutor::new())
+                .serve_connection(TokioIo::new(stream), service)
+                .await
+            {
+                debug!("Connection failed: {}", e);
+            }
+        });
+    };
+    if let Err(e) = std::fs::remove_file(path) {
+        warn!("Unable to remove {}: {}", path.display(), e);
+    }
+    result
+}
+
+/// Completes when Ctrl-C is pressed, or never if it cannot be listened for.
+async fn shutdown_signal() {
+    if let Err(e) = tokio::signal::ctrl_c().await {
+        warn!("Unable to listen for Ctrl-C: {}", e);
+        std::future::pending::<()>().await
+    }
+}
+
+async fn check(
+    State(state): State<ProxyState>,
+    Path(number): Path<String>,
+    headers: HeaderMap,
+) -> Response {
+    debug!("Proxying request for {}", number);
+    match state.check(&number, no_cache(&headers)).await {
+        Ok(response) => Json(response).into_response(),
+        Err(e @ IsEvenApiError::InvalidInput { .. }) => (
+            StatusCode::BAD_REQUEST,
+            Json(ProxyError {
+                error: e.to_string(),
+            }),
+        )
+            .into_response(),
+        Err(IsEvenApiError::Api(e)) => (e.status(), Json(e.response())).into_response(),
+        Err(IsEvenApiError::Protocol(ProtocolError::UnexpectedStatus(e, status))) => {
+            (status, Json(e)).into_response()
+        }
+        Err(e @ IsEvenApiError::Protocol(ProtocolError::UnexpectedBody(_, status, _))) => (
+            status,
+            Json(ProxyError {
+                error: e.to_string(),
+            }),
+        )
+            .into_response(),
+        Err(e) => (
+            StatusCode::BAD_GATEWAY,
+            Json(ProxyError {
+                error: e.to_string(),
+            }),
+        )
+            .into_response(),
+    }
+}
+
+/// Returns `true` if a request with `headers` asks not to be answered from a cache, as clients refreshing a response
+/// do.
+fn no_cache(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(CACHE_CONTROL)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|directive| directive.trim().eq_ignore_ascii_case("no-cache"))
+}
+
+async fn healthz() -> &'static str {
+    "ok\n"
+}
+
+async fn readyz(State(state): State<ProxyState>) -> Response {
+    if state.upstream_reachable().await {
+        "ok\n".into_response()
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "upstream unreachable\n").into_response()
+    }
+}
+
+async fn metrics(State(state): State<ProxyState>) -> Response {
+    (
+        [(CONTENT_TYPE, "text/plain; version=0.0.4")],
+        state.metrics.render(&state.cache),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use iseven_api::{mock::MockServer, Plan};
+
+    use super::*;
+
+    #[test]
+    fn test_no_cache() {
+        let mut headers = HeaderMap::new();
+        assert!(!no_cache(&headers));
+        headers.insert(CACHE_CONTROL, "max-age=0, No-Cache".parse().unwrap());
+        assert!(no_cache(&headers));
+    }
+
+    #[tokio::test]
+    async fn test_check_refresh() {
+        let server = MockServer::start("127.0.0.1:0", Plan::Free).await.unwrap();
+        let cache = ResponseCache::new(10);
+        let builder = IsEvenApiClient::builder().base_url(server.url());
+        let state = ProxyState {
+            client: builder.clone().cache(cache.clone()).build().unwrap(),
+            probe: builder.build().unwrap(),
+            cache: cache.clone(),
+            metrics: Arc::new(Metrics::default()),
+        };
+        for refresh in [false, false, true] {
+            assert!(state.check("42", refresh).await.unwrap().iseven());
+        }
+        let metrics = state.metrics.render(&cache);
+        assert!(metrics.contains("iseven_proxy_cache_hits_total 1\n"));
+        assert!(metrics.contains("iseven_proxy_cache_misses_total 2\n"));
+        // the cache is only looked up by the client, and a refresh skips it
+        assert_eq!((cache.stats().hits(), cache.stats().misses()), (1, 1));
+    }
+}
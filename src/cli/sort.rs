@@ -0,0 +1,148 @@
+//! Sorting and grouping of buffered results (`--sort` and `--group-by`).
+
+use std::{cmp::Ordering, time::Duration};
+
+use clap::ValueEnum;
+use iseven_api::IsEvenApiError;
+
+use super::{input::Input, locale::Messages, output::Outcome};
+
+/// Order of results, as accepted by `--sort`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SortKey {
+    /// Alphabetically by the input as given
+    Input,
+    /// Numerically by the number checked
+    Number,
+    /// Even numbers first, then odd numbers, then failures
+    Parity,
+}
+
+/// Grouping of results, as accepted by `--group-by`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum GroupBy {
+    /// Under "Even:" and "Odd:" headings
+    Parity,
+}
+
+/// A result held back until all numbers have been checked.
+#[derive(Debug)]
+pub struct Buffered {
+    pub input: Input,
+    pub result: Result<Outcome, IsEvenApiError>,
+    pub latency: Duration,
+}
+
+/// Group of a result when grouping by parity, in the order the groups are printed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Group {
+    Even,
+    Odd,
+    /// Failures, and responses without a known parity
+    Other,
+}
+
+impl Group {
+    /// Returns the group of `result`.
+    pub fn of(result: &Result<Outcome, IsEvenApiError>) -> Self {
+        match result.as_ref().ok().and_then(Outcome::iseven) {
+            Some(true) => Group::Even,
+            Some(false) => Group::Odd,
+            None => Group::Other,
+        }
+    }
+
+    /// Returns the heading printed above the group.
+    pub fn heading(self, messages: &Messages) -> &'static str {
+        match self {
+            Group::Even => messages.even_heading,
+            Group::Odd => messages.odd_heading,
+            Group::Other => messages.errors_heading,
+        }
+    }
+}
+
+/// Sorts `results` by `key`, then by group if `group_by` is set. Sorting is stable, so results which compare equal
+/// stay in the order they were checked.
+pub fn sort(results: &mut [Buffered], key: Option<SortKey>, group_by: Option<GroupBy>) {
+    match key {
+        Some(SortKey::Input) => results.sort_by(|a, b| a.input.original.cmp(&b.input.original)),
+        Some(SortKey::Number) => {
+            results.sort_by(|a, b| compare_numbers(&a.input.number, &b.input.number))
+        }
+        Some(SortKey::Parity) => results.sort_by_key(|r| Group::of(&r.result)),
+        None => {}
+    }
+    if group_by == Some(GroupBy::Parity) {
+        results.sort_by_key(|r| Group::of(&r.result));
+    }
+}
+
+/// Compares numbers of any size numerically. Anything which is not a decimal integer sorts after all integers, in
+/// alphabetical order.
+fn compare_numbers(a: &str, b: &str) -> Ordering {
+    match (decimal(a), decimal(b)) {
+        (Some((a_negative, a_digits)), Some((b_negative, b_digits))) => {
+            // without leading zeros, a longer number has the larger magnitude
+            let magnitude = a_digits
+                .len()
+                .cmp(&b_digits.len())
+                .then_with(|| a_digits.cmp(b_digits));
+            match (a_negative, b_negative) {
+                (false, false) => magnitude,
+                (true, true) => magnitude.reverse(),
+                (true, false) => Ordering::Less,
+                (false, true) => Ordering::Greater,
+            }
+        }
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => a.cmp(b),
+    }
+}
+
+/// Splits a decimal integer into its sign and digits without leading zeros. Zero is never negative.
+fn decimal(s: &str) -> Option<(bool, &str)> {
+    let (negative, digits) = match s.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, s),
+    };
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let digits = digits.trim_start_matches('0');
+    Some((negative && !digits.is_empty(), digits))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_numbers() {
+        let mut numbers = [
+            "10",
+            "abc",
+            "-3",
+            "99999999999999999999999999999999999999999",
+            "2",
+            "-0",
+            "-12",
+            "007",
+        ];
+        numbers.sort_by(|a, b| compare_numbers(a, b));
+        assert_eq!(
+            numbers,
+            [
+                "-12",
+                "-3",
+                "-0",
+                "2",
+                "007",
+                "10",
+                "99999999999999999999999999999999999999999",
+                "abc"
+            ]
+        );
+    }
+}
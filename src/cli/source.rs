@@ -0,0 +1,193 @@
+//! Sources of the numbers to check: the command line, a CSV file, stdin, the clipboard, or a random number generator.
+
+use std::{
+    fmt::{Display, Formatter},
+    io::{self, IsTerminal},
+    ops::RangeInclusive,
+    vec,
+};
+
+use serde::Serialize;
+use tokio::io::{AsyncBufReadExt, BufReader, Lines, Stdin};
+
+use super::csv::Table;
+
+/// Where an input came from, for error reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Location {
+    /// Position among the numbers given on the command line, starting at 1
+    Argument(usize),
+    /// Line of stdin, of the CSV file or of the clipboard
+    Line(usize),
+    /// Position among the randomly generated numbers, starting at 1
+    Random(usize),
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Location::Argument(n) => write!(f, "argument {}", n),
+            Location::Line(n) => write!(f, "line {}", n),
+            Location::Random(n) => write!(f, "random number {}", n),
+        }
+    }
+}
+
+/// Numbers to check, in order, with where they came from.
+#[derive(Debug)]
+pub enum Inputs {
+    /// Given on the command line, taken from a CSV file or the clipboard, or randomly generated
+    List(vec::IntoIter<(Location, String)>),
+    /// Read from stdin, one per line, as they arrive
+    Stdin {
+        lines: Lines<BufReader<Stdin>>,
+        /// Number of lines read so far
+        line: usize,
+    },
+}
+
+impl Inputs {
+    /// Returns the numbers given on the command line.
+    pub fn arguments(numbers: &[String]) -> Self {
+        let inputs = numbers
+            .iter()
+            .enumerate()
+            .map(|(i, number)| (Location::Argument(i + 1), number.clone()));
+        Inputs::List(inputs.collect::<Vec<_>>().into_iter())
+    }
+
+    /// Returns the numbers in the selected column of a CSV file.
+    pub fn csv(table: &Table) -> Self {
+        let inputs = table
+            .values()
+            .enumerate()
+            .map(|(i, value)| (Location::Line(table.line(i)), value.to_string()));
+        Inputs::List(inputs.collect::<Vec<_>>().into_iter())
+    }
+
+    /// Returns the numbers in text copied from the clipboard, separated by whitespace or commas.
+    pub fn clipboard(text: &str) -> Self {
+        let inputs = text.lines().enumerate().flat_map(|(i, line)| {
+            line.split(|c: char| c.is_whitespace() || c == ',')
+                .filter(|number| !number.is_empty())
+                .map(move |number| (Location::Line(i + 1), number.to_string()))
+        });
+        Inputs::List(inputs.collect::<Vec<_>>().into_iter())
+    }
+
+    /// Returns `count` numbers drawn uniformly from `range`, the same ones for every run with the same `seed`.
+    pub fn random(count: usize, range: RangeInclusive<i64>, seed: u64) -> Self {
+        let mut rng = fastrand::Rng::with_seed(seed);
+        let inputs = (1..=count).map(|i| (Location::Random(i), rng.i64(range.clone()).to_string()));
+        Inputs::List(inputs.collect::<Vec<_>>().into_iter())
+    }
+
+    /// Reads numbers from stdin, skipping blank lines.
+    pub fn stdin() -> Self {
+        Inputs::Stdin {
+            lines: BufReader::new(tokio::io::stdin()).lines(),
+            line: 0,
+        }
+    }
+
+    /// Reads all remaining inputs, e.g. to validate them before checking any.
+    pub async fn read_all(mut self) -> io::Result<Vec<(Location, String)>> {
+        let mut inputs = Vec::new();
+        while let Some(input) = self.next().await {
+            inputs.push(input?);
+        }
+        Ok(inputs)
+    }
+
+    /// Returns the number of inputs, if known in advance.
+    pub fn len(&self) -> Option<usize> {
+        match self {
+            Inputs::List(inputs) => Some(inputs.len()),
+            Inputs::Stdin { .. } => None,
+        }
+    }
+
+    /// Returns the next input, waiting for it to arrive on stdin if necessary.
+    pub async fn next(&mut self) -> Option<io::Result<(Location, String)>> {
+        match self {
+            Inputs::List(inputs) => inputs.next().map(Ok),
+            Inputs::Stdin { lines, line } => loop {
+                let text = match lines.next_line().await {
+                    Ok(Some(text)) => text,
+                    Ok(None) => return None,
+                    Err(e) => return Some(Err(e)),
+                };
+                *line += 1;
+                if !text.trim().is_empty() {
+                    return Some(Ok((Location::Line(*line), text.trim().to_string())));
+                }
+            },
+        }
+    }
+}
+
+/// Returns `true` if stdin should be read for numbers, because it is not a terminal.
+pub fn stdin_has_input() -> bool {
+    !io::stdin().is_terminal()
+}
+
+/// Returns `true` if stdin is a pipe, in which case output is flushed after every result.
+#[cfg(unix)]
+pub fn stdin_is_pipe() -> bool {
+    use std::{
+        fs::File,
+        os::{fd::AsFd, unix::fs::FileTypeExt},
+    };
+
+    io::stdin()
+        .as_fd()
+        .try_clone_to_owned()
+        .map(File::from)
+        .and_then(|file| file.metadata())
+        .is_ok_and(|metadata| metadata.file_type().is_fifo())
+}
+
+/// Returns `true` if stdin is a pipe, in which case output is flushed after every result.
+#[cfg(not(unix))]
+pub fn stdin_is_pipe() -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clipboard() {
+        let Inputs::List(inputs) = Inputs::clipboard("42, 7\n\n  0x1f\t-3,\r\n") else {
+            panic!("clipboard inputs are a list");
+        };
+        assert_eq!(
+            inputs.collect::<Vec<_>>(),
+            [
+                (Location::Line(1), "42".to_string()),
+                (Location::Line(1), "7".to_string()),
+                (Location::Line(3), "0x1f".to_string()),
+                (Location::Line(3), "-3".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_random() {
+        let Inputs::List(inputs) = Inputs::random(100, -5..=5, 42) else {
+            panic!("random inputs are a list");
+        };
+        let inputs = inputs.collect::<Vec<_>>();
+        assert_eq!(inputs.len(), 100);
+        assert_eq!(inputs[99].0, Location::Random(100));
+        assert!(inputs
+            .iter()
+            .all(|(_, number)| (-5..=5).contains(&number.parse::<i64>().unwrap())));
+        let Inputs::List(again) = Inputs::random(100, -5..=5, 42) else {
+            panic!("random inputs are a list");
+        };
+        assert_eq!(again.collect::<Vec<_>>(), inputs);
+    }
+}
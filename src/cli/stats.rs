@@ -0,0 +1,214 @@
+//! End-of-run summary statistics (`--stats` and `--summary-json`).
+
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter},
+    fs, io,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use iseven_api::{ApiError, IsEvenApiError, ProtocolError};
+use serde::Serialize;
+
+/// Counters collected over a batch run.
+#[derive(Debug, Clone)]
+pub struct Stats {
+    started: Instant,
+    even: usize,
+    odd: usize,
+    unknown: usize,
+    errors: usize,
+    errors_by_kind: BTreeMap<&'static str, usize>,
+    cache_hits: usize,
+    duplicates: usize,
+    total_latency: Duration,
+}
+
+impl Stats {
+    /// Starts collecting statistics, using the current time as the start of the run.
+    pub fn start() -> Self {
+        Self {
+            started: Instant::now(),
+            even: 0,
+            odd: 0,
+            unknown: 0,
+            errors: 0,
+            errors_by_kind: BTreeMap::new(),
+            cache_hits: 0,
+            duplicates: 0,
+            total_latency: Duration::ZERO,
+        }
+    }
+
+    /// Records a successful check. `iseven` is `None` if the parity could not be determined from the response.
+    pub fn record_success(&mut self, iseven: Option<bool>, latency: Duration) {
+        match iseven {
+            Some(true) => self.even += 1,
+            Some(false) => self.odd += 1,
+            None => self.unknown += 1,
+        }
+        self.total_latency += latency;
+    }
+
+    /// Records a failed check, with a short description of the kind of error such as `"network_error"`.
+    pub fn record_error(&mut self, kind: &'static str, latency: Duration) {
+        self.errors += 1;
+        *self.errors_by_kind.entry(kind).or_default() += 1;
+        self.total_latency += latency;
+    }
+
+    /// Records that a check was answered from the cache.
+    pub fn record_cache_hit(&mut self) {
+        self.cache_hits += 1;
+    }
+
+    /// Records that a check was answered from the result for an earlier duplicate input (`--dedup`).
+    pub fn record_duplicate(&mut self) {
+        self.duplicates += 1;
+    }
+
+    /// Returns the number of checks answered from an earlier duplicate, each of which saved a request.
+    pub fn duplicates(&self) -> usize {
+        self.duplicates
+    }
+
+    /// Returns the number of failed checks recorded.
+    pub fn errors(&self) -> usize {
+        self.errors
+    }
+
+    /// Returns the number of even results recorded.
+    pub fn even(&self) -> usize {
+        self.even
+    }
+
+    /// Returns the number of odd results recorded.
+    pub fn odd(&self) -> usize {
+        self.odd
+    }
+
+    /// Returns the total number of checks recorded.
+    pub fn total(&self) -> usize {
+        self.even + self.odd + self.unknown + self.errors
+    }
+
+    /// Returns the mean latency of all recorded checks.
+    pub fn average_latency(&self) -> Duration {
+        match u32::try_from(self.total()) {
+            Ok(0) | Err(_) => Duration::ZERO,
+            Ok(n) => self.total_latency / n,
+        }
+    }
+}
+
+/// Returns the kind of `error`, as counted in the summary.
+pub fn error_kind(error: &IsEvenApiError) -> &'static str {
+    match error {
+        IsEvenApiError::Api(ApiError::NumberOutOfRange(_)) => "number_out_of_range",
+        IsEvenApiError::Api(ApiError::InvalidNumber(_)) => "invalid_number",
+        IsEvenApiError::Api(ApiError::Unauthorized(_)) => "unauthorized",
+        IsEvenApiError::Api(ApiError::RateLimited(_)) => "rate_limited",
+        IsEvenApiError::Transport(_) => "network_error",
+        IsEvenApiError::Protocol(ProtocolError::Decode(..)) => "invalid_response",
+        IsEvenApiError::InvalidInput { .. } => "invalid_input",
+        _ => "error_response",
+    }
+}
+
+/// Machine-readable report of a run (`--summary-json`).
+#[derive(Debug, Serialize)]
+pub struct Summary<'a> {
+    /// Number of inputs given
+    inputs: usize,
+    /// Whether the run was interrupted before all inputs were checked
+    interrupted: bool,
+    #[serde(flatten)]
+    counts: Counts<'a>,
+    /// Persistent cache statistics, if the cache is enabled
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cache: Option<CacheSummary>,
+}
+
+#[derive(Debug, Serialize)]
+struct Counts<'a> {
+    checked: usize,
+    even: usize,
+    odd: usize,
+    unknown: usize,
+    errors: usize,
+    errors_by_kind: &'a BTreeMap<&'static str, usize>,
+    /// Checks answered from an earlier duplicate input (`--dedup`)
+    duplicates: usize,
+    total_ms: u128,
+    average_latency_ms: u128,
+}
+
+/// Cache statistics in a [`Summary`].
+#[derive(Debug, Serialize)]
+struct CacheSummary {
+    /// Checks answered from the cache
+    hits: usize,
+    /// Responses in the cache at the end of the run
+    entries: usize,
+}
+
+impl Summary<'_> {
+    /// Writes the summary as JSON to `path`.
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json + "\n")
+    }
+}
+
+impl Stats {
+    /// Returns a machine-readable summary of a run over `inputs` numbers. `cache_entries` is the number of responses
+    /// in the persistent cache, if it is enabled.
+    pub fn summary(
+        &self,
+        inputs: usize,
+        interrupted: bool,
+        cache_entries: Option<usize>,
+    ) -> Summary<'_> {
+        Summary {
+            inputs,
+            interrupted,
+            counts: Counts {
+                checked: self.total(),
+                even: self.even,
+                odd: self.odd,
+                unknown: self.unknown,
+                errors: self.errors,
+                errors_by_kind: &self.errors_by_kind,
+                duplicates: self.duplicates,
+                total_ms: self.started.elapsed().as_millis(),
+                average_latency_ms: self.average_latency().as_millis(),
+            },
+            cache: cache_entries.map(|entries| CacheSummary {
+                hits: self.cache_hits,
+                entries,
+            }),
+        }
+    }
+}
+
+impl Display for Stats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Checked: {}", self.total())?;
+        writeln!(f, "Even: {}", self.even)?;
+        writeln!(f, "Odd: {}", self.odd)?;
+        if self.unknown > 0 {
+            writeln!(f, "Unknown: {}", self.unknown)?;
+        }
+        writeln!(f, "Errors: {}", self.errors)?;
+        if self.duplicates > 0 {
+            writeln!(f, "Duplicates skipped: {}", self.duplicates)?;
+        }
+        writeln!(f, "Total time: {}ms", self.started.elapsed().as_millis())?;
+        write!(
+            f,
+            "Average latency: {}ms",
+            self.average_latency().as_millis()
+        )
+    }
+}
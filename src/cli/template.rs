@@ -0,0 +1,156 @@
+//! User-supplied output templates (`--template`).
+
+use std::{fmt::Write, str::FromStr, time::Duration};
+
+use iseven_api::{IsEvenApiError, IsEvenApiResponse};
+
+/// Placeholders available in an output template.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Placeholder {
+    Number,
+    Parity,
+    Ad,
+    Latency,
+    Error,
+}
+
+impl Placeholder {
+    const NAMES: &'static [&'static str] = &["number", "parity", "ad", "latency", "error"];
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "number" => Some(Self::Number),
+            "parity" => Some(Self::Parity),
+            "ad" => Some(Self::Ad),
+            "latency" => Some(Self::Latency),
+            "error" => Some(Self::Error),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Placeholder(Placeholder),
+}
+
+/// A parsed output template such as `"{number} is {parity}"`.
+///
+/// Literal braces are written as `{{` and `}}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    segments: Vec<Segment>,
+}
+
+impl FromStr for Template {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = s.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => return Err(format!("unclosed placeholder `{{{}`", name)),
+                        }
+                    }
+                    let placeholder = Placeholder::from_name(&name).ok_or_else(|| {
+                        format!(
+                            "unknown placeholder `{{{}}}` (expected one of: {})",
+                            name,
+                            Placeholder::NAMES.join(", ")
+                        )
+                    })?;
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+                    segments.push(Segment::Placeholder(placeholder));
+                }
+                '}' => return Err("unmatched `}` (use `}}` for a literal brace)".to_string()),
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+        Ok(Self { segments })
+    }
+}
+
+impl Template {
+    /// Renders the template for a single result. Placeholders which do not apply to the result (e.g. `{error}` for a
+    /// successful request) are rendered as empty strings.
+    pub fn render(
+        &self,
+        number: &str,
+        result: Result<&IsEvenApiResponse, &IsEvenApiError>,
+        latency: Duration,
+    ) -> String {
+        let mut out = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(s) => out.push_str(s),
+                Segment::Placeholder(p) => match (p, result) {
+                    (Placeholder::Number, _) => out.push_str(number),
+                    (Placeholder::Parity, Ok(r)) => write!(out, "{}", r).unwrap(),
+                    (Placeholder::Ad, Ok(r)) => out.push_str(r.ad()),
+                    (Placeholder::Latency, _) => write!(out, "{}ms", latency.as_millis()).unwrap(),
+                    (Placeholder::Error, Err(e)) => write!(out, "{}", e).unwrap(),
+                    _ => {}
+                },
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let template: Template = "{number} is {{{parity}}}".parse().unwrap();
+        assert_eq!(
+            template.segments,
+            vec![
+                Segment::Placeholder(Placeholder::Number),
+                Segment::Literal(" is {".to_string()),
+                Segment::Placeholder(Placeholder::Parity),
+                Segment::Literal("}".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let template: Template = "{number} is {parity} ({latency}){error}".parse().unwrap();
+        let response = serde_json::from_str(r#"{"ad": "Buy isEven", "iseven": true}"#).unwrap();
+        assert_eq!(
+            template.render("42", Ok(&response), Duration::from_millis(120)),
+            "42 is even (120ms)"
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!("{nope}".parse::<Template>().is_err());
+        assert!("{number".parse::<Template>().is_err());
+        assert!("number}".parse::<Template>().is_err());
+    }
+}
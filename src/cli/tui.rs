@@ -0,0 +1,355 @@
+//! Interactive dashboard of numbers being monitored (`tui` subcommand).
+//!
+//! The dashboard is drawn with plain ANSI escape sequences on the alternate screen, with the input box as the last
+//! line. Input is read a line at a time in the terminal's normal mode, and the table above it is redrawn in place
+//! on every refresh, leaving whatever is being typed untouched.
+//!
+//! ratatui is not used: a single table and an input line need only a handful of escape sequences, and reading input
+//! in the terminal's normal mode keeps line editing and Ctrl-C working without putting the terminal in raw mode and
+//! restoring it on every exit path. The dashboard therefore needs a terminal which understands the ANSI escape
+//! sequences for the alternate screen and cursor movement.
+
+use std::{
+    collections::VecDeque,
+    error::Error,
+    fmt::Write as _,
+    io::{self, stdout, IsTerminal, Write},
+    path::Path,
+    time::{Duration, Instant, SystemTime},
+};
+
+use clap::Args;
+use iseven_api::{IsEvenApiClient, IsEvenApiClientBuilder, ResponseCache};
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+use super::{cache, color::Colors, stats::error_kind};
+
+/// Number of latencies shown in each sparkline.
+const HISTORY_LEN: usize = 20;
+
+/// Bars of a sparkline, from lowest to highest.
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+const ENTER_ALTERNATE_SCREEN: &str = "\x1b[?1049h";
+const LEAVE_ALTERNATE_SCREEN: &str = "\x1b[?1049l";
+const CLEAR_SCREEN: &str = "\x1b[H\x1b[2J";
+const CLEAR_LINE: &str = "\x1b[2K";
+const SAVE_CURSOR: &str = "\x1b7";
+const RESTORE_CURSOR: &str = "\x1b8";
+const HOME: &str = "\x1b[H";
+
+/// Arguments for the `tui` subcommand.
+#[derive(Debug, Args)]
+pub struct TuiArgs {
+    /// Time between re-checks of every number on the dashboard, e.g. "5s"
+    #[arg(short, long, value_parser = humantime::parse_duration, default_value = "5s")]
+    interval: Duration,
+
+    /// Numbers to put on the dashboard at startup
+    numbers: Vec<String>,
+}
+
+/// Result of the latest check of a number.
+#[derive(Debug)]
+struct Check {
+    /// Parity, or the kind of error
+    result: Result<bool, &'static str>,
+    latency: Duration,
+    /// Whether the response came from the cache
+    cached: bool,
+}
+
+/// A number on the dashboard.
+#[derive(Debug)]
+struct Row {
+    number: String,
+    last: Option<Check>,
+    /// Latencies of the most recent checks, oldest first
+    history: VecDeque<Duration>,
+}
+
+impl Row {
+    fn new(number: String) -> Self {
+        Self {
+            number,
+            last: None,
+            history: VecDeque::with_capacity(HISTORY_LEN),
+        }
+    }
+
+    fn record(&mut self, check: Check) {
+        if self.history.len() == HISTORY_LEN {
+            self.history.pop_front();
+        }
+        self.history.push_back(check.latency);
+        self.last = Some(check);
+    }
+}
+
+/// State of the dashboard.
+struct Dashboard {
+    client: IsEvenApiClient,
+    cache: Option<ResponseCache>,
+    rows: Vec<Row>,
+    interval: Duration,
+    last_refresh: Option<SystemTime>,
+    colors: Colors,
+}
+
+impl TuiArgs {
+    /// Builds a client from `builder` and runs the dashboard until `q` is entered, stdin is closed or Ctrl-C is
+    /// pressed.
+    pub async fn run(
+        &self,
+        builder: IsEvenApiClientBuilder,
+        cache_ttl: Option<Duration>,
+        persist: Option<&Path>,
+        colors: Colors,
+    ) -> Result<(), Box<dyn Error>> {
+        if !stdout().is_terminal() || !io::stdin().is_terminal() {
+            return Err("the dashboard needs a terminal".into());
+        }
+        let cache = match persist {
+            Some(path) => {
+                let cache = cache::new_cache(cache_ttl);
+                cache::load(path, &cache)?;
+                Some(cache)
+            }
+            None => None,
+        };
+        let builder = match &cache {
+            Some(cache) => builder.cache(cache.clone()),
+            None => builder,
+        };
+        let mut dashboard = Dashboard {
+            client: builder.build()?,
+            cache,
+            rows: self.numbers.iter().cloned().map(Row::new).collect(),
+            interval: self.interval,
+            last_refresh: None,
+            colors,
+        };
+
+        print!("{}", ENTER_ALTERNATE_SCREEN);
+        let result = dashboard.run().await;
+        print!("{}", LEAVE_ALTERNATE_SCREEN);
+        stdout().flush()?;
+        result?;
+        if let (Some(path), Some(cache)) = (persist, &dashboard.cache) {
+            cache::save(path, cache)?;
+        }
+        Ok(())
+    }
+}
+
+impl Dashboard {
+    async fn run(&mut self) -> io::Result<()> {
+        let mut lines = BufReader::new(tokio::io::stdin()).lines();
+        let mut refresh = tokio::time::interval(self.interval);
+        self.draw()?;
+        loop {
+            tokio::select! {
+                line = lines.next_line() => {
+                    let Some(line) = line? else { break };
+                    if !self.submit(&line).await {
+                        break;
+                    }
+                    self.draw()?;
+                }
+                _ = refresh.tick() => {
+                    self.refresh().await;
+                    self.redraw_table()?;
+                }
+                _ = tokio::signal::ctrl_c() => break,
+            }
+        }
+        Ok(())
+    }
+
+    /// Handles a line typed into the input box. Returns `false` if the dashboard should be closed.
+    async fn submit(&mut self, line: &str) -> bool {
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("q" | "quit") => return false,
+            Some("rm") => {
+                let numbers = words.collect::<Vec<_>>();
+                self.rows
+                    .retain(|row| !numbers.contains(&row.number.as_str()));
+            }
+            _ => {
+                for number in line.split(|c: char| c.is_whitespace() || c == ',') {
+                    if number.is_empty() || self.rows.iter().any(|row| row.number == number) {
+                        continue;
+                    }
+                    let mut row = Row::new(number.to_string());
+                    row.record(self.check(number).await);
+                    self.rows.push(row);
+                }
+            }
+        }
+        true
+    }
+
+    /// Re-checks every number on the dashboard.
+    async fn refresh(&mut self) {
+        for i in 0..self.rows.len() {
+            let check = self.check(&self.rows[i].number).await;
+            self.rows[i].record(check);
+        }
+        self.last_refresh = Some(SystemTime::now());
+    }
+
+    async fn check(&self, number: &str) -> Check {
+        let start = Instant::now();
+        let result = self.client.get_str(number).await;
+        Check {
+            latency: start.elapsed(),
+            cached: result
+                .as_ref()
+                .is_ok_and(|response| response.metadata().from_cache()),
+            result: result.map(|r| r.iseven()).map_err(|e| error_kind(&e)),
+        }
+    }
+
+    /// Clears the screen and draws the whole dashboard, leaving the cursor in an empty input box.
+    fn draw(&self) -> io::Result<()> {
+        let mut out = stdout().lock();
+        write!(out, "{}", CLEAR_SCREEN)?;
+        for line in self.render() {
+            writeln!(out, "{}", line)?;
+        }
+        write!(out, "> ")?;
+        out.flush()
+    }
+
+    /// Redraws everything above the input box, keeping the cursor and anything typed so far where they are.
+    fn redraw_table(&self) -> io::Result<()> {
+        let mut out = stdout().lock();
+        write!(out, "{}{}", SAVE_CURSOR, HOME)?;
+        for line in self.render() {
+            writeln!(out, "{}{}", CLEAR_LINE, line)?;
+        }
+        write!(out, "{}", RESTORE_CURSOR)?;
+        out.flush()
+    }
+
+    /// Returns the lines of the dashboard above the input box.
+    fn render(&self) -> Vec<String> {
+        let last_refresh = self.last_refresh.map_or("never".to_string(), |time| {
+            humantime::format_rfc3339_seconds(time).to_string()
+        });
+        let width = self
+            .rows
+            .iter()
+            .map(|row| row.number.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max("NUMBER".len());
+        let mut lines = vec![
+            format!(
+                "iseven_api: refreshing every {}, last refresh {}",
+                humantime::format_duration(self.interval),
+                last_refresh
+            ),
+            String::new(),
+            format!(
+                "{:<width$}  {:<7}  {:>10}  {:<5}  HISTORY",
+                "NUMBER",
+                "PARITY",
+                "LATENCY",
+                "CACHE",
+                width = width
+            ),
+        ];
+        for row in &self.rows {
+            let mut line = format!("{:<width$}  ", row.number, width = width);
+            match &row.last {
+                Some(check) => {
+                    let parity = match check.result {
+                        Ok(iseven) => self.colors.parity_word(
+                            iseven,
+                            &format!("{:<7}", if iseven { "even" } else { "odd" }),
+                        ),
+                        Err(_) => self.colors.error(format_args!("{:<7}", "error")),
+                    };
+                    let _ = write!(
+                        line,
+                        "{}  {:>7.1} ms  {:<5}  {}",
+                        parity,
+                        check.latency.as_secs_f64() * 1000.0,
+                        if check.cached { "hit" } else { "" },
+                        sparkline(row.history.iter().copied())
+                    );
+                    if let Err(kind) = check.result {
+                        let _ = write!(line, "  {}", self.colors.error(kind));
+                    }
+                }
+                None => line.push_str("waiting"),
+            }
+            lines.push(line);
+        }
+        lines.push(String::new());
+        lines.push(
+            "Type numbers to add them, \"rm <number>\" to remove them, or \"q\" to quit"
+                .to_string(),
+        );
+        lines
+    }
+}
+
+/// Draws latencies as a sparkline, scaled between the lowest and highest of them.
+fn sparkline(latencies: impl Iterator<Item = Duration> + Clone) -> String {
+    let min = latencies.clone().min().unwrap_or_default();
+    let max = latencies.clone().max().unwrap_or_default();
+    let range = (max - min).as_secs_f64();
+    latencies
+        .map(|latency| {
+            let level = if range > 0.0 {
+                ((latency - min).as_secs_f64() / range * (BARS.len() - 1) as f64).round() as usize
+            } else {
+                0
+            };
+            BARS[level]
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::color::ColorChoice;
+
+    #[tokio::test]
+    async fn test_check_cached() {
+        let server = iseven_api::mock::MockServer::start("127.0.0.1:0", iseven_api::Plan::Free)
+            .await
+            .unwrap();
+        let cache = cache::new_cache(None);
+        let dashboard = Dashboard {
+            client: IsEvenApiClientBuilder::new()
+                .base_url(server.url())
+                .cache(cache.clone())
+                .build()
+                .unwrap(),
+            cache: Some(cache),
+            rows: Vec::new(),
+            interval: Duration::from_secs(5),
+            last_refresh: None,
+            colors: ColorChoice::Never.for_stream(&stdout()),
+        };
+        assert!(!dashboard.check("42").await.cached);
+        // the cache is keyed on the canonical form of the number
+        assert!(dashboard.check("042").await.cached);
+        // only the client looks the cache up
+        let stats = dashboard.cache.as_ref().unwrap().stats();
+        assert_eq!((stats.hits(), stats.misses()), (1, 1));
+    }
+
+    #[test]
+    fn test_sparkline() {
+        let latencies = [10, 80, 45, 10].map(Duration::from_millis);
+        assert_eq!(sparkline(latencies.into_iter()), "▁█▅▁");
+        assert_eq!(sparkline([Duration::from_millis(5); 3].into_iter()), "▁▁▁");
+        assert_eq!(sparkline(std::iter::empty()), "");
+    }
+}
@@ -0,0 +1,118 @@
+//! Comparison of the API's answers with locally computed ones (`verify` subcommand).
+
+use std::error::Error;
+
+use clap::Args;
+use iseven_api::{ApiError, IsEvenApiClientBuilder, IsEvenApiError, IsEvenApiResponse, Plan};
+
+use super::offline;
+
+/// Arguments for the `verify` subcommand.
+#[derive(Debug, Args)]
+pub struct VerifyArgs {
+    /// Numbers to check with both the API and local computation
+    #[arg(required = true, allow_negative_numbers = true)]
+    numbers: Vec<String>,
+}
+
+/// How the API's answer for a number compares with the local one.
+#[derive(Debug, PartialEq, Eq)]
+enum Outcome {
+    Match,
+    Mismatch {
+        api: &'static str,
+        local: &'static str,
+    },
+    /// The API failed in a way which says nothing about the number, e.g. it could not be reached
+    Unverified(String),
+}
+
+impl VerifyArgs {
+    /// Builds a client from `builder` and checks every number with both the API and local computation for `plan`,
+    /// printing each discrepancy and a summary. Returns `false` if any answer differs or could not be verified.
+    pub async fn run(
+        &self,
+        builder: IsEvenApiClientBuilder,
+        plan: Plan,
+    ) -> Result<bool, Box<dyn Error>> {
+        let client = builder.build()?;
+        let mut mismatches = 0;
+        let mut unverified = 0;
+        for number in &self.numbers {
+            let api = client.get_str(number).await;
+            let local = offline::check(number, plan, None);
+            match compare(&api, &local) {
+                Outcome::Match => {}
+                Outcome::Mismatch { api, local } => {
+                    mismatches += 1;
+                    println!("{}: API says {}, expected {}", number, api, local);
+                }
+                Outcome::Unverified(e) => {
+                    unverified += 1;
+                    println!("{}: unable to verify: {}", number, e);
+                }
+            }
+        }
+        println!(
+            "{} checked: {} matched, {} mismatched, {} could not be verified",
+            self.numbers.len(),
+            self.numbers.len() - mismatches - unverified,
+            mismatches,
+            unverified
+        );
+        Ok(mismatches == 0 && unverified == 0)
+    }
+}
+
+fn compare(
+    api: &Result<IsEvenApiResponse, IsEvenApiError>,
+    local: &Result<IsEvenApiResponse, IsEvenApiError>,
+) -> Outcome {
+    let Some(api_answer) = answer(api) else {
+        let error = api.as_ref().expect_err("only errors have no answer");
+        return Outcome::Unverified(error.to_string());
+    };
+    let local_answer = answer(local).expect("local computation gives an answer for every number");
+    if api_answer == local_answer {
+        Outcome::Match
+    } else {
+        Outcome::Mismatch {
+            api: api_answer,
+            local: local_answer,
+        }
+    }
+}
+
+/// Returns what a result says about its number, or `None` if it is an error which says nothing about it.
+fn answer(result: &Result<IsEvenApiResponse, IsEvenApiError>) -> Option<&'static str> {
+    match result {
+        Ok(response) if response.iseven() => Some("even"),
+        Ok(_) => Some("odd"),
+        Err(
+            IsEvenApiError::Api(ApiError::InvalidNumber(_)) | IsEvenApiError::InvalidInput { .. },
+        ) => Some("invalid number"),
+        Err(IsEvenApiError::Api(ApiError::NumberOutOfRange(_))) => Some("out of range"),
+        Err(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare() {
+        let local = offline::check("7", Plan::Free, None);
+        assert_eq!(compare(&local, &local), Outcome::Match);
+        let even = offline::check("8", Plan::Free, None);
+        assert_eq!(
+            compare(&even, &local),
+            Outcome::Mismatch {
+                api: "even",
+                local: "odd"
+            }
+        );
+        let invalid = offline::check("abc", Plan::Free, None);
+        assert_eq!(compare(&invalid, &invalid), Outcome::Match);
+    }
+}
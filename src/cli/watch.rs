@@ -0,0 +1,99 @@
+//! Periodic re-checking of numbers, reporting changes (`--watch`).
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant, SystemTime},
+};
+
+use super::{client::Client, color::Colors, input::Input, interrupt::Interrupt, stats::error_kind};
+
+/// Last reported state of a number.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum State {
+    Parity(bool),
+    /// Error kind, as returned by [`error_kind`], and message
+    Error(&'static str, String),
+}
+
+impl State {
+    /// Returns `true` if `other` differs enough to be reported. Errors of the same kind count as the same state, as
+    /// their messages may contain details which change between requests.
+    fn changed_from(&self, other: &State) -> bool {
+        match (self, other) {
+            (State::Error(kind, _), State::Error(other_kind, _)) => kind != other_kind,
+            _ => self != other,
+        }
+    }
+
+    fn describe(&self, colors: Colors) -> String {
+        match self {
+            State::Parity(iseven) => colors.parity(*iseven),
+            State::Error(_, message) => colors.error(format_args!("error: {}", message)),
+        }
+    }
+}
+
+/// Checks `inputs` every `interval` until Ctrl-C is pressed, printing the first result for each number and then
+/// only results which differ from the previous one.
+pub async fn run(
+    client: &Client,
+    inputs: &[Input],
+    interval: Duration,
+    interrupt: &Interrupt,
+    colors: Colors,
+) {
+    let mut states: HashMap<&str, State> = HashMap::new();
+    loop {
+        let round_started = Instant::now();
+        for input in inputs {
+            if interrupt.is_set() {
+                return;
+            }
+            let result = tokio::select! {
+                biased;
+                _ = interrupt.aborted() => return,
+                result = client.get(&input.number) => result,
+            };
+            let state = match result {
+                Ok(response) => State::Parity(response.iseven()),
+                Err(e) => State::Error(error_kind(&e), e.to_string()),
+            };
+            let time = humantime::format_rfc3339_seconds(SystemTime::now());
+            match states.get(input.number.as_str()) {
+                None => println!("[{}] {}: {}", time, input, state.describe(colors)),
+                Some(previous) if state.changed_from(previous) => println!(
+                    "[{}] {}: {} -> {}",
+                    time,
+                    input,
+                    previous.describe(colors),
+                    state.describe(colors)
+                ),
+                Some(_) => continue,
+            }
+            states.insert(&input.number, state);
+        }
+        tokio::select! {
+            _ = interrupt.stopped() => return,
+            _ = tokio::time::sleep(interval.saturating_sub(round_started.elapsed())) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changed_from() {
+        let even = State::Parity(true);
+        let odd = State::Parity(false);
+        let timeout = State::Error("network_error", "timed out".to_string());
+        let refused = State::Error("network_error", "connection refused".to_string());
+        let invalid = State::Error("invalid_number", "Invalid number.".to_string());
+        assert!(odd.changed_from(&even));
+        assert!(timeout.changed_from(&even));
+        assert!(!refused.changed_from(&timeout));
+        assert!(invalid.changed_from(&timeout));
+        assert!(!even.changed_from(&even));
+    }
+}
@@ -0,0 +1,125 @@
+//! Parsing of numbers written as English words (`--words`).
+
+/// Numbers below twenty.
+const UNITS: [&str; 20] = [
+    "zero",
+    "one",
+    "two",
+    "three",
+    "four",
+    "five",
+    "six",
+    "seven",
+    "eight",
+    "nine",
+    "ten",
+    "eleven",
+    "twelve",
+    "thirteen",
+    "fourteen",
+    "fifteen",
+    "sixteen",
+    "seventeen",
+    "eighteen",
+    "nineteen",
+];
+
+/// Multiples of ten from twenty, starting at index 2.
+const TENS: [&str; 10] = [
+    "", "", "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+];
+
+/// Scale words, from largest to smallest.
+const SCALES: [(&str, u64); 4] = [
+    ("trillion", 1_000_000_000_000),
+    ("billion", 1_000_000_000),
+    ("million", 1_000_000),
+    ("thousand", 1_000),
+];
+
+/// Parses a number written in English words, such as "forty-two", "one hundred and seven" or "minus three".
+///
+/// Returns `None` if the text is not a number in words.
+pub fn parse(text: &str) -> Option<i64> {
+    let text = text.to_lowercase();
+    let mut words = text
+        .split(|c: char| c.is_whitespace() || c == '-' || c == ',')
+        .filter(|w| !w.is_empty() && *w != "and")
+        .peekable();
+    let negative = words
+        .next_if(|w| *w == "minus" || *w == "negative")
+        .is_some();
+    let words = words.collect::<Vec<_>>();
+    if words == ["zero"] {
+        return Some(0);
+    }
+
+    let mut total: u64 = 0;
+    // value of the group below the current scale, e.g. 107 in "one hundred and seven thousand"
+    let mut group: u64 = 0;
+    // smallest scale seen so far, as scales must decrease
+    let mut last_scale = u64::MAX;
+    for word in &words {
+        if let Some(unit) = UNITS[1..].iter().position(|u| u == word) {
+            // a unit may only follow nothing, a hundred or a multiple of ten
+            if !group.is_multiple_of(100) && (!group.is_multiple_of(10) || group % 100 < 20) {
+                return None;
+            }
+            group += unit as u64 + 1;
+        } else if let Some(tens) = TENS[2..].iter().position(|t| t == word) {
+            if !group.is_multiple_of(100) {
+                return None;
+            }
+            group += (tens as u64 + 2) * 10;
+        } else if *word == "hundred" {
+            if group == 0 || group >= 10 {
+                return None;
+            }
+            group *= 100;
+        } else if let Some(&(_, scale)) = SCALES.iter().find(|(s, _)| s == word) {
+            if group == 0 || scale >= last_scale {
+                return None;
+            }
+            total = total.checked_add(group.checked_mul(scale)?)?;
+            group = 0;
+            last_scale = scale;
+        } else {
+            return None;
+        }
+    }
+    if words.is_empty() {
+        return None;
+    }
+    let value = i64::try_from(total.checked_add(group)?).ok()?;
+    Some(if negative { -value } else { value })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("forty-two"), Some(42));
+        assert_eq!(parse("one hundred and seven"), Some(107));
+        assert_eq!(parse("Zero"), Some(0));
+        assert_eq!(parse("minus three"), Some(-3));
+        assert_eq!(parse("nineteen"), Some(19));
+        assert_eq!(
+            parse("two million, three hundred thousand and twelve"),
+            Some(2_300_012)
+        );
+        assert_eq!(parse("twelve hundred"), None);
+        for invalid in [
+            "",
+            "and",
+            "forty forty",
+            "seven three",
+            "thousand",
+            "one thousand million",
+            "forty-two apples",
+        ] {
+            assert_eq!(parse(invalid), None, "{}", invalid);
+        }
+    }
+}
@@ -0,0 +1,173 @@
+//! Sources of the current time, so that time-dependent behavior can be tested without waiting.
+
+use std::{
+    fmt::Debug,
+    future::Future,
+    pin::Pin,
+    time::{Duration, Instant, SystemTime},
+};
+#[cfg(not(feature = "client"))]
+use std::{
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+};
+
+/// Future returned by [`Clock::sleep`].
+pub type Sleep = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// A source of the current time, and a way of waiting for time to pass.
+///
+/// Retry delays and rate limiting wait with the clock of the client, set with
+/// [`IsEvenApiClientBuilder::clock`](crate::IsEvenApiClientBuilder::clock). Caches and rate limiters, which may be
+/// shared between clients, read the time from their own clocks, set with
+/// [`ResponseCache::with_clock`](crate::ResponseCache::with_clock) and
+/// [`RateLimiter::with_clock`](crate::RateLimiter::with_clock). All of them use [`SystemClock`] by default.
+///
+/// Tests can use `MockClock` from the `test-util` feature instead, whose time only passes when told to.
+pub trait Clock: Debug + Send + Sync {
+    /// Returns the current monotonic time, used for measuring intervals.
+    fn now(&self) -> Instant;
+
+    /// Returns the current wall-clock time, used for timestamps such as when responses were cached.
+    fn system_time(&self) -> SystemTime;
+
+    /// Returns a future which completes once `duration` has passed.
+    ///
+    /// By default, this is a Tokio timer with the `client` feature, and otherwise a timer on a background thread which
+    /// works with any executor. Either way, the real time is waited for, so clocks whose time is not the real time
+    /// should implement this.
+    fn sleep(&self, duration: Duration) -> Sleep {
+        #[cfg(feature = "client")]
+        return Box::pin(tokio::time::sleep(duration));
+        #[cfg(not(feature = "client"))]
+        return Box::pin(ThreadSleep::new(duration));
+    }
+
+    /// Blocks the current thread until `duration` has passed.
+    fn sleep_blocking(&self, duration: Duration);
+}
+
+/// The real time, as told by the operating system.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn system_time(&self) -> SystemTime {
+        SystemTime::now()
+    }
+
+    fn sleep_blocking(&self, duration: Duration) {
+        std::thread::sleep(duration);
+    }
+}
+
+/// Future which completes once a deadline has passed, woken by a thread sleeping until then, for sleeping without
+/// Tokio.
+#[cfg(not(feature = "client"))]
+#[derive(Debug)]
+struct ThreadSleep {
+    deadline: Instant,
+    /// Waker of the latest poll, shared with the thread once it is started
+    waker: Option<Arc<Mutex<Waker>>>,
+}
+
+#[cfg(not(feature = "client"))]
+impl ThreadSleep {
+    fn new(duration: Duration) -> Self {
+        Self {
+            deadline: Instant::now() + duration,
+            waker: None,
+        }
+    }
+}
+
+#[cfg(not(feature = "client"))]
+impl Future for ThreadSleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if Instant::now() >= self.deadline {
+            return Poll::Ready(());
+        }
+        match &self.waker {
+            Some(waker) => waker
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .clone_from(cx.waker()),
+            None => {
+                let waker = Arc::new(Mutex::new(cx.waker().clone()));
+                let deadline = self.deadline;
+                let shared = waker.clone();
+                std::thread::spawn(move || {
+                    std::thread::sleep(deadline.saturating_duration_since(Instant::now()));
+                    shared
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .wake_by_ref();
+                });
+                self.waker = Some(waker);
+            }
+        }
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    /// A clock implemented without `sleep`, as a crate building without the `client` feature would.
+    #[derive(Debug)]
+    struct WallClock;
+
+    impl Clock for WallClock {
+        fn now(&self) -> Instant {
+            Instant::now()
+        }
+
+        fn system_time(&self) -> SystemTime {
+            SystemTime::now()
+        }
+
+        fn sleep_blocking(&self, duration: Duration) {
+            thread::sleep(duration);
+        }
+    }
+
+    #[cfg(not(feature = "client"))]
+    #[test]
+    fn test_default_sleep() {
+        use std::task::Wake;
+
+        struct Unpark(thread::Thread);
+
+        impl Wake for Unpark {
+            fn wake(self: Arc<Self>) {
+                self.0.unpark();
+            }
+        }
+
+        let waker = Arc::new(Unpark(thread::current())).into();
+        let mut cx = Context::from_waker(&waker);
+        let start = Instant::now();
+        let mut sleep = WallClock.sleep(Duration::from_millis(20));
+        while sleep.as_mut().poll(&mut cx).is_pending() {
+            thread::park();
+        }
+        assert!(start.elapsed() >= Duration::from_millis(20));
+    }
+
+    #[cfg(feature = "client")]
+    #[tokio::test]
+    async fn test_default_sleep() {
+        let start = Instant::now();
+        WallClock.sleep(Duration::from_millis(20)).await;
+        assert!(start.elapsed() >= Duration::from_millis(20));
+    }
+}
@@ -0,0 +1,437 @@
+//! Errors which can result from checking a number.
+
+use crate::{input, IsEvenApiErrorResponse, StatusCode};
+
+/// An error type containing errors which can result from the API call.
+///
+/// Errors are grouped by the layer they come from: [`TransportError`]s happen while sending the request or receiving
+/// the response, [`ProtocolError`]s when a response is not in the format of the API, and [`ApiError`]s are error
+/// responses from the API itself. Use [`Self::kind`] to tell the layers apart without matching on the errors.
+///
+/// All error enums are non-exhaustive, so that new kinds of errors can be added without breaking existing matches.
+///
+/// # Examples
+///
+#[cfg_attr(feature = "client", doc = "```no_run")]
+#[cfg_attr(not(feature = "client"), doc = "```ignore")]
+/// # use std::error::Error;
+/// use iseven_api::{ApiError, IsEvenApiClient, IsEvenApiError};
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn Error>> {
+/// let client = IsEvenApiClient::new();
+/// match client.get(1000000).await {
+///     Ok(response) => println!("{}", response),
+///     Err(IsEvenApiError::Api(ApiError::NumberOutOfRange(_))) => println!("upgrade your plan"),
+///     Err(e) if e.is_transient() => println!("try again later: {}", e),
+///     Err(e) => return Err(e.into()),
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(thiserror::Error, Debug)]
+#[non_exhaustive]
+pub enum IsEvenApiError {
+    /// The request could not be sent, or its response could not be received
+    #[error(transparent)]
+    Transport(#[from] TransportError),
+    /// The response was not in the format of the API
+    #[error(transparent)]
+    Protocol(#[from] ProtocolError),
+    /// The API answered with an error response
+    #[error(transparent)]
+    Api(#[from] ApiError),
+    /// Input which cannot be a valid number, rejected by the client without making a request
+    #[error("invalid input {input:?}: {reason}")]
+    InvalidInput {
+        /// The input as given
+        input: String,
+        /// What is wrong with the input
+        reason: input::ParseError,
+    },
+}
+
+/// Errors in sending a request or receiving its response.
+///
+/// Each error carries the ID of the request it happened for, if the request was made by a client.
+#[derive(thiserror::Error, Debug)]
+#[non_exhaustive]
+pub enum TransportError {
+    /// Error in making the request with reqwest, e.g. a connection failure or a timeout, along with the request ID
+    #[cfg(feature = "client")]
+    #[error("network error: {0}")]
+    Http(#[source] reqwest::Error, Option<String>),
+    /// Error in making the request with the blocking client, which does not use reqwest when the `client` feature is
+    /// disabled, along with the request ID
+    #[cfg(feature = "blocking")]
+    #[error("network error: {0}")]
+    Io(#[source] std::io::Error, Option<String>),
+}
+
+/// Responses which are not in the format of the API.
+///
+/// Each error carries the ID of the request it answers if the request was made by a client, either alongside it or in
+/// the metadata of the error response.
+#[derive(thiserror::Error, Debug)]
+#[non_exhaustive]
+pub enum ProtocolError {
+    /// Successful response whose body is not an answer from the API, along with the request ID
+    #[error("invalid response: {0}")]
+    Decode(#[source] serde_json::Error, Option<String>),
+    /// Error response with a status code which the API does not use for any known error, such as a server error
+    #[error("Server returned status code {1}: {0}")]
+    UnexpectedStatus(IsEvenApiErrorResponse, StatusCode),
+    /// Unsuccessful response whose body is not an error response from the API, such as the error page of a gateway,
+    /// along with its body as text and the request ID
+    #[error("Server returned status code {1} with a body which is not an error response")]
+    UnexpectedBody(String, StatusCode, Option<String>),
+}
+
+/// Error responses from the API.
+#[derive(thiserror::Error, Debug)]
+#[non_exhaustive]
+pub enum ApiError {
+    /// Invalid number specified (HTTP 400)
+    #[error(transparent)]
+    InvalidNumber(IsEvenApiErrorResponse),
+    /// Number out of range for your [pricing plan](https://isevenapi.xyz/#pricing) (HTTP 401)
+    #[error(transparent)]
+    NumberOutOfRange(IsEvenApiErrorResponse),
+    /// Missing or invalid API key (HTTP 403)
+    #[error(transparent)]
+    Unauthorized(IsEvenApiErrorResponse),
+    /// Too many requests for your pricing plan (HTTP 429)
+    #[error(transparent)]
+    RateLimited(IsEvenApiErrorResponse),
+}
+
+/// The layer an [`IsEvenApiError`] comes from, returned by [`IsEvenApiError::kind`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// [`IsEvenApiError::Transport`]
+    Transport,
+    /// [`IsEvenApiError::Protocol`]
+    Protocol,
+    /// [`IsEvenApiError::Api`]
+    Api,
+    /// [`IsEvenApiError::InvalidInput`]
+    InvalidInput,
+}
+
+impl IsEvenApiError {
+    /// Returns the layer the error comes from.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            IsEvenApiError::Transport(_) => ErrorKind::Transport,
+            IsEvenApiError::Protocol(_) => ErrorKind::Protocol,
+            IsEvenApiError::Api(_) => ErrorKind::Api,
+            IsEvenApiError::InvalidInput { .. } => ErrorKind::InvalidInput,
+        }
+    }
+
+    /// Returns the error response from the API, if the error is one.
+    pub fn api_error(&self) -> Option<&ApiError> {
+        match self {
+            IsEvenApiError::Api(e) => Some(e),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if the error is likely to be temporary, meaning the same request may succeed if retried.
+    ///
+    /// This includes connection failures and timeouts, as well as rate limiting (HTTP 429) and server errors
+    /// (HTTP 5xx), whatever the body of the response.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            IsEvenApiError::Transport(e) => e.is_transient(),
+            IsEvenApiError::Protocol(
+                ProtocolError::UnexpectedStatus(_, status)
+                | ProtocolError::UnexpectedBody(_, status, _),
+            ) => *status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error(),
+            IsEvenApiError::Api(ApiError::RateLimited(_)) => true,
+            IsEvenApiError::Protocol(ProtocolError::Decode(..))
+            | IsEvenApiError::Api(_)
+            | IsEvenApiError::InvalidInput { .. } => false,
+        }
+    }
+
+    /// Returns the ID of the request which failed, for quoting in support requests or finding it in the client's debug
+    /// logs.
+    ///
+    /// Every error for a request made by a client has an ID, whether the request could not be sent, its response could
+    /// not be decoded or the API answered with an error response. Returns `None` for
+    /// [`IsEvenApiError::InvalidInput`], as no request was made, and for errors which did not come from a client, such
+    /// as ones returned by [`parse_response`](crate::sansio::parse_response).
+    pub fn request_id(&self) -> Option<&str> {
+        match self {
+            IsEvenApiError::Transport(e) => e.request_id(),
+            IsEvenApiError::Protocol(e) => e.request_id(),
+            IsEvenApiError::Api(e) => e.response().metadata().request_id(),
+            IsEvenApiError::InvalidInput { .. } => None,
+        }
+    }
+
+    /// Attaches the ID of the request the error happened for, unless the error already has one or cannot have one.
+    #[cfg(any(feature = "client", feature = "blocking"))]
+    pub(crate) fn with_request_id(mut self, request_id: &str) -> Self {
+        let slot = match &mut self {
+            IsEvenApiError::Transport(e) => e.request_id_mut(),
+            IsEvenApiError::Protocol(e) => e.request_id_mut(),
+            IsEvenApiError::Api(_) | IsEvenApiError::InvalidInput { .. } => None,
+        };
+        if let Some(slot @ None) = slot {
+            *slot = Some(request_id.to_string());
+        }
+        self
+    }
+
+    /// Returns the error response the API answered with, if any.
+    pub fn error_response(&self) -> Option<&IsEvenApiErrorResponse> {
+        match self {
+            IsEvenApiError::Api(e) => Some(e.response()),
+            IsEvenApiError::Protocol(ProtocolError::UnexpectedStatus(e, _)) => Some(e),
+            _ => None,
+        }
+    }
+
+    /// Returns the HTTP status code of the response which caused the error, if there was one.
+    ///
+    /// [`IsEvenApiError::InvalidInput`] has no status code, as no request was made, which tells inputs rejected by the
+    /// client apart from ones rejected by the API.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            IsEvenApiError::Api(e) => Some(e.status()),
+            IsEvenApiError::Protocol(
+                ProtocolError::UnexpectedStatus(_, status)
+                | ProtocolError::UnexpectedBody(_, status, _),
+            ) => Some(*status),
+            IsEvenApiError::Transport(e) => e.status(),
+            IsEvenApiError::Protocol(ProtocolError::Decode(..))
+            | IsEvenApiError::InvalidInput { .. } => None,
+        }
+    }
+}
+
+impl TransportError {
+    /// Returns `true` if the error is likely to be temporary, such as a connection failure or a timeout.
+    pub fn is_transient(&self) -> bool {
+        match *self {
+            #[cfg(feature = "client")]
+            TransportError::Http(ref e, _) => {
+                e.is_connect()
+                    || e.is_timeout()
+                    || e.status()
+                        .is_some_and(|s| s == StatusCode::TOO_MANY_REQUESTS || s.is_server_error())
+            }
+            #[cfg(feature = "blocking")]
+            TransportError::Io(ref e, _) => matches!(
+                e.kind(),
+                std::io::ErrorKind::ConnectionRefused
+                    | std::io::ErrorKind::ConnectionReset
+                    | std::io::ErrorKind::ConnectionAborted
+                    | std::io::ErrorKind::TimedOut
+                    | std::io::ErrorKind::UnexpectedEof
+            ),
+        }
+    }
+
+    /// Returns the HTTP status code of the response which caused the error, if there was one.
+    pub fn status(&self) -> Option<StatusCode> {
+        match *self {
+            #[cfg(feature = "client")]
+            TransportError::Http(ref e, _) => e.status(),
+            #[cfg(feature = "blocking")]
+            TransportError::Io(..) => None,
+        }
+    }
+
+    /// Returns the ID of the request the error happened for, if it was made by a client.
+    pub fn request_id(&self) -> Option<&str> {
+        match *self {
+            #[cfg(feature = "client")]
+            TransportError::Http(_, ref request_id) => request_id.as_deref(),
+            #[cfg(feature = "blocking")]
+            TransportError::Io(_, ref request_id) => request_id.as_deref(),
+        }
+    }
+
+    #[cfg(any(feature = "client", feature = "blocking"))]
+    fn request_id_mut(&mut self) -> Option<&mut Option<String>> {
+        match *self {
+            #[cfg(feature = "client")]
+            TransportError::Http(_, ref mut request_id) => Some(request_id),
+            #[cfg(feature = "blocking")]
+            TransportError::Io(_, ref mut request_id) => Some(request_id),
+        }
+    }
+}
+
+impl ProtocolError {
+    /// Returns the ID of the request the response answers, if it was made by a client.
+    pub fn request_id(&self) -> Option<&str> {
+        match self {
+            ProtocolError::Decode(_, request_id)
+            | ProtocolError::UnexpectedBody(_, _, request_id) => request_id.as_deref(),
+            ProtocolError::UnexpectedStatus(e, _) => e.metadata().request_id(),
+        }
+    }
+
+    #[cfg(any(feature = "client", feature = "blocking"))]
+    fn request_id_mut(&mut self) -> Option<&mut Option<String>> {
+        match self {
+            ProtocolError::Decode(_, request_id)
+            | ProtocolError::UnexpectedBody(_, _, request_id) => Some(request_id),
+            ProtocolError::UnexpectedStatus(..) => None,
+        }
+    }
+}
+
+impl ApiError {
+    /// Returns the error response from the API.
+    pub fn response(&self) -> &IsEvenApiErrorResponse {
+        match self {
+            ApiError::InvalidNumber(e)
+            | ApiError::NumberOutOfRange(e)
+            | ApiError::Unauthorized(e)
+            | ApiError::RateLimited(e) => e,
+        }
+    }
+
+    /// Returns the HTTP status code the API answers with for the error.
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ApiError::InvalidNumber(_) => StatusCode::BAD_REQUEST,
+            ApiError::NumberOutOfRange(_) => StatusCode::UNAUTHORIZED,
+            ApiError::Unauthorized(_) => StatusCode::FORBIDDEN,
+            ApiError::RateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
+        }
+    }
+
+    /// Returns the error for `response` answered with `status`, or gives `response` back if the status is not used for
+    /// any known error.
+    pub fn from_status(
+        status: StatusCode,
+        response: IsEvenApiErrorResponse,
+    ) -> Result<Self, IsEvenApiErrorResponse> {
+        match status {
+            StatusCode::BAD_REQUEST => Ok(ApiError::InvalidNumber(response)),
+            StatusCode::UNAUTHORIZED => Ok(ApiError::NumberOutOfRange(response)),
+            StatusCode::FORBIDDEN => Ok(ApiError::Unauthorized(response)),
+            StatusCode::TOO_MANY_REQUESTS => Ok(ApiError::RateLimited(response)),
+            _ => Err(response),
+        }
+    }
+}
+
+#[cfg(feature = "client")]
+impl From<reqwest::Error> for TransportError {
+    fn from(e: reqwest::Error) -> Self {
+        TransportError::Http(e, None)
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl From<std::io::Error> for TransportError {
+    fn from(e: std::io::Error) -> Self {
+        TransportError::Io(e, None)
+    }
+}
+
+impl From<serde_json::Error> for ProtocolError {
+    fn from(e: serde_json::Error) -> Self {
+        ProtocolError::Decode(e, None)
+    }
+}
+
+#[cfg(feature = "client")]
+impl From<reqwest::Error> for IsEvenApiError {
+    fn from(e: reqwest::Error) -> Self {
+        IsEvenApiError::Transport(e.into())
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl From<std::io::Error> for IsEvenApiError {
+    fn from(e: std::io::Error) -> Self {
+        IsEvenApiError::Transport(e.into())
+    }
+}
+
+impl From<serde_json::Error> for IsEvenApiError {
+    fn from(e: serde_json::Error) -> Self {
+        IsEvenApiError::Protocol(e.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response() -> IsEvenApiErrorResponse {
+        serde_json::from_str(r#"{"error": "error"}"#).unwrap()
+    }
+
+    #[test]
+    fn test_from_status() {
+        for status in [400, 401, 403, 429] {
+            let status = StatusCode::from_u16(status).unwrap();
+            let error = ApiError::from_status(status, response()).unwrap();
+            assert_eq!(error.status(), status);
+            assert_eq!(IsEvenApiError::from(error).status(), Some(status));
+        }
+        assert!(ApiError::from_status(StatusCode::BAD_GATEWAY, response()).is_err());
+    }
+
+    #[test]
+    fn test_kind() {
+        let rate_limited = IsEvenApiError::from(ApiError::RateLimited(response()));
+        assert_eq!(rate_limited.kind(), ErrorKind::Api);
+        assert!(rate_limited.is_transient());
+        assert!(rate_limited.api_error().is_some());
+
+        let out_of_range = IsEvenApiError::from(ApiError::NumberOutOfRange(response()));
+        assert!(!out_of_range.is_transient());
+
+        let server_error = IsEvenApiError::from(ProtocolError::UnexpectedStatus(
+            response(),
+            StatusCode::SERVICE_UNAVAILABLE,
+        ));
+        assert_eq!(server_error.kind(), ErrorKind::Protocol);
+        assert!(server_error.is_transient());
+        assert!(server_error.api_error().is_none());
+        assert!(server_error.error_response().is_some());
+
+        let decode = IsEvenApiError::from(serde_json::from_str::<u8>("").unwrap_err());
+        assert_eq!(decode.kind(), ErrorKind::Protocol);
+        assert_eq!(decode.status(), None);
+
+        for status in [
+            StatusCode::TOO_MANY_REQUESTS,
+            StatusCode::SERVICE_UNAVAILABLE,
+        ] {
+            let error_page = IsEvenApiError::from(ProtocolError::UnexpectedBody(
+                "<html></html>".to_string(),
+                status,
+                None,
+            ));
+            assert_eq!(error_page.status(), Some(status));
+            assert!(error_page.is_transient());
+        }
+    }
+
+    #[cfg(any(feature = "client", feature = "blocking"))]
+    #[test]
+    fn test_with_request_id() {
+        let decode = IsEvenApiError::from(serde_json::from_str::<u8>("").unwrap_err());
+        assert_eq!(decode.request_id(), None);
+        let decode = decode.with_request_id("1").with_request_id("2");
+        assert_eq!(decode.request_id(), Some("1"));
+
+        let invalid_input = IsEvenApiError::InvalidInput {
+            input: "abc".to_string(),
+            reason: input::ParseError::Empty,
+        };
+        assert_eq!(invalid_input.with_request_id("1").request_id(), None);
+    }
+}
@@ -0,0 +1,163 @@
+//! C bindings, enabled with the `ffi` feature.
+//!
+//! The functions here are exported from the `cdylib` build of the crate and declared in `include/iseven_api.h`,
+//! which is generated with [cbindgen](https://github.com/mozilla/cbindgen):
+//!
+//! ```text
+//! cbindgen --config cbindgen.toml --output include/iseven_api.h
+//! ```
+//!
+//! Checks are made with a [`IsEvenApiBlockingClient`] shared by all threads, so C callers get connection pooling
+//! without managing a client themselves.
+
+use std::{
+    ffi::{c_char, CStr, CString},
+    ptr,
+    sync::OnceLock,
+};
+
+use crate::{ApiError, IsEvenApiBlockingClient, IsEvenApiError};
+
+/// Outcome of [`iseven_check`].
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IsEvenStatus {
+    /// The number was checked, and `iseven` and `ad` are set
+    Ok = 0,
+    /// The API rejected the number as invalid
+    InvalidNumber = 1,
+    /// The number is outside the range allowed by the plan
+    NumberOutOfRange = 2,
+    /// The API answered with an unexpected error response
+    ErrorResponse = 3,
+    /// The API could not be reached, or its response could not be read
+    NetworkError = 4,
+    /// `number` or `out` is null, or `number` is not valid UTF-8
+    InvalidArgument = 5,
+    /// `number` cannot be a valid number, and was rejected without making a request
+    InvalidInput = 6,
+}
+
+/// Result of [`iseven_check`]. Strings are owned by the library and freed with [`iseven_result_free`].
+#[repr(C)]
+#[derive(Debug)]
+pub struct IsEvenResult {
+    /// Whether the number is even, if the check succeeded
+    pub iseven: bool,
+    /// Advertisement in the response if the check succeeded, otherwise null
+    pub ad: *mut c_char,
+    /// Description of the error if the check failed, otherwise null
+    pub error: *mut c_char,
+}
+
+fn client() -> &'static IsEvenApiBlockingClient {
+    static CLIENT: OnceLock<IsEvenApiBlockingClient> = OnceLock::new();
+    CLIENT.get_or_init(IsEvenApiBlockingClient::new)
+}
+
+/// Converts `s` to a C string owned by the caller, dropping any interior nul bytes.
+fn to_c_string(s: &str) -> *mut c_char {
+    CString::new(s.replace('\0', ""))
+        .expect("nul bytes were removed")
+        .into_raw()
+}
+
+fn status(error: &IsEvenApiError) -> IsEvenStatus {
+    match error {
+        IsEvenApiError::Api(ApiError::InvalidNumber(_)) => IsEvenStatus::InvalidNumber,
+        IsEvenApiError::Api(ApiError::NumberOutOfRange(_)) => IsEvenStatus::NumberOutOfRange,
+        IsEvenApiError::Api(_) | IsEvenApiError::Protocol(_) => IsEvenStatus::ErrorResponse,
+        IsEvenApiError::Transport(_) => IsEvenStatus::NetworkError,
+        IsEvenApiError::InvalidInput { .. } => IsEvenStatus::InvalidInput,
+    }
+}
+
+/// Checks whether `number`, given in decimal, is even, storing the result in `out`.
+///
+/// `out` is always overwritten unless the status is `InvalidArgument`, and must be passed to
+/// [`iseven_result_free`] afterwards. This function blocks until the API answers, and may be called from any thread.
+///
+/// # Safety
+///
+/// `number` must be null or point to a nul-terminated string, and `out` must be null or point to memory valid for
+/// writing an `IsEvenResult`.
+#[no_mangle]
+pub unsafe extern "C" fn iseven_check(
+    number: *const c_char,
+    out: *mut IsEvenResult,
+) -> IsEvenStatus {
+    if number.is_null() || out.is_null() {
+        return IsEvenStatus::InvalidArgument;
+    }
+    let Ok(number) = CStr::from_ptr(number).to_str() else {
+        return IsEvenStatus::InvalidArgument;
+    };
+    let (result, status) = match client().get_str(number) {
+        Ok(response) => (
+            IsEvenResult {
+                iseven: response.iseven(),
+                ad: to_c_string(response.ad()),
+                error: ptr::null_mut(),
+            },
+            IsEvenStatus::Ok,
+        ),
+        Err(e) => (
+            IsEvenResult {
+                iseven: false,
+                ad: ptr::null_mut(),
+                error: to_c_string(&e.to_string()),
+            },
+            status(&e),
+        ),
+    };
+    out.write(result);
+    status
+}
+
+/// Frees the strings in a result filled in by [`iseven_check`], leaving them null. Freeing a result twice is
+/// harmless.
+///
+/// # Safety
+///
+/// `result` must be null or point to a result filled in by `iseven_check`, or freed with this function.
+#[no_mangle]
+pub unsafe extern "C" fn iseven_result_free(result: *mut IsEvenResult) {
+    let Some(result) = result.as_mut() else {
+        return;
+    };
+    for s in [&mut result.ad, &mut result.error] {
+        if !s.is_null() {
+            drop(CString::from_raw(*s));
+            *s = ptr::null_mut();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invalid_argument() {
+        let mut result = IsEvenResult {
+            iseven: false,
+            ad: ptr::null_mut(),
+            error: ptr::null_mut(),
+        };
+        unsafe {
+            assert_eq!(
+                iseven_check(ptr::null(), &mut result),
+                IsEvenStatus::InvalidArgument
+            );
+            assert_eq!(
+                iseven_check(c"42".as_ptr(), ptr::null_mut()),
+                IsEvenStatus::InvalidArgument
+            );
+            result.error = to_c_string("message");
+            iseven_result_free(&mut result);
+            assert!(result.error.is_null());
+            iseven_result_free(&mut result);
+            iseven_result_free(ptr::null_mut());
+        }
+    }
+}
@@ -0,0 +1,508 @@
+//! Replaying of recorded API responses, for testing the whole client stack without the live API.
+//!
+//! A [`Cassette`] is a list of responses recorded from the API, which a [`CassetteServer`] plays back to clients.
+//! Unlike the [mock server](crate::mock), which computes answers, cassettes can reproduce anything the API or the
+//! infrastructure in front of it ever sent, such as rate limiting, server errors or bodies which are not JSON.
+//! Cassettes for the common cases are bundled as [`Scenario`]s, and more can be recorded with [`Cassette::record`] or
+//! written by hand.
+
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    io,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::{Response, StatusCode},
+    routing::get,
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    net::{TcpListener, ToSocketAddrs},
+    task::JoinHandle,
+};
+
+use crate::{input, sansio, Url};
+
+/// Headers which describe the connection rather than the response, so are not recorded.
+const SKIPPED_HEADERS: &[&str] = &["connection", "content-length", "date", "transfer-encoding"];
+
+/// A response recorded from the API, along with the number it answered.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Interaction {
+    number: String,
+    #[serde(with = "status_code")]
+    status: StatusCode,
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+    body: String,
+}
+
+impl Interaction {
+    /// Creates an interaction answering `number` with `status` and `body`. The number is converted to the canonical
+    /// form which clients request, as described in [`input::normalize`].
+    pub fn new(number: impl Display, status: StatusCode, body: impl Into<String>) -> Self {
+        Self {
+            number: input::normalize(&number.to_string()).into_owned(),
+            status,
+            headers: BTreeMap::new(),
+            body: body.into(),
+        }
+    }
+
+    /// Adds a header to the response.
+    pub fn with_header(mut self, name: &str, value: impl Into<String>) -> Self {
+        self.headers.insert(name.to_ascii_lowercase(), value.into());
+        self
+    }
+
+    /// Returns the number the response is for.
+    pub fn number(&self) -> &str {
+        &self.number
+    }
+
+    /// Returns the status code of the response.
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// Returns the headers of the response, with lowercase names.
+    pub fn headers(&self) -> &BTreeMap<String, String> {
+        &self.headers
+    }
+
+    /// Returns the body of the response.
+    pub fn body(&self) -> &str {
+        &self.body
+    }
+
+    /// Returns the interaction as an HTTP response.
+    fn response(&self) -> Response<Body> {
+        let mut response = Response::builder().status(self.status);
+        for (name, value) in &self.headers {
+            response = response.header(name, value);
+        }
+        response
+            .body(Body::from(self.body.clone()))
+            .unwrap_or_else(|e| {
+                Response::builder()
+                    .status(StatusCode::INTERNAL_SERVER_ERROR)
+                    .body(Body::from(format!("invalid interaction: {}", e)))
+                    .expect("valid response")
+            })
+    }
+}
+
+/// Serializes status codes as numbers.
+mod status_code {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    use super::StatusCode;
+
+    pub(super) fn serialize<S: Serializer>(
+        status: &StatusCode,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u16(status.as_u16())
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<StatusCode, D::Error> {
+        StatusCode::from_u16(u16::deserialize(deserializer)?).map_err(D::Error::custom)
+    }
+}
+
+/// Responses recorded from the API, in the order they were received.
+///
+/// Cassettes are stored as JSON, with the body of each response as a string so that bodies which are not JSON can be
+/// recorded too:
+///
+/// ```json
+/// {
+///   "interactions": [
+///     {
+///       "number": "42",
+///       "status": 200,
+///       "headers": { "content-type": "application/json; charset=utf-8" },
+///       "body": "{\"ad\": \"Buy isEvenCoin, the hottest new cryptocurrency!\", \"iseven\": true}"
+///     }
+///   ]
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cassette {
+    interactions: Vec<Interaction>,
+}
+
+impl Cassette {
+    /// Creates an empty cassette.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the bundled cassette for `scenario`.
+    pub fn scenario(scenario: Scenario) -> Self {
+        Self::from_json(scenario.json()).expect("bundled cassettes are valid")
+    }
+
+    /// Parses a cassette from JSON.
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+
+    /// Returns the cassette as pretty-printed JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("cassettes are serializable")
+    }
+
+    /// Records the responses of the API at `base_url` for each of `numbers`, in order.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a request fails without a response.
+    pub async fn record<I>(base_url: &Url, numbers: I) -> reqwest::Result<Self>
+    where
+        I: IntoIterator,
+        I::Item: Display,
+    {
+        let client = reqwest::Client::new();
+        let mut cassette = Self::new();
+        for number in numbers {
+            let response = client
+                .get(sansio::request_url(base_url.as_str(), &number))
+                .send()
+                .await?;
+            let mut interaction = Interaction::new(number, response.status(), "");
+            for (name, value) in response.headers() {
+                if let Ok(value) = value.to_str() {
+                    if !SKIPPED_HEADERS.contains(&name.as_str()) {
+                        interaction = interaction.with_header(name.as_str(), value);
+                    }
+                }
+            }
+            interaction.body = response.text().await?;
+            cassette = cassette.with(interaction);
+        }
+        Ok(cassette)
+    }
+
+    /// Adds `interaction` after the existing ones.
+    pub fn with(mut self, interaction: Interaction) -> Self {
+        self.interactions.push(interaction);
+        self
+    }
+
+    /// Returns the interactions in the cassette.
+    pub fn interactions(&self) -> &[Interaction] {
+        &self.interactions
+    }
+}
+
+/// Situations covered by the bundled cassettes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Scenario {
+    /// Answers for 0, 1, 2, 3, 5, 8, 9, 10, 5283 and 88888.
+    Answers,
+    /// 42 rejected as an invalid number with HTTP 400.
+    InvalidNumber,
+    /// 1000000, 2147483647 and -1 rejected as out of range for the free plan with HTTP 401.
+    NumberOutOfRange,
+    /// 42 rejected with HTTP 429 and a `Retry-After` header, then answered.
+    RateLimited,
+    /// 42 failing with HTTP 503, then 502, then answered.
+    ServerError,
+    /// 42 answered with an HTML error page instead of JSON.
+    GarbageBody,
+}
+
+impl Scenario {
+    /// All scenarios.
+    pub const ALL: [Scenario; 6] = [
+        Scenario::Answers,
+        Scenario::InvalidNumber,
+        Scenario::NumberOutOfRange,
+        Scenario::RateLimited,
+        Scenario::ServerError,
+        Scenario::GarbageBody,
+    ];
+
+    fn json(self) -> &'static str {
+        match self {
+            Scenario::Answers => include_str!("cassettes/answers.json"),
+            Scenario::InvalidNumber => include_str!("cassettes/invalid_number.json"),
+            Scenario::NumberOutOfRange => include_str!("cassettes/number_out_of_range.json"),
+            Scenario::RateLimited => include_str!("cassettes/rate_limited.json"),
+            Scenario::ServerError => include_str!("cassettes/server_error.json"),
+            Scenario::GarbageBody => include_str!("cassettes/garbage_body.json"),
+        }
+    }
+}
+
+/// Interactions of a cassette being played, and the numbers requested so far.
+#[derive(Debug)]
+struct Player {
+    cassette: Cassette,
+    played: Vec<bool>,
+    requests: Vec<String>,
+}
+
+impl Player {
+    /// Returns the first interaction for `number` which has not been played yet, or the last one if all of them have.
+    fn play(&mut self, number: String) -> Option<&Interaction> {
+        let interactions = &self.cassette.interactions;
+        let index = (0..interactions.len())
+            .find(|&i| !self.played[i] && interactions[i].number == number)
+            .or_else(|| interactions.iter().rposition(|i| i.number == number));
+        self.requests.push(number);
+        let index = index?;
+        self.played[index] = true;
+        Some(&interactions[index])
+    }
+}
+
+/// A local server playing back a [`Cassette`] in place of isEven API.
+///
+/// Each request is answered with the first interaction for its number which has not been played yet, so that e.g. a
+/// failure followed by a success can be recorded for testing retries. Once all interactions for a number have been
+/// played, the last one is repeated. Numbers without interactions are answered with HTTP 404.
+///
+/// The server runs on the current tokio runtime until the `CassetteServer` is dropped.
+///
+/// # Examples
+///
+/// ```
+/// # use std::error::Error;
+/// use iseven_api::{
+///     harness::{Cassette, CassetteServer, Scenario},
+///     IsEvenApiClient, IsEvenApiError, RetryPolicy,
+/// };
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn Error>> {
+/// let server = CassetteServer::start(Cassette::scenario(Scenario::RateLimited)).await?;
+/// let client = IsEvenApiClient::builder()
+///     .base_url(server.url())
+///     .retry_policy(RetryPolicy::new(1).with_delay(std::time::Duration::ZERO))
+///     .build()?;
+/// assert!(client.get(42).await?.iseven());
+/// assert_eq!(server.requests(), ["42", "42"]);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct CassetteServer {
+    addr: SocketAddr,
+    player: Arc<Mutex<Player>>,
+    handle: JoinHandle<()>,
+}
+
+impl CassetteServer {
+    /// Starts a server playing `cassette` on a free port of the loopback interface.
+    pub async fn start(cassette: Cassette) -> io::Result<Self> {
+        Self::start_on("127.0.0.1:0", cassette).await
+    }
+
+    /// Starts a server playing `cassette`, listening on `addr`.
+    pub async fn start_on<A: ToSocketAddrs>(addr: A, cassette: Cassette) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr).await?;
+        let addr = listener.local_addr()?;
+        let player = Arc::new(Mutex::new(Player {
+            played: vec![false; cassette.interactions.len()],
+            cassette,
+            requests: Vec::new(),
+        }));
+        let app = Router::new()
+            .route("/api/iseven/:number", get(play))
+            .with_state(player.clone());
+        let handle = tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, app).await {
+                log::error!("Cassette server failed: {}", e);
+            }
+        });
+        Ok(Self {
+            addr,
+            player,
+            handle,
+        })
+    }
+
+    /// Returns the address the server is listening on.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Returns the base URL of the API, for use with [`IsEvenApiClientBuilder::base_url`](crate::IsEvenApiClientBuilder::base_url).
+    pub fn url(&self) -> Url {
+        Url::parse(&format!("http://{}/api/iseven/", self.addr)).expect("valid URL")
+    }
+
+    /// Returns the numbers requested so far, in the order they were requested.
+    pub fn requests(&self) -> Vec<String> {
+        self.player.lock().unwrap().requests.clone()
+    }
+}
+
+impl Drop for CassetteServer {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+async fn play(
+    State(player): State<Arc<Mutex<Player>>>,
+    Path(number): Path<String>,
+) -> Response<Body> {
+    let mut player = player.lock().unwrap();
+    match player.play(number) {
+        Some(interaction) => interaction.response(),
+        None => Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .header("content-type", "application/json")
+            .body(Body::from(
+                r#"{"error": "No interaction recorded for this number."}"#,
+            ))
+            .expect("valid response"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{ApiError, IsEvenApiClient, IsEvenApiError, ProtocolError, RetryPolicy};
+
+    fn client(server: &CassetteServer, retries: u32) -> IsEvenApiClient {
+        IsEvenApiClient::builder()
+            .base_url(server.url())
+            .retry_policy(RetryPolicy::new(retries).with_delay(Duration::ZERO))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_cassette_json() {
+        for scenario in Scenario::ALL {
+            let cassette = Cassette::scenario(scenario);
+            assert!(!cassette.interactions().is_empty());
+            assert_eq!(Cassette::from_json(&cassette.to_json()).unwrap(), cassette);
+        }
+        let cassette = Cassette::new()
+            .with(Interaction::new("+042", StatusCode::OK, "{}").with_header("Retry-After", "1"));
+        assert_eq!(cassette.interactions()[0].number(), "42");
+        assert_eq!(cassette.interactions()[0].headers()["retry-after"], "1");
+        assert!(Cassette::from_json(
+            r#"{"interactions": [{"number": "1", "status": 1000, "body": ""}]}"#
+        )
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_scenarios() {
+        let server = CassetteServer::start(Cassette::scenario(Scenario::InvalidNumber))
+            .await
+            .unwrap();
+        let error = client(&server, 3).get(42).await.unwrap_err();
+        assert!(matches!(
+            error,
+            IsEvenApiError::Api(ApiError::InvalidNumber(_))
+        ));
+        assert_eq!(server.requests(), ["42"]);
+
+        let server = CassetteServer::start(Cassette::scenario(Scenario::RateLimited))
+            .await
+            .unwrap();
+        let error = client(&server, 0).get(42).await.unwrap_err();
+        assert_eq!(error.status(), Some(StatusCode::TOO_MANY_REQUESTS));
+        assert!(error.is_transient());
+        assert!(client(&server, 0).get(42).await.unwrap().iseven());
+
+        let server = CassetteServer::start(Cassette::scenario(Scenario::ServerError))
+            .await
+            .unwrap();
+        let error = client(&server, 1).get(42).await.unwrap_err();
+        assert_eq!(error.status(), Some(StatusCode::BAD_GATEWAY));
+        assert!(client(&server, 1).get(42).await.unwrap().iseven());
+        assert_eq!(server.requests(), ["42", "42", "42"]);
+
+        let server = CassetteServer::start(Cassette::scenario(Scenario::GarbageBody))
+            .await
+            .unwrap();
+        let error = client(&server, 3).get(42).await.unwrap_err();
+        assert!(matches!(
+            error,
+            IsEvenApiError::Protocol(ProtocolError::Decode(..))
+        ));
+        assert!(!error.is_transient());
+
+        let error = client(&server, 0).get(7).await.unwrap_err();
+        assert_eq!(error.status(), Some(StatusCode::NOT_FOUND));
+    }
+
+    #[tokio::test]
+    async fn test_error_page_retried() {
+        let cassette = Cassette::new()
+            .with(
+                Interaction::new(42, StatusCode::SERVICE_UNAVAILABLE, "Service Unavailable")
+                    .with_header("content-type", "text/plain"),
+            )
+            .with(Interaction::new(
+                42,
+                StatusCode::OK,
+                r#"{"ad": "ad", "iseven": true}"#,
+            ));
+        let server = CassetteServer::start(cassette.clone()).await.unwrap();
+        assert!(client(&server, 1).get(42).await.unwrap().iseven());
+        assert_eq!(server.requests(), ["42", "42"]);
+
+        let server = CassetteServer::start(cassette).await.unwrap();
+        let error = client(&server, 0).get(42).await.unwrap_err();
+        assert!(matches!(
+            error,
+            IsEvenApiError::Protocol(ProtocolError::UnexpectedBody(ref body, ..))
+                if body == "Service Unavailable"
+        ));
+        assert_eq!(error.status(), Some(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(error.request_id().is_some());
+    }
+
+    #[cfg(feature = "mock-server")]
+    #[tokio::test]
+    async fn test_record() {
+        use crate::{
+            mock::{self, MockServer},
+            Plan,
+        };
+
+        let mock = MockServer::start("127.0.0.1:0", Plan::Free).await.unwrap();
+        let cassette = Cassette::record(&mock.url(), ["42", "abc", "-1"])
+            .await
+            .unwrap();
+        let statuses: Vec<_> = cassette
+            .interactions()
+            .iter()
+            .map(Interaction::status)
+            .collect();
+        assert_eq!(
+            statuses,
+            [
+                StatusCode::OK,
+                StatusCode::BAD_REQUEST,
+                StatusCode::UNAUTHORIZED
+            ]
+        );
+        assert!(!cassette.interactions()[0]
+            .headers()
+            .contains_key("content-length"));
+
+        let server = CassetteServer::start(cassette).await.unwrap();
+        let replayed = client(&server, 0).get(42).await.unwrap();
+        assert_eq!(replayed, mock::respond("42", Plan::Free).unwrap());
+    }
+}
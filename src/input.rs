@@ -0,0 +1,336 @@
+//! Normalization and validation of numbers given as text, shared by the clients, the command line app and the proxy
+//! server.
+
+use std::borrow::Cow;
+
+/// Converts `number` to its canonical form, so that equivalent representations of a number such as `42`, ` 42`,
+/// `+42` and `042` share cache entries and request URLs.
+///
+/// Surrounding whitespace, a leading `+` and leading zeros are removed, and `-0` becomes `0`. Digit separators are
+/// also stripped, so that copy-pasted numbers like `1_000_000` and `1,000,000` are accepted. Underscores may separate
+/// any digits. Commas must separate groups of three digits, as in `1,000,000`, since in some locales a comma is a
+/// decimal separator instead. Anything which is not an integer after stripping separators, including separators in
+/// other positions, is returned unchanged, for the API to judge.
+///
+/// # Examples
+///
+/// ```
+/// use iseven_api::input::normalize;
+///
+/// assert_eq!(normalize(" +042 "), "42");
+/// assert_eq!(normalize("1_000_000"), "1000000");
+/// assert_eq!(normalize("-1,000,000"), "-1000000");
+/// assert_eq!(normalize("1,5"), "1,5");
+/// ```
+pub fn normalize(number: &str) -> Cow<'_, str> {
+    parse(number).unwrap_or(Cow::Borrowed(number))
+}
+
+/// Parses `number` as a decimal integer, returning it in the canonical form described in [`normalize`], or an error
+/// describing what is wrong with it.
+///
+/// Unlike [`normalize`], which passes anything it does not understand through for the API to judge, this rejects
+/// inputs which the API would reject anyway, so they can be reported without making a request.
+///
+/// # Examples
+///
+/// ```
+/// use iseven_api::input::{parse, ParseError};
+///
+/// assert_eq!(parse(" +1_000 ").unwrap(), "1000");
+/// assert_eq!(parse(""), Err(ParseError::Empty));
+/// assert_eq!(parse("4.2"), Err(ParseError::InvalidCharacter { character: '.', position: 2 }));
+/// ```
+pub fn parse(number: &str) -> Result<Cow<'_, str>, ParseError> {
+    let trimmed = number.trim();
+    if trimmed.is_empty() {
+        return Err(ParseError::Empty);
+    }
+    let (negative, unsigned) = match trimmed.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, trimmed.strip_prefix('+').unwrap_or(trimmed)),
+    };
+    if unsigned.is_empty() {
+        return Err(ParseError::NoDigits);
+    }
+    // position of a byte offset into `unsigned` within the original input, counting characters from 1
+    let offset = (number.len() - number.trim_start().len()) + (trimmed.len() - unsigned.len());
+    let position = |index: usize| number[..offset + index].chars().count() + 1;
+    if let Some((index, character)) = unsigned
+        .char_indices()
+        .find(|&(_, c)| !c.is_ascii_digit() && c != '_' && c != ',')
+    {
+        return Err(ParseError::InvalidCharacter {
+            character,
+            position: position(index),
+        });
+    }
+    if let Some(index) = misplaced_separator(unsigned) {
+        return Err(ParseError::MisplacedSeparator {
+            position: position(index),
+        });
+    }
+    let digits: String = unsigned.chars().filter(char::is_ascii_digit).collect();
+    let canonical = match digits.trim_start_matches('0') {
+        "" => "0",
+        digits if !negative => digits,
+        digits => return Ok(Cow::Owned(format!("-{}", digits))),
+    };
+    if canonical == number {
+        Ok(Cow::Borrowed(number))
+    } else {
+        Ok(Cow::Owned(canonical.to_string()))
+    }
+}
+
+/// Largest exponent accepted in scientific notation by [`parse_lenient`], to keep converted numbers to a sensible
+/// length.
+const MAX_EXPONENT: i64 = 1000;
+
+/// Parses `number` like [`parse`], but also accepts decimals and scientific notation which denote exact integers, such
+/// as `42.0` and `4.20e1`, converting them to integers.
+///
+/// # Examples
+///
+/// ```
+/// use iseven_api::input::{parse_lenient, ParseError};
+///
+/// assert_eq!(parse_lenient("42.0").unwrap(), "42");
+/// assert_eq!(parse_lenient("-4.20E+1").unwrap(), "-42");
+/// assert_eq!(parse_lenient("1e3").unwrap(), "1000");
+/// assert_eq!(parse_lenient("4.25"), Err(ParseError::NotAnInteger));
+/// ```
+pub fn parse_lenient(number: &str) -> Result<Cow<'_, str>, ParseError> {
+    let error = match parse(number) {
+        Ok(number) => return Ok(number),
+        Err(e) => e,
+    };
+    let trimmed = number.trim();
+    let (sign, unsigned) = match trimmed.strip_prefix(['-', '+']) {
+        Some(rest) => trimmed.split_at(trimmed.len() - rest.len()),
+        None => ("", trimmed),
+    };
+    match integral(unsigned) {
+        Some(Ok(digits)) => Ok(Cow::Owned(
+            parse(&format!("{}{}", sign, digits))?.into_owned(),
+        )),
+        Some(Err(e)) => Err(e),
+        None => Err(error),
+    }
+}
+
+/// Converts an unsigned decimal such as `42.0`, or scientific notation such as `4.2e1`, to the digits of the integer it
+/// denotes. Returns `None` if `s` is in neither notation.
+fn integral(s: &str) -> Option<Result<String, ParseError>> {
+    let (mantissa, exponent) = s.split_once(['e', 'E']).unwrap_or((s, "0"));
+    let (int_part, frac_part) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let is_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    let exponent_digits = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+    if int_part.is_empty()
+        || !is_digits(int_part)
+        || !is_digits(frac_part)
+        || exponent_digits.is_empty()
+        || !is_digits(exponent_digits)
+    {
+        return None;
+    }
+    let digits = format!("{}{}", int_part, frac_part);
+    if digits.bytes().all(|b| b == b'0') {
+        return Some(Ok("0".to_string()));
+    }
+    let exponent = match exponent.parse::<i64>() {
+        Ok(e) if e <= MAX_EXPONENT => e,
+        _ if !exponent.starts_with('-') => return Some(Err(ParseError::TooLarge)),
+        _ => return Some(Err(ParseError::NotAnInteger)),
+    };
+    // position of the decimal point within `digits` after applying the exponent
+    let point = int_part.len() as i64 + exponent;
+    let len = digits.len() as i64;
+    if point >= len {
+        Some(Ok(digits + &"0".repeat((point - len) as usize)))
+    } else if point > 0 && digits[point as usize..].bytes().all(|b| b == b'0') {
+        Some(Ok(digits[..point as usize].to_string()))
+    } else {
+        Some(Err(ParseError::NotAnInteger))
+    }
+}
+
+/// Reasons for [`parse`] and [`parse_lenient`] to reject a number.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// Empty, or only whitespace
+    #[error("no number given")]
+    Empty,
+    /// A sign without any digits
+    #[error("no digits after the sign")]
+    NoDigits,
+    /// A character which is not a digit or digit separator
+    #[error("invalid character {character:?} at position {position}")]
+    InvalidCharacter {
+        /// The offending character
+        character: char,
+        /// Position of the character in the input, counting characters from 1
+        position: usize,
+    },
+    /// A digit separator which is not between digits, or a comma which does not separate groups of three digits
+    #[error("misplaced digit separator at position {position}")]
+    MisplacedSeparator {
+        /// Position of the separator in the input, counting characters from 1
+        position: usize,
+    },
+    /// A decimal or scientific notation for a value which is not an integer, rejected by [`parse_lenient`]
+    #[error("not an integer")]
+    NotAnInteger,
+    /// Scientific notation with an exponent too large to convert, rejected by [`parse_lenient`]
+    #[error("number is too large to convert")]
+    TooLarge,
+}
+
+/// Returns the index of the first separator in `digits` which is out of place, if any. `digits` must consist of ASCII
+/// digits and separators only.
+///
+/// Underscores may separate any digits. Commas must separate groups of three digits, after a first group of up to
+/// three, and may not be mixed with underscores.
+fn misplaced_separator(digits: &str) -> Option<usize> {
+    let uses_commas = digits.contains(',');
+    let mut group_start = 0;
+    for (index, b) in digits.bytes().enumerate() {
+        if b.is_ascii_digit() {
+            continue;
+        }
+        let group = index - group_start;
+        let valid = match b {
+            b',' if group_start == 0 => (1..=3).contains(&group),
+            b',' => group == 3,
+            _ => !uses_commas && group > 0,
+        };
+        if !valid {
+            return Some(index);
+        }
+        group_start = index + 1;
+    }
+    let last = digits.len() - group_start;
+    (group_start > 0 && (last == 0 || (uses_commas && last != 3))).then(|| group_start - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("1_000_000"), "1000000");
+        assert_eq!(normalize("1_0"), "10");
+        assert_eq!(normalize("+1,000"), "1000");
+        assert_eq!(normalize("12,345,678"), "12345678");
+        for equivalent in [
+            "42",
+            " 42",
+            "42\n",
+            "+42",
+            "042",
+            "+0_042",
+            &42u64.to_string(),
+        ] {
+            assert_eq!(normalize(equivalent), "42");
+        }
+        assert_eq!(normalize("-042"), "-42");
+        assert_eq!(normalize("-0"), "0");
+        assert_eq!(normalize("000"), "0");
+        for unchanged in [
+            "1,5",
+            "1234,567",
+            "1,000_000",
+            "_1",
+            "1__0",
+            "1,000,",
+            "a_b",
+            "0x1F",
+            "4.2e1",
+            "+-1",
+            "-",
+            " ",
+            "",
+        ] {
+            assert_eq!(normalize(unchanged), unchanged);
+        }
+    }
+
+    #[test]
+    fn test_parse_lenient() {
+        assert_eq!(parse_lenient(" 042 ").unwrap(), "42");
+        assert_eq!(parse_lenient("42.0").unwrap(), "42");
+        assert_eq!(parse_lenient("+42.").unwrap(), "42");
+        assert_eq!(parse_lenient("1e6").unwrap(), "1000000");
+        assert_eq!(parse_lenient("4.2e1").unwrap(), "42");
+        assert_eq!(parse_lenient("-4.20E+1").unwrap(), "-42");
+        assert_eq!(parse_lenient("420e-1").unwrap(), "42");
+        assert_eq!(parse_lenient("-0.0e5").unwrap(), "0");
+        assert_eq!(parse_lenient("4.25e1"), Err(ParseError::NotAnInteger));
+        assert_eq!(parse_lenient("0.5"), Err(ParseError::NotAnInteger));
+        assert_eq!(parse_lenient("1e-1"), Err(ParseError::NotAnInteger));
+        assert_eq!(parse_lenient("1e99999"), Err(ParseError::TooLarge));
+        for invalid in ["1e", "e5", ".5", "1.2.3", "abc"] {
+            assert!(
+                matches!(
+                    parse_lenient(invalid),
+                    Err(ParseError::InvalidCharacter { .. })
+                ),
+                "{}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("\t-0_042 ").unwrap(), "-42");
+        assert_eq!(parse(""), Err(ParseError::Empty));
+        assert_eq!(parse("  "), Err(ParseError::Empty));
+        assert_eq!(parse(" +"), Err(ParseError::NoDigits));
+        assert_eq!(
+            parse("+-1"),
+            Err(ParseError::InvalidCharacter {
+                character: '-',
+                position: 2
+            })
+        );
+        assert_eq!(
+            parse(" 1é2"),
+            Err(ParseError::InvalidCharacter {
+                character: 'é',
+                position: 3
+            })
+        );
+        assert_eq!(
+            parse("1é2a"),
+            Err(ParseError::InvalidCharacter {
+                character: 'é',
+                position: 2
+            })
+        );
+        assert_eq!(
+            parse("0x1F"),
+            Err(ParseError::InvalidCharacter {
+                character: 'x',
+                position: 2
+            })
+        );
+        for (input, position) in [
+            ("_1", 1),
+            ("1__0", 3),
+            ("1,5", 2),
+            ("1234,567", 5),
+            ("1,000_000", 6),
+            ("-1,000,", 7),
+            ("1_", 2),
+        ] {
+            assert_eq!(
+                parse(input),
+                Err(ParseError::MisplacedSeparator { position }),
+                "{}",
+                input
+            );
+        }
+    }
+}
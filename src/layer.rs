@@ -0,0 +1,230 @@
+//! Middleware for the requests made by API clients.
+//!
+//! A layer is an [`IsEvenLayer`] added with [`IsEvenApiClientBuilder::layer`](crate::IsEvenApiClientBuilder::layer)
+//! or the `layer` method of a client. It sees every request sent to the API, including retries but not responses
+//! served from the cache, and can change its URL and headers, e.g. for authentication. Once the request has been
+//! answered or has failed, the layer is told the outcome, e.g. for logging or quota accounting.
+//!
+//! Layers run in a defined order, like the layers of an onion: requests pass through them in the order they were
+//! added, and outcomes pass through them in the reverse order, so the first layer added is the outermost one.
+//!
+//! # Examples
+//!
+#![cfg_attr(feature = "client", doc = "```")]
+#![cfg_attr(not(feature = "client"), doc = "```ignore")]
+//! # use std::error::Error;
+//! use std::sync::atomic::{AtomicUsize, Ordering};
+//! use iseven_api::{layer::{self, IsEvenLayer}, IsEvenApiClient, IsEvenApiError};
+//!
+//! /// Counts the requests answered by the API.
+//! #[derive(Default)]
+//! struct Quota(AtomicUsize);
+//!
+//! impl IsEvenLayer for Quota {
+//!     fn on_response(&self, _request: &layer::Request, outcome: Result<&layer::Response, &IsEvenApiError>) {
+//!         if outcome.is_ok() {
+//!             self.0.fetch_add(1, Ordering::Relaxed);
+//!         }
+//!     }
+//! }
+//!
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! let client = IsEvenApiClient::builder().layer(Quota::default()).build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    fmt::{Debug, Formatter},
+    sync::Arc,
+};
+
+use http::{HeaderMap, StatusCode, Version};
+
+use crate::{IsEvenApiError, ResponseHead};
+
+/// Middleware for the requests made by API clients. See the [module documentation](self) for details.
+///
+/// Both methods do nothing by default, so a layer only needs to implement the one it is interested in.
+pub trait IsEvenLayer: Send + Sync {
+    /// Called before `request` is sent, after the layers added before this one.
+    fn on_request(&self, request: &mut Request) {
+        let _ = request;
+    }
+
+    /// Called once `request` has been answered with a response or has failed with an error, before the layers added
+    /// before this one. Error responses from the API are passed as responses, as their body has not been read yet.
+    fn on_response(&self, request: &Request, outcome: Result<&Response, &IsEvenApiError>) {
+        let _ = (request, outcome);
+    }
+}
+
+/// Request about to be sent to the API.
+#[derive(Debug, Clone)]
+pub struct Request {
+    number: String,
+    url: String,
+    headers: HeaderMap,
+}
+
+impl Request {
+    pub(crate) fn new(number: &str, url: String) -> Self {
+        Self {
+            number: number.to_string(),
+            url,
+            headers: HeaderMap::new(),
+        }
+    }
+
+    /// Returns the number being checked.
+    pub fn number(&self) -> &str {
+        &self.number
+    }
+
+    /// Returns the URL being requested.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Sets the URL to request instead.
+    pub fn set_url<S: Into<String>>(&mut self, url: S) {
+        self.url = url.into();
+    }
+
+    /// Returns the headers sent with the request. They do not include the `Authorization` header for the API key set
+    /// with [`IsEvenApiClientBuilder::api_key`](crate::IsEvenApiClientBuilder::api_key), which is added after all
+    /// layers have run.
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// Returns the headers sent with the request for modification.
+    pub fn headers_mut(&mut self) -> &mut HeaderMap {
+        &mut self.headers
+    }
+}
+
+/// Response received from the API, without its body.
+#[derive(Debug, Clone)]
+pub struct Response {
+    status: StatusCode,
+    version: Version,
+    headers: HeaderMap,
+}
+
+impl Response {
+    pub(crate) fn from_head<R: ResponseHead>(response: &R) -> Self {
+        Self {
+            status: response.status(),
+            version: response.version(),
+            headers: response.headers().clone(),
+        }
+    }
+
+    /// Returns the HTTP status code of the response.
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// Returns the HTTP version of the response.
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    /// Returns the headers of the response.
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+}
+
+/// Layers added to a client, in the order they were added.
+#[derive(Clone, Default)]
+pub(crate) struct Layers(Vec<Arc<dyn IsEvenLayer>>);
+
+impl Layers {
+    pub(crate) fn push(&mut self, layer: Arc<dyn IsEvenLayer>) {
+        self.0.push(layer);
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Passes `request` through each layer in order.
+    pub(crate) fn on_request(&self, request: &mut Request) {
+        for layer in &self.0 {
+            layer.on_request(request);
+        }
+    }
+
+    /// Passes the outcome of `request` through each layer in reverse order.
+    pub(crate) fn on_response(
+        &self,
+        request: &Request,
+        outcome: Result<&Response, &IsEvenApiError>,
+    ) {
+        for layer in self.0.iter().rev() {
+            layer.on_response(request, outcome);
+        }
+    }
+}
+
+impl Debug for Layers {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Layers({})", self.0.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use http::HeaderValue;
+
+    use super::*;
+
+    /// Layer recording the order in which it is called.
+    struct Named(&'static str, Arc<Mutex<Vec<String>>>);
+
+    impl IsEvenLayer for Named {
+        fn on_request(&self, request: &mut Request) {
+            self.1.lock().unwrap().push(format!("request {}", self.0));
+            request
+                .headers_mut()
+                .append("x-layer", HeaderValue::from_static(self.0));
+        }
+
+        fn on_response(&self, _request: &Request, _outcome: Result<&Response, &IsEvenApiError>) {
+            self.1.lock().unwrap().push(format!("response {}", self.0));
+        }
+    }
+
+    #[test]
+    fn test_layer_order() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let mut layers = Layers::default();
+        layers.push(Arc::new(Named("outer", calls.clone())));
+        layers.push(Arc::new(Named("inner", calls.clone())));
+
+        let mut request = Request::new("42", "http://localhost/42".to_string());
+        layers.on_request(&mut request);
+        let response = Response {
+            status: StatusCode::OK,
+            version: Version::HTTP_11,
+            headers: HeaderMap::new(),
+        };
+        layers.on_response(&request, Ok(&response));
+
+        assert_eq!(
+            *calls.lock().unwrap(),
+            [
+                "request outer",
+                "request inner",
+                "response inner",
+                "response outer"
+            ]
+        );
+        let added: Vec<_> = request.headers().get_all("x-layer").iter().collect();
+        assert_eq!(added, ["outer", "inner"]);
+    }
+}
@@ -3,7 +3,8 @@
 //! Includes the library as well as a simple command line app.
 //!
 //! # Examples
-//! ```
+#![cfg_attr(feature = "client", doc = "```")]
+#![cfg_attr(not(feature = "client"), doc = "```ignore")]
 //! use std::error::Error;
 //! use iseven_api::IsEvenApiClient;
 //!
@@ -23,19 +24,110 @@
 //! ```
 //!
 //! # Feature flags
+//! - **client** (default) - Enables [`IsEvenApiClient`] and [`IsEvenApiClientBuilder`], which make requests with
+//!   reqwest. Without it, the crate only has the response and error types and the I/O-free functions in [`sansio`],
+//!   for checking numbers with an HTTP client of your own. All of the features below except **blocking** and
+//!   **ffi** enable it.
 //! - **blocking** - Enables [`IsEvenApiBlockingClient`] which is a blocking alternative to [`IsEvenApiClient`]
-//! and does not require async runtime. It also enables 'convenience' functions [`is_odd`] and [`is_even`].
+//!   and does not require async runtime. It also enables 'convenience' functions [`is_odd`] and [`is_even`].
+//!   Without **client**, the blocking client makes requests with a small built-in HTTP/1.1 client instead of
+//!   reqwest, so `default-features = false, features = ["blocking"]` builds without tokio.
+//! - **mock-server** - Enables [`mock::MockServer`], a local server imitating isEven API for developing and testing
+//!   without internet access.
+//! - **test-util** - Enables [`test_util`], with utilities for testing code which uses the API such as a generator of
+//!   deterministic fake ads and a [`Clock`] which only moves when told to. Also enabled by `mock-server` and
+//!   `test-harness`.
+//! - **test-harness** - Enables [`harness`], for testing the whole client stack against responses recorded from the
+//!   API, including error responses and bodies which are not JSON.
+//! - **runtime-agnostic** - Lets [`IsEvenApiClient`] be awaited from executors other than tokio, such as async-std
+//!   or smol, by running its requests on a background tokio runtime when there is no current one.
+//! - **http3** - Enables [`IsEvenApiClientBuilder::http3`] for making requests over HTTP/3 (QUIC). reqwest's HTTP/3
+//!   support is unstable, so this feature also requires building with `RUSTFLAGS='--cfg reqwest_unstable'`, or
+//!   with `rustflags = ["--cfg", "reqwest_unstable"]` in the `[build]` section of your `.cargo/config.toml`.
+//! - **ffi** - Exports C bindings from the `cdylib` build of the crate, declared in `include/iseven_api.h`. See
+//!   [`ffi`] for details.
+//! - **wasm** - Enables [`wasm::WasmIsEvenClient`], a client for browsers and other JavaScript environments which
+//!   makes requests with `fetch`, exported with `wasm-bindgen`.
 //! - **cli** - Command line app. This feature does not add any extra library functionality.
 
 #![warn(missing_docs)]
 
 use std::fmt::{Display, Formatter};
+#[cfg(any(feature = "client", feature = "blocking"))]
+use std::time::Instant;
+
+#[cfg(feature = "client")]
+pub use batch::{BatchItem, BatchOrder, ChunkProgress, GetStream, DEFAULT_CHUNK_SIZE};
+#[cfg(any(feature = "client", feature = "blocking"))]
+use builder::ClientConfig;
+#[cfg(any(feature = "client", feature = "blocking"))]
+pub use builder::IsEvenApiClientBuilder;
+pub use cache::{CacheStats, CachedResponse, EvictionPolicy, ResponseCache};
+pub use clock::{Clock, Sleep, SystemClock};
+pub use error::{ApiError, ErrorKind, IsEvenApiError, ProtocolError, TransportError};
+pub use http::StatusCode;
+pub use metadata::ResponseMetadata;
+pub use number::ApiNumber;
+pub use plan::Plan;
+pub use rate_limit::RateLimiter;
+#[cfg(any(feature = "client", feature = "blocking"))]
+pub use request::GetRequest;
+pub use retry::{RetryBudget, RetryPolicy};
+pub use rng::{Rng, SeededRng, ThreadRng};
+#[cfg(any(feature = "client", feature = "blocking"))]
+pub use url::Url;
+
+#[cfg(feature = "client")]
+use futures_channel::mpsc::SendError;
+#[cfg(feature = "client")]
+use futures_util::{future::Either, Sink, Stream, StreamExt};
+#[cfg(any(feature = "client", feature = "blocking"))]
+use log::{debug, log_enabled, trace, Level};
+#[cfg(any(feature = "client", feature = "blocking"))]
+use request::CacheMode;
+#[cfg(feature = "client")]
+use reqwest::{Client, Response};
+#[cfg(any(feature = "client", feature = "blocking"))]
+use sansio::decode;
+use serde::{Deserialize, Serialize};
 
-use log::debug;
-use reqwest::{Client, Response, StatusCode};
-use serde::Deserialize;
+#[cfg(all(feature = "blocking", not(feature = "client")))]
+mod agent;
+#[cfg(feature = "client")]
+mod batch;
+#[cfg(any(feature = "client", feature = "blocking"))]
+mod builder;
+mod cache;
+mod clock;
+mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "test-harness")]
+pub mod harness;
+pub mod input;
+#[cfg(any(feature = "client", feature = "blocking"))]
+pub mod layer;
+mod metadata;
+#[cfg(feature = "mock-server")]
+pub mod mock;
+mod number;
+mod plan;
+mod rate_limit;
+#[cfg(any(feature = "client", feature = "blocking"))]
+mod request;
+mod retry;
+mod rng;
+#[cfg(feature = "runtime-agnostic")]
+mod runtime;
+pub mod sansio;
+pub mod store;
+#[cfg(feature = "test-util")]
+pub mod test_util;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
-const API_URL: &str = "https://api.isevenapi.xyz/api/iseven/";
+/// Default base URL of the API, used unless another one is set with [`IsEvenApiClientBuilder::base_url`].
+pub const API_URL: &str = "https://api.isevenapi.xyz/api/iseven/";
 
 /// Checks if a number is even.
 ///
@@ -48,17 +140,44 @@ const API_URL: &str = "https://api.isevenapi.xyz/api/iseven/";
 ///
 ///
 /// # Examples
-/// ```
+/// ```no_run
 /// use iseven_api::is_even;
 ///
 /// # fn main() {
 /// assert!(is_even(42));
 /// # }
+/// ```
+///
+/// `number` is an integer or an [`ApiNumber`]. Use [`is_even_str`] for numbers given as text.
 #[cfg(feature = "blocking")]
-pub fn is_even<T: Display>(number: T) -> bool {
+pub fn is_even(number: impl Into<ApiNumber>) -> bool {
     IsEvenApiBlockingClient::new().get(number).unwrap().iseven()
 }
 
+/// Like [`is_even`], for a number given as text, such as user input.
+///
+/// # Panics
+///
+/// Panics if `number` is not a valid number, and on the same errors as [`is_even`].
+#[cfg(feature = "blocking")]
+pub fn is_even_str(number: &str) -> bool {
+    IsEvenApiBlockingClient::new()
+        .get_str(number)
+        .unwrap()
+        .iseven()
+}
+
+/// Checks if a number, given as anything which can be displayed, is even.
+///
+/// # Panics
+///
+/// Panics on the same errors as [`is_even_str`].
+#[cfg(feature = "blocking")]
+#[deprecated(note = "use `is_even` for integers and `ApiNumber`s, or `is_even_str` for text")]
+pub fn is_even_display<T: Display>(number: T) -> bool {
+    is_even_str(&number.to_string())
+}
+
 /// Checks if a number is odd.
 ///
 /// # Panics
@@ -70,17 +189,41 @@ pub fn is_even<T: Display>(number: T) -> bool {
 ///
 ///
 /// # Examples
-/// ```
+/// ```no_run
 /// use iseven_api::is_odd;
 ///
 /// # fn main() {
 /// assert!(is_odd(333));
 /// # }
+/// ```
+///
+/// `number` is an integer or an [`ApiNumber`]. Use [`is_odd_str`] for numbers given as text.
 #[cfg(feature = "blocking")]
-pub fn is_odd<T: Display>(number: T) -> bool {
+pub fn is_odd(number: impl Into<ApiNumber>) -> bool {
     !is_even(number)
 }
 
+/// Like [`is_odd`], for a number given as text, such as user input.
+///
+/// # Panics
+///
+/// Panics if `number` is not a valid number, and on the same errors as [`is_odd`].
+#[cfg(feature = "blocking")]
+pub fn is_odd_str(number: &str) -> bool {
+    !is_even_str(number)
+}
+
+/// Checks if a number, given as anything which can be displayed, is odd.
+///
+/// # Panics
+///
+/// Panics on the same errors as [`is_odd_str`].
+#[cfg(feature = "blocking")]
+#[deprecated(note = "use `is_odd` for integers and `ApiNumber`s, or `is_odd_str` for text")]
+pub fn is_odd_display<T: Display>(number: T) -> bool {
+    is_odd_str(&number.to_string())
+}
+
 /// Asynchronous API client for isEven API.
 ///
 /// If you need a blocking client, use [`IsEvenApiBlockingClient`] instead.
@@ -108,11 +251,14 @@ pub fn is_odd<T: Display>(number: T) -> bool {
 /// #   Ok(())
 /// # }
 /// ```
+#[cfg(feature = "client")]
 #[derive(Debug, Clone)]
 pub struct IsEvenApiClient {
     client: Client,
+    config: ClientConfig,
 }
 
+#[cfg(feature = "client")]
 impl IsEvenApiClient {
     /// Creates a new instance of [`IsEvenApiClient`] with a default HTTP client.
     pub fn new() -> Self {
@@ -122,7 +268,22 @@ impl IsEvenApiClient {
     /// Creates a new instance of [`IsEvenApiClient`] with a supplied [`reqwest::Client`].
     pub fn with_client(client: Client) -> Self {
         debug!("Creating async HTTP client");
-        Self { client }
+        Self {
+            client,
+            config: ClientConfig::default(),
+        }
+    }
+
+    /// Creates a new [`IsEvenApiClientBuilder`] for configuring a client.
+    pub fn builder() -> IsEvenApiClientBuilder {
+        IsEvenApiClientBuilder::new()
+    }
+
+    /// Adds a [layer](layer) which every request passes through, after the layers added before it. See
+    /// [`IsEvenApiClientBuilder::layer`].
+    pub fn layer<L: layer::IsEvenLayer + 'static>(mut self, layer: L) -> Self {
+        self.config.layers.push(std::sync::Arc::new(layer));
+        self
     }
 
     /// sends a GET request to the isEven API for a given number. The return value includes the `bool`
@@ -134,14 +295,346 @@ impl IsEvenApiClient {
     /// request or parsing of the response.
     ///
     /// * If the number is outside the range for your [pricing plan](https://isevenapi.xyz/#pricing),
-    /// it will return [`IsEvenApiError::NumberOutOfRange`].
-    /// * If the input is not a valid number, it returns [`IsEvenApiError::InvalidNumber`].
-    /// * For other API error reponses, it returns [`IsEvenApiError::UnknownErrorResponse`] along with an HTTP status code.
-    /// * If the error is in the request [`IsEvenApiError::NetworkError`] is returned.
-    pub async fn get<T: Display>(&self, number: T) -> Result<IsEvenApiResponse, IsEvenApiError> {
-        let response = self.fetch_response(number).await?;
-        let status = response.status();
-        parse_response(response.json().await?, status)
+    ///   it will return [`ApiError::NumberOutOfRange`].
+    /// * If the input cannot possibly be a valid number, it returns [`IsEvenApiError::InvalidInput`] without making a
+    ///   request.
+    /// * If the API rejects the number as invalid, it returns [`ApiError::InvalidNumber`].
+    /// * For error responses with other status codes, it returns [`ProtocolError::UnexpectedStatus`] along with the
+    ///   status code.
+    /// * If the error is in the request [`IsEvenApiError::Transport`] is returned.
+    /// * If the response body is neither an answer nor an error response, [`ProtocolError::Decode`] is returned for a
+    ///   successful status code, or [`ProtocolError::UnexpectedBody`] along with the status code otherwise.
+    /// * If the error is transient and the client has a [`RetryPolicy`], the request is retried before the error is
+    ///   returned.
+    ///
+    /// The number is converted to its canonical form as described in [`input::normalize`], so that e.g. `042` and
+    /// `+42` share cached responses.
+    ///
+    /// If the client has a [`ResponseCache`], cached responses are returned without making a request. Use
+    /// [`Self::request`] to bypass the cache for a single request.
+    ///
+    /// `number` is an integer or an [`ApiNumber`]. Use [`Self::get_str`] for numbers given as text.
+    pub async fn get(
+        &self,
+        number: impl Into<ApiNumber>,
+    ) -> Result<IsEvenApiResponse, IsEvenApiError> {
+        self.get_with(number.into(), CacheMode::Use).await
+    }
+
+    /// Like [`Self::get`], for a number given as text, such as user input.
+    ///
+    /// # Errors
+    /// Returns [`IsEvenApiError::InvalidInput`] without making a request if `number` cannot be a valid number, and
+    /// otherwise the same errors as [`Self::get`].
+    pub async fn get_str(&self, number: &str) -> Result<IsEvenApiResponse, IsEvenApiError> {
+        self.get_with(number, CacheMode::Use).await
+    }
+
+    /// Like [`Self::get_str`], for a number given as anything which can be displayed.
+    ///
+    /// # Errors
+    /// See [`Self::get_str`].
+    #[deprecated(note = "use `get` for integers and `ApiNumber`s, or `get_str` for text")]
+    pub async fn get_display<T: Display>(
+        &self,
+        number: T,
+    ) -> Result<IsEvenApiResponse, IsEvenApiError> {
+        self.get_with(number, CacheMode::Use).await
+    }
+
+    /// Returns a builder for a request for `number` with options of its own, such as bypassing the cache.
+    pub fn request(&self, number: impl Into<ApiNumber>) -> GetRequest<'_, Self> {
+        GetRequest::new(self, number.into().into())
+    }
+
+    /// Like [`Self::request`], for a number given as text.
+    pub fn request_str(&self, number: &str) -> GetRequest<'_, Self> {
+        GetRequest::new(self, number.to_string())
+    }
+
+    /// Like [`Self::request_str`], for a number given as anything which can be displayed.
+    #[deprecated(note = "use `request` for integers and `ApiNumber`s, or `request_str` for text")]
+    pub fn request_display<T: Display>(&self, number: T) -> GetRequest<'_, Self> {
+        GetRequest::new(self, number.to_string())
+    }
+
+    pub(crate) async fn get_with<T: Display>(
+        &self,
+        number: T,
+        cache_mode: CacheMode,
+    ) -> Result<IsEvenApiResponse, IsEvenApiError> {
+        #[cfg(feature = "runtime-agnostic")]
+        if runtime::outside_tokio() {
+            let (client, number) = (self.clone(), number.to_string());
+            return runtime::spawn(async move { client.get_inner(number, cache_mode).await }).await;
+        }
+        self.get_inner(number, cache_mode).await
+    }
+
+    async fn get_inner<T: Display>(
+        &self,
+        number: T,
+        cache_mode: CacheMode,
+    ) -> Result<IsEvenApiResponse, IsEvenApiError> {
+        let number = self.config.parse(number)?;
+        if let Some((response, refresh)) = self.config.cached(&number, cache_mode) {
+            if let Some(refresh) = refresh {
+                let client = self.clone();
+                tokio::spawn(async move {
+                    let _refresh = refresh;
+                    let _ = client.fetch(number, CacheMode::Use).await;
+                });
+            }
+            return Ok(response);
+        }
+        self.fetch(number, cache_mode).await
+    }
+
+    /// Requests `number`, retrying according to the retry policy, and caches the response.
+    async fn fetch(
+        &self,
+        number: String,
+        cache_mode: CacheMode,
+    ) -> Result<IsEvenApiResponse, IsEvenApiError> {
+        let request_id = self.config.new_request_id();
+        let mut retry = 0;
+        loop {
+            match self
+                .get_once(&number, &request_id, cache_mode)
+                .await
+                .map_err(|e| e.with_request_id(&request_id))
+            {
+                Err(e) if self.config.should_retry(retry, &e) => {
+                    let delay = self.config.retry_delay(retry);
+                    debug!(
+                        "[{}] Request failed ({}), retrying in {:?}",
+                        request_id, e, delay
+                    );
+                    self.config.clock.sleep(delay).await;
+                    retry += 1;
+                }
+                result => return self.config.store(number, result),
+            }
+        }
+    }
+
+    /// Checks each of `numbers`, making up to `concurrency` requests at a time, and returns a stream of the results in
+    /// the given `order`.
+    ///
+    /// Each result is a [`BatchItem`] tagged with the number it is for and its position among `numbers`, so results
+    /// can be told apart even if they are yielded as they complete. Failures are yielded like any other result, and do
+    /// not stop the remaining numbers from being checked.
+    ///
+    /// Numbers are taken from `numbers` in chunks of [`DEFAULT_CHUNK_SIZE`], so even an iterator of millions of
+    /// numbers is never collected at once. To choose the chunk size or report progress after each chunk, pass
+    /// `futures_util::stream::iter(numbers)` to [`Self::get_stream`] and use [`GetStream::chunk_size`] and
+    /// [`GetStream::on_chunk`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `concurrency` is 0.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use std::error::Error;
+    /// use futures_util::StreamExt;
+    /// use iseven_api::{BatchOrder, IsEvenApiClient};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn Error>> {
+    /// let client = IsEvenApiClient::new();
+    /// let mut results = client.get_many([4, 8, 15, 16, 23, 42], 3, BatchOrder::Completion);
+    /// while let Some(item) = results.next().await {
+    ///     match item.result() {
+    ///         Ok(response) => println!("#{}: {} is {}", item.index(), item.input(), response),
+    ///         Err(e) => println!("#{}: {} failed: {}", item.index(), item.input(), e),
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_many<'a, I>(
+        &'a self,
+        numbers: I,
+        concurrency: usize,
+        order: BatchOrder,
+    ) -> impl Stream<Item = BatchItem<I::Item>> + 'a
+    where
+        I: IntoIterator,
+        I::IntoIter: 'a,
+        I::Item: Into<ApiNumber> + Clone + 'a,
+    {
+        Self::in_order(
+            self.get_stream(futures_util::stream::iter(numbers), concurrency),
+            order,
+        )
+    }
+
+    /// Like [`Self::get_many`], for numbers given as text.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `concurrency` is 0.
+    pub fn get_many_str<'a, I>(
+        &'a self,
+        numbers: I,
+        concurrency: usize,
+        order: BatchOrder,
+    ) -> impl Stream<Item = BatchItem<I::Item>> + 'a
+    where
+        I: IntoIterator,
+        I::IntoIter: 'a,
+        I::Item: AsRef<str> + 'a,
+    {
+        let numbers = futures_util::stream::iter(numbers);
+        Self::in_order(self.get_stream_str(numbers, concurrency), order)
+    }
+
+    /// Like [`Self::get_many_str`], for numbers given as anything which can be displayed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `concurrency` is 0.
+    #[deprecated(note = "use `get_many` for integers and `ApiNumber`s, or `get_many_str` for text")]
+    pub fn get_many_display<'a, I>(
+        &'a self,
+        numbers: I,
+        concurrency: usize,
+        order: BatchOrder,
+    ) -> impl Stream<Item = BatchItem<I::Item>> + 'a
+    where
+        I: IntoIterator,
+        I::IntoIter: 'a,
+        I::Item: Display + 'a,
+    {
+        let numbers = futures_util::stream::iter(numbers);
+        #[allow(deprecated)]
+        Self::in_order(self.get_stream_display(numbers, concurrency), order)
+    }
+
+    /// Yields the results of `requests` in `order`.
+    fn in_order<'a, S>(
+        requests: GetStream<'a, S>,
+        order: BatchOrder,
+    ) -> impl Stream<Item = BatchItem<S::Item>> + 'a
+    where
+        S: Stream + 'a,
+        S::Item: 'a,
+    {
+        match order {
+            BatchOrder::Input => Either::Left(requests.ordered()),
+            BatchOrder::Completion => Either::Right(requests.unordered()),
+        }
+    }
+
+    /// Checks each number from the stream `numbers` as it arrives, making up to `concurrency` requests at a time.
+    ///
+    /// Results are [`BatchItem`]s like those of [`Self::get_many`], yielded in the order chosen with
+    /// [`GetStream::ordered`], which holds back results to yield them in the order the numbers arrived, or
+    /// [`GetStream::unordered`], which yields them as soon as they complete.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `concurrency` is 0.
+    pub fn get_stream<'a, S>(&'a self, numbers: S, concurrency: usize) -> GetStream<'a, S>
+    where
+        S: Stream + 'a,
+        S::Item: Into<ApiNumber> + Clone + 'a,
+    {
+        let text = |number: &S::Item| String::from(number.clone().into());
+        GetStream::new(self, numbers, text, concurrency)
+    }
+
+    /// Like [`Self::get_stream`], for numbers given as text.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `concurrency` is 0.
+    pub fn get_stream_str<'a, S>(&'a self, numbers: S, concurrency: usize) -> GetStream<'a, S>
+    where
+        S: Stream + 'a,
+        S::Item: AsRef<str> + 'a,
+    {
+        let text = |number: &S::Item| number.as_ref().to_string();
+        GetStream::new(self, numbers, text, concurrency)
+    }
+
+    /// Like [`Self::get_stream_str`], for numbers given as anything which can be displayed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `concurrency` is 0.
+    #[deprecated(
+        note = "use `get_stream` for integers and `ApiNumber`s, or `get_stream_str` for text"
+    )]
+    pub fn get_stream_display<'a, S>(&'a self, numbers: S, concurrency: usize) -> GetStream<'a, S>
+    where
+        S: Stream + 'a,
+        S::Item: Display + 'a,
+    {
+        let text = |number: &S::Item| number.to_string();
+        GetStream::new(self, numbers, text, concurrency)
+    }
+
+    /// Returns a sink which numbers can be sent into to be checked, paired with the stream of their results.
+    ///
+    /// Up to `concurrency` numbers are checked at a time. Results are [`BatchItem`]s like those of [`Self::get_many`],
+    /// yielded as soon as they complete and tagged with the position in which their number was sent. The sink only
+    /// takes a few numbers ahead of the ones being checked, so sending waits whenever the API or the client's
+    /// [`RateLimiter`] is the bottleneck, slowing producers down to the rate numbers can be checked at.
+    ///
+    /// Numbers are only checked while the stream is polled. The stream ends once every clone of the sink has been
+    /// closed or dropped and the remaining results have been yielded, and sending fails once the stream is dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `concurrency` is 0.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use std::error::Error;
+    /// use futures_util::{SinkExt, StreamExt};
+    /// use iseven_api::{ApiNumber, IsEvenApiClient};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn Error>> {
+    /// let client = IsEvenApiClient::new();
+    /// let (mut sink, results) = client.checker_sink(4);
+    /// tokio::spawn(async move {
+    ///     for n in 0..1000 {
+    ///         // waits while the checks fall behind
+    ///         sink.send(ApiNumber::from(n)).await?;
+    ///     }
+    ///     sink.close().await
+    /// });
+    /// let checked = results.filter(|item| std::future::ready(item.result().is_ok())).count().await;
+    /// println!("{} numbers checked", checked);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn checker_sink(
+        &self,
+        concurrency: usize,
+    ) -> (
+        impl Sink<ApiNumber, Error = SendError> + Clone + Unpin,
+        impl Stream<Item = BatchItem<ApiNumber>>,
+    ) {
+        assert!(concurrency > 0, "concurrency must be greater than 0");
+        let (sender, receiver) = futures_channel::mpsc::channel(concurrency);
+        let client = self.clone();
+        let results = receiver
+            .enumerate()
+            .map(move |(index, number): (usize, ApiNumber)| {
+                let client = client.clone();
+                async move {
+                    let result = client.get_with(&number, CacheMode::Use).await;
+                    BatchItem::new(index, number, result)
+                }
+            })
+            .buffer_unordered(concurrency);
+        (sender, results)
     }
 
     /// sends a GET request to the isEven API for a given number and returns its JSON response as a `String`.
@@ -150,19 +643,148 @@ impl IsEvenApiClient {
     ///
     /// Unlike [`Self::get`], error responses will NOT be considered an error. Only request failures will be reported
     /// as an error.
-    pub async fn get_json<T: Display>(&self, number: T) -> Result<String, IsEvenApiError> {
-        let response = self.fetch_response(number).await?;
-        Ok(response.text().await.expect("Unable to decode response body"))
+    pub async fn get_json(&self, number: impl Into<ApiNumber>) -> Result<String, IsEvenApiError> {
+        self.get_json_with(number.into()).await
+    }
+
+    /// Like [`Self::get_json`], for a number given as text.
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::get_json`].
+    pub async fn get_json_str(&self, number: &str) -> Result<String, IsEvenApiError> {
+        self.get_json_with(number).await
+    }
+
+    /// Like [`Self::get_json_str`], for a number given as anything which can be displayed.
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::get_json`].
+    #[deprecated(note = "use `get_json` for integers and `ApiNumber`s, or `get_json_str` for text")]
+    pub async fn get_json_display<T: Display>(&self, number: T) -> Result<String, IsEvenApiError> {
+        self.get_json_with(number).await
+    }
+
+    async fn get_json_with<T: Display>(&self, number: T) -> Result<String, IsEvenApiError> {
+        #[cfg(feature = "runtime-agnostic")]
+        if runtime::outside_tokio() {
+            let (client, number) = (self.clone(), number.to_string());
+            return runtime::spawn(async move { client.get_json_inner(number).await }).await;
+        }
+        self.get_json_inner(number).await
+    }
+
+    async fn get_json_inner<T: Display>(&self, number: T) -> Result<String, IsEvenApiError> {
+        let request_id = self.config.new_request_id();
+        let mut retry = 0;
+        loop {
+            match self
+                .fetch_response(&number, &request_id, CacheMode::NoCache)
+                .await
+                .map_err(|e| e.with_request_id(&request_id))
+            {
+                Err(e) if self.config.should_retry(retry, &e) => {
+                    self.config
+                        .clock
+                        .sleep(self.config.retry_delay(retry))
+                        .await;
+                    retry += 1;
+                }
+                response => {
+                    return Ok(response?
+                        .text()
+                        .await
+                        .expect("Unable to decode response body"))
+                }
+            }
+        }
+    }
+
+    /// Returns the URL which [`Self::get`] requests for `number`, e.g. for printing it without making a request.
+    pub fn request_url(&self, number: impl Into<ApiNumber>) -> String {
+        self.config.request_url(number.into())
+    }
+
+    /// Like [`Self::request_url`], for a number given as text.
+    pub fn request_url_str(&self, number: &str) -> String {
+        self.config.request_url(number)
+    }
+
+    /// Like [`Self::request_url_str`], for a number given as anything which can be displayed.
+    #[deprecated(
+        note = "use `request_url` for integers and `ApiNumber`s, or `request_url_str` for text"
+    )]
+    pub fn request_url_display<T: Display>(&self, number: T) -> String {
+        self.config.request_url(number)
+    }
+
+    /// Make a single request and parse the response
+    async fn get_once<T: Display>(
+        &self,
+        number: T,
+        request_id: &str,
+        cache_mode: CacheMode,
+    ) -> Result<IsEvenApiResponse, IsEvenApiError> {
+        let response = self.fetch_response(number, request_id, cache_mode).await?;
+        let status = response.status();
+        let metadata = ResponseMetadata::new(request_id, response.headers());
+        decode(status, &response.bytes().await?, metadata)
     }
 
     /// Make the actual web request
-    async fn fetch_response<T: Display>(&self, number: T) -> reqwest::Result<Response> {
-        let request_url = format!("{api_url}{num}", api_url = API_URL, num = number);
-        debug!("Fetching API response from {}", request_url);
-        self.client.get(request_url).send().await
+    async fn fetch_response<T: Display>(
+        &self,
+        number: T,
+        request_id: &str,
+        cache_mode: CacheMode,
+    ) -> Result<Response, IsEvenApiError> {
+        if let Some(limiter) = &self.config.rate_limiter {
+            self.config.clock.sleep(limiter.reserve()).await;
+        }
+        let request = self.config.prepare(number, request_id, cache_mode);
+        debug!("[{}] GET {}", request_id, request.url());
+        let started = Instant::now();
+        let result = self
+            .send(
+                self.config.authorize(
+                    self.client
+                        .get(request.url())
+                        .headers(request.headers().clone()),
+                ),
+            )
+            .await;
+        let response = self
+            .config
+            .finish(&request, result.map_err(IsEvenApiError::from))?;
+        log_response(&response, request_id, started);
+        if !log_enabled!(Level::Trace) {
+            return Ok(response);
+        }
+        // reading the body for logging consumes the response, so an equivalent one is rebuilt from it
+        let parts = response_parts(&response);
+        let body = response.bytes().await?;
+        trace!("Response body: {}", String::from_utf8_lossy(&body));
+        Ok(parts.body(body).expect("valid response parts").into())
+    }
+
+    /// Sends `request`, trying HTTP/3 first if it is enabled.
+    async fn send(&self, request: reqwest::RequestBuilder) -> reqwest::Result<Response> {
+        #[cfg(feature = "http3")]
+        if let Some(h3) = request.try_clone() {
+            let h3 = h3.version(http::Version::HTTP_3).build()?;
+            if self.config.try_http3(h3.url()) {
+                match self.client.execute(h3).await {
+                    Ok(response) => return Ok(response),
+                    Err(e) => self.config.http3_failed(&e),
+                }
+            }
+        }
+        request.send().await
     }
 }
 
+#[cfg(feature = "client")]
 impl Default for IsEvenApiClient {
     fn default() -> Self {
         Self::new()
@@ -180,7 +802,7 @@ impl Default for IsEvenApiClient {
 ///
 /// # Examples
 ///
-/// ```
+/// ```no_run
 /// # use std::error::Error;
 /// use iseven_api::IsEvenApiBlockingClient;
 ///
@@ -200,20 +822,59 @@ impl Default for IsEvenApiClient {
 #[cfg(feature = "blocking")]
 #[derive(Debug, Clone)]
 pub struct IsEvenApiBlockingClient {
+    #[cfg(feature = "client")]
     client: reqwest::blocking::Client,
+    #[cfg(not(feature = "client"))]
+    client: agent::Agent,
+    config: ClientConfig,
 }
 
 #[cfg(feature = "blocking")]
 impl IsEvenApiBlockingClient {
     /// Creates a new instance of [`IsEvenApiBlockingClient`] with a default HTTP client.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the TLS backend cannot be initialised. Use [`IsEvenApiClientBuilder::build_blocking`] to handle this
+    /// failure.
+    #[cfg(feature = "client")]
     pub fn new() -> Self {
         Self::with_client(reqwest::blocking::Client::new())
     }
 
-    /// Creates a new instance of [`IsEvenApiBlockingClient`] with a supplied [`reqwest::Client`].
+    /// Creates a new instance of [`IsEvenApiBlockingClient`] with a default HTTP client.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the TLS backend cannot be initialised. Use [`IsEvenApiClientBuilder::build_blocking`] to handle this
+    /// failure.
+    #[cfg(not(feature = "client"))]
+    pub fn new() -> Self {
+        IsEvenApiClientBuilder::new()
+            .build_blocking()
+            .expect("Unable to initialise the TLS backend")
+    }
+
+    /// Creates a new instance of [`IsEvenApiBlockingClient`] with a supplied [`reqwest::blocking::Client`].
+    #[cfg(feature = "client")]
     pub fn with_client(client: reqwest::blocking::Client) -> Self {
         debug!("Creating blocking HTTP client");
-        Self { client }
+        Self {
+            client,
+            config: ClientConfig::default(),
+        }
+    }
+
+    /// Creates a new [`IsEvenApiClientBuilder`] for configuring a client.
+    pub fn builder() -> IsEvenApiClientBuilder {
+        IsEvenApiClientBuilder::new()
+    }
+
+    /// Adds a [layer](layer) which every request passes through, after the layers added before it. See
+    /// [`IsEvenApiClientBuilder::layer`].
+    pub fn layer<L: layer::IsEvenLayer + 'static>(mut self, layer: L) -> Self {
+        self.config.layers.push(std::sync::Arc::new(layer));
+        self
     }
 
     /// sends a GET request to the isEven API for a given number. The return value includes the `bool`
@@ -222,10 +883,87 @@ impl IsEvenApiBlockingClient {
     ///
     /// # Errors
     /// See [`IsEvenApiClient::get`] for a list of possible errors.
-    pub fn get<T: Display>(&self, number: T) -> Result<IsEvenApiResponse, IsEvenApiError> {
-        let response = self.fetch_response(number)?;
-        let status = response.status();
-        parse_response(response.json()?, status)
+    pub fn get(&self, number: impl Into<ApiNumber>) -> Result<IsEvenApiResponse, IsEvenApiError> {
+        self.get_with(number.into(), CacheMode::Use)
+    }
+
+    /// Like [`Self::get`], for a number given as text, such as user input.
+    ///
+    /// # Errors
+    /// See [`IsEvenApiClient::get_str`] for a list of possible errors.
+    pub fn get_str(&self, number: &str) -> Result<IsEvenApiResponse, IsEvenApiError> {
+        self.get_with(number, CacheMode::Use)
+    }
+
+    /// Like [`Self::get_str`], for a number given as anything which can be displayed.
+    ///
+    /// # Errors
+    /// See [`IsEvenApiClient::get_str`].
+    #[deprecated(note = "use `get` for integers and `ApiNumber`s, or `get_str` for text")]
+    pub fn get_display<T: Display>(&self, number: T) -> Result<IsEvenApiResponse, IsEvenApiError> {
+        self.get_with(number, CacheMode::Use)
+    }
+
+    /// Returns a builder for a request for `number` with options of its own, such as bypassing the cache.
+    pub fn request(&self, number: impl Into<ApiNumber>) -> GetRequest<'_, Self> {
+        GetRequest::new(self, number.into().into())
+    }
+
+    /// Like [`Self::request`], for a number given as text.
+    pub fn request_str(&self, number: &str) -> GetRequest<'_, Self> {
+        GetRequest::new(self, number.to_string())
+    }
+
+    /// Like [`Self::request_str`], for a number given as anything which can be displayed.
+    #[deprecated(note = "use `request` for integers and `ApiNumber`s, or `request_str` for text")]
+    pub fn request_display<T: Display>(&self, number: T) -> GetRequest<'_, Self> {
+        GetRequest::new(self, number.to_string())
+    }
+
+    pub(crate) fn get_with<T: Display>(
+        &self,
+        number: T,
+        cache_mode: CacheMode,
+    ) -> Result<IsEvenApiResponse, IsEvenApiError> {
+        let number = self.config.parse(number)?;
+        if let Some((response, refresh)) = self.config.cached(&number, cache_mode) {
+            if let Some(refresh) = refresh {
+                let client = self.clone();
+                std::thread::spawn(move || {
+                    let _refresh = refresh;
+                    let _ = client.fetch(number, CacheMode::Use);
+                });
+            }
+            return Ok(response);
+        }
+        self.fetch(number, cache_mode)
+    }
+
+    /// Requests `number`, retrying according to the retry policy, and caches the response.
+    fn fetch(
+        &self,
+        number: String,
+        cache_mode: CacheMode,
+    ) -> Result<IsEvenApiResponse, IsEvenApiError> {
+        let request_id = self.config.new_request_id();
+        let mut retry = 0;
+        loop {
+            match self
+                .get_once(&number, &request_id, cache_mode)
+                .map_err(|e| e.with_request_id(&request_id))
+            {
+                Err(e) if self.config.should_retry(retry, &e) => {
+                    let delay = self.config.retry_delay(retry);
+                    debug!(
+                        "[{}] Request failed ({}), retrying in {:?}",
+                        request_id, e, delay
+                    );
+                    self.config.clock.sleep_blocking(delay);
+                    retry += 1;
+                }
+                result => return self.config.store(number, result),
+            }
+        }
     }
 
     /// sends a GET request to the isEven API for a given number and returns its JSON response as a `String`.
@@ -233,16 +971,159 @@ impl IsEvenApiBlockingClient {
     ///
     /// Unlike [`Self::get`], error responses will NOT be considered an error. Only request failures will be reported
     /// as an error.
-    pub fn get_json<T: Display>(&self, number: T) -> Result<String, IsEvenApiError> {
-        let response = self.fetch_response(number)?;
-        Ok(response.text().expect("Unable to decode response body"))
+    pub fn get_json(&self, number: impl Into<ApiNumber>) -> Result<String, IsEvenApiError> {
+        self.get_json_with(number.into())
+    }
+
+    /// Like [`Self::get_json`], for a number given as text.
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::get_json`].
+    pub fn get_json_str(&self, number: &str) -> Result<String, IsEvenApiError> {
+        self.get_json_with(number)
+    }
+
+    /// Like [`Self::get_json_str`], for a number given as anything which can be displayed.
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::get_json`].
+    #[deprecated(note = "use `get_json` for integers and `ApiNumber`s, or `get_json_str` for text")]
+    pub fn get_json_display<T: Display>(&self, number: T) -> Result<String, IsEvenApiError> {
+        self.get_json_with(number)
+    }
+
+    fn get_json_with<T: Display>(&self, number: T) -> Result<String, IsEvenApiError> {
+        let request_id = self.config.new_request_id();
+        let mut retry = 0;
+        loop {
+            match self
+                .fetch_response(&number, &request_id, CacheMode::NoCache)
+                .map_err(|e| e.with_request_id(&request_id))
+            {
+                Err(e) if self.config.should_retry(retry, &e) => {
+                    self.config
+                        .clock
+                        .sleep_blocking(self.config.retry_delay(retry));
+                    retry += 1;
+                }
+                response => return Ok(response?.text().expect("Unable to decode response body")),
+            }
+        }
+    }
+
+    /// Returns the URL which [`Self::get`] requests for `number`, e.g. for printing it without making a request.
+    pub fn request_url(&self, number: impl Into<ApiNumber>) -> String {
+        self.config.request_url(number.into())
+    }
+
+    /// Like [`Self::request_url`], for a number given as text.
+    pub fn request_url_str(&self, number: &str) -> String {
+        self.config.request_url(number)
+    }
+
+    /// Like [`Self::request_url_str`], for a number given as anything which can be displayed.
+    #[deprecated(
+        note = "use `request_url` for integers and `ApiNumber`s, or `request_url_str` for text"
+    )]
+    pub fn request_url_display<T: Display>(&self, number: T) -> String {
+        self.config.request_url(number)
+    }
+
+    /// Make a single request and parse the response
+    fn get_once<T: Display>(
+        &self,
+        number: T,
+        request_id: &str,
+        cache_mode: CacheMode,
+    ) -> Result<IsEvenApiResponse, IsEvenApiError> {
+        let response = self.fetch_response(number, request_id, cache_mode)?;
+        let status = response.status();
+        let metadata = ResponseMetadata::new(request_id, response.headers());
+        decode(status, &response.bytes()?, metadata)
+    }
+
+    /// Make the actual web request
+    #[cfg(feature = "client")]
+    fn fetch_response<T: Display>(
+        &self,
+        number: T,
+        request_id: &str,
+        cache_mode: CacheMode,
+    ) -> Result<reqwest::blocking::Response, IsEvenApiError> {
+        if let Some(limiter) = &self.config.rate_limiter {
+            self.config.clock.sleep_blocking(limiter.reserve());
+        }
+        let request = self.config.prepare(number, request_id, cache_mode);
+        debug!("[{}] GET {}", request_id, request.url());
+        let started = Instant::now();
+        let result = self.send(
+            self.config.authorize_blocking(
+                self.client
+                    .get(request.url())
+                    .headers(request.headers().clone()),
+            ),
+        );
+        let response = self
+            .config
+            .finish(&request, result.map_err(IsEvenApiError::from))?;
+        log_response(&response, request_id, started);
+        if !log_enabled!(Level::Trace) {
+            return Ok(response);
+        }
+        let parts = response_parts(&response);
+        let body = response.bytes()?;
+        trace!("Response body: {}", String::from_utf8_lossy(&body));
+        Ok(parts.body(body).expect("valid response parts").into())
+    }
+
+    /// Sends `request`, trying HTTP/3 first if it is enabled.
+    #[cfg(feature = "client")]
+    fn send(
+        &self,
+        request: reqwest::blocking::RequestBuilder,
+    ) -> reqwest::Result<reqwest::blocking::Response> {
+        #[cfg(feature = "http3")]
+        if let Some(h3) = request.try_clone() {
+            let h3 = h3.version(http::Version::HTTP_3).build()?;
+            if self.config.try_http3(h3.url()) {
+                match self.client.execute(h3) {
+                    Ok(response) => return Ok(response),
+                    Err(e) => self.config.http3_failed(&e),
+                }
+            }
+        }
+        request.send()
     }
 
     /// Make the actual web request
-    fn fetch_response<T: Display>(&self, number: T) -> reqwest::Result<reqwest::blocking::Response> {
-        let request_url = format!("{api_url}{num}", api_url = API_URL, num = number);
-        debug!("Fetching API response from {}", request_url);
-        self.client.get(request_url).send()
+    #[cfg(not(feature = "client"))]
+    fn fetch_response<T: Display>(
+        &self,
+        number: T,
+        request_id: &str,
+        cache_mode: CacheMode,
+    ) -> Result<agent::Response, IsEvenApiError> {
+        if let Some(limiter) = &self.config.rate_limiter {
+            self.config.clock.sleep_blocking(limiter.reserve());
+        }
+        let request = self.config.prepare(number, request_id, cache_mode);
+        debug!("[{}] GET {}", request_id, request.url());
+        let started = Instant::now();
+        let authorization = self.config.api_key().map(|key| format!("Bearer {}", key));
+        let result = self
+            .client
+            .get(request.url(), request.headers(), authorization.as_deref());
+        let response = self
+            .config
+            .finish(&request, result.map_err(IsEvenApiError::from))?;
+        log_response(&response, request_id, started);
+        trace!(
+            "Response body: {}",
+            String::from_utf8_lossy(response.body())
+        );
+        Ok(response)
     }
 }
 
@@ -254,10 +1135,21 @@ impl Default for IsEvenApiBlockingClient {
 }
 
 /// Struct containing the return response from the API.
-#[derive(Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+///
+/// Responses fetched by a client know the number they answer, which is not part of the API's JSON and so is not
+/// serialized. Comparisons and hashing take the number into account, but not the [metadata](Self::metadata).
+/// Responses are ordered by number first, using the numeric order of [`ApiNumber`] with responses without a number
+/// first, then by ad and parity, so sorting responses to different numbers puts them in numeric order. Use
+/// [`Self::cmp_by_number`] to compare by number alone, e.g. to deduplicate responses to the same number with different
+/// ads.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct IsEvenApiResponse {
+    #[serde(skip)]
+    number: Option<ApiNumber>,
     ad: String,
     iseven: bool,
+    #[serde(skip)]
+    metadata: ResponseMetadata,
 }
 
 impl IsEvenApiResponse {
@@ -275,6 +1167,63 @@ impl IsEvenApiResponse {
     pub fn isodd(&self) -> bool {
         !self.iseven()
     }
+
+    /// Returns details of the request which produced the response, such as its ID.
+    pub fn metadata(&self) -> &ResponseMetadata {
+        &self.metadata
+    }
+
+    /// Returns the number the response answers, or `None` if it is not known, e.g. for responses returned by
+    /// [`sansio::parse_response`].
+    pub fn number(&self) -> Option<&ApiNumber> {
+        self.number.as_ref()
+    }
+
+    /// Returns the response, answering `number`. Useful for responses parsed with [`sansio::parse_response`].
+    pub fn with_number(self, number: ApiNumber) -> Self {
+        Self {
+            number: Some(number),
+            ..self
+        }
+    }
+
+    /// Compares the numbers the responses answer, ignoring everything else. Responses without a number come first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iseven_api::{sansio, ApiNumber, StatusCode};
+    ///
+    /// let answer = |number: i32, ad: &str| {
+    ///     let body = format!(r#"{{"ad": "{}", "iseven": {}}}"#, ad, number % 2 == 0);
+    ///     let response = sansio::parse_response(StatusCode::OK, body.as_bytes()).unwrap();
+    ///     response.with_number(ApiNumber::from(number))
+    /// };
+    /// let mut responses = vec![answer(10, "a"), answer(-3, "b"), answer(10, "c")];
+    /// responses.sort_by(|a, b| a.cmp_by_number(b));
+    /// responses.dedup_by(|a, b| a.cmp_by_number(b).is_eq());
+    /// let numbers: Vec<_> = responses.iter().map(|r| r.number().unwrap().as_str()).collect();
+    /// assert_eq!(numbers, ["-3", "10"]);
+    /// ```
+    pub fn cmp_by_number(&self, other: &Self) -> std::cmp::Ordering {
+        self.number.cmp(&other.number)
+    }
+
+    /// Returns the response, answering `number` if it is a valid number.
+    /// Returns the response marked as served from a cache, as told by [`ResponseMetadata::from_cache`].
+    pub(crate) fn cached(self) -> Self {
+        Self {
+            metadata: self.metadata.cached(),
+            ..self
+        }
+    }
+
+    pub(crate) fn for_number(self, number: &str) -> Self {
+        Self {
+            number: number.parse().ok(),
+            ..self
+        }
+    }
 }
 
 impl Display for IsEvenApiResponse {
@@ -284,10 +1233,14 @@ impl Display for IsEvenApiResponse {
 }
 
 /// Struct containing the error response from the API.
-#[derive(thiserror::Error, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(
+    thiserror::Error, Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
 #[error("{}", self.error)]
 pub struct IsEvenApiErrorResponse {
     error: String,
+    #[serde(skip)]
+    metadata: ResponseMetadata,
 }
 
 impl IsEvenApiErrorResponse {
@@ -295,86 +1248,187 @@ impl IsEvenApiErrorResponse {
     pub fn error(&self) -> &str {
         &self.error
     }
+
+    /// Returns details of the request which produced the error response, such as its ID.
+    pub fn metadata(&self) -> &ResponseMetadata {
+        &self.metadata
+    }
+}
+
+/// Common interface of async and blocking responses, for logging and layers.
+#[cfg(any(feature = "client", feature = "blocking"))]
+trait ResponseHead {
+    fn status(&self) -> StatusCode;
+    fn version(&self) -> http::Version;
+    fn headers(&self) -> &http::HeaderMap;
+}
+
+#[cfg(feature = "client")]
+impl ResponseHead for Response {
+    fn status(&self) -> StatusCode {
+        self.status()
+    }
+
+    fn version(&self) -> http::Version {
+        self.version()
+    }
+
+    fn headers(&self) -> &http::HeaderMap {
+        self.headers()
+    }
 }
 
-/// An error type containing errors which can result from the API call.
-#[derive(thiserror::Error, Debug)]
-pub enum IsEvenApiError {
-    /// Number out of range for your [pricing plan](https://isevenapi.xyz/#pricing)
-    #[error(transparent)]
-    NumberOutOfRange(IsEvenApiErrorResponse),
-    /// Invalid number specified
-    #[error(transparent)]
-    InvalidNumber(IsEvenApiErrorResponse),
-    /// Unknown error response received, with HTTP status code
-    #[error("Server returned status code {1}: {0}")]
-    UnknownErrorResponse(IsEvenApiErrorResponse, StatusCode),
-    /// Error in making API request
-    #[error("network error: {0}")]
-    NetworkError(#[from] reqwest::Error),
+#[cfg(all(feature = "blocking", feature = "client"))]
+impl ResponseHead for reqwest::blocking::Response {
+    fn status(&self) -> StatusCode {
+        self.status()
+    }
+
+    fn version(&self) -> http::Version {
+        self.version()
+    }
+
+    fn headers(&self) -> &http::HeaderMap {
+        self.headers()
+    }
 }
 
-/// Enum of response types for serde
-#[derive(Deserialize, Debug)]
-#[serde(untagged)]
-enum IsEvenResponseType {
-    Ok(IsEvenApiResponse),
-    Err(IsEvenApiErrorResponse),
+#[cfg(all(feature = "blocking", not(feature = "client")))]
+impl ResponseHead for agent::Response {
+    fn status(&self) -> StatusCode {
+        self.status()
+    }
+
+    fn version(&self) -> http::Version {
+        self.version()
+    }
+
+    fn headers(&self) -> &http::HeaderMap {
+        self.headers()
+    }
+}
+
+/// Logs the status of a response to the request with ID `request_id` sent at `started`, and its headers at trace
+/// level.
+#[cfg(any(feature = "client", feature = "blocking"))]
+fn log_response<R: ResponseHead>(response: &R, request_id: &str, started: Instant) {
+    debug!(
+        "[{}] {:?} {} in {:?}",
+        request_id,
+        response.version(),
+        response.status(),
+        started.elapsed()
+    );
+    if log_enabled!(Level::Trace) {
+        for (name, value) in response.headers() {
+            trace!("{}: {}", name, String::from_utf8_lossy(value.as_bytes()));
+        }
+    }
 }
 
-fn parse_response(
-    json: IsEvenResponseType,
-    status: StatusCode,
-) -> Result<IsEvenApiResponse, IsEvenApiError> {
-    match json {
-        IsEvenResponseType::Ok(r) => Ok(r),
-        IsEvenResponseType::Err(e) => match status.as_u16() {
-            400 => Err(IsEvenApiError::InvalidNumber(e)),
-            401 => Err(IsEvenApiError::NumberOutOfRange(e)),
-            _ => Err(IsEvenApiError::UnknownErrorResponse(e, status)),
-        },
+/// Returns a builder for a response with the same status, version and headers as `response`.
+#[cfg(feature = "client")]
+fn response_parts<R: ResponseHead>(response: &R) -> http::response::Builder {
+    let mut builder = http::Response::builder()
+        .status(response.status())
+        .version(response.version());
+    if let Some(headers) = builder.headers_mut() {
+        *headers = response.headers().clone();
     }
+    builder
 }
 
-#[cfg(test)]
+#[cfg(all(test, any(feature = "client", feature = "blocking")))]
 mod tests {
     use crate::*;
 
+    #[cfg(feature = "test-harness")]
     const ODD_INTS: [i32; 5] = [1, 3, 5, 9, 5283];
+    #[cfg(feature = "test-harness")]
     const EVEN_INTS: [i32; 5] = [0, 2, 8, 10, 88888];
+    #[cfg(feature = "test-harness")]
     const OUT_OF_RANGE_INTS: [i32; 3] = [1000000, i32::MAX, -1];
     const INVALID_INPUT: [&str; 4] = ["abc", "1.0.0", "hello world.as_u16()", "3.14"];
 
+    #[cfg(feature = "test-harness")]
+    async fn cassette_server(scenario: harness::Scenario) -> harness::CassetteServer {
+        harness::CassetteServer::start(harness::Cassette::scenario(scenario))
+            .await
+            .unwrap()
+    }
+
+    #[cfg(feature = "test-harness")]
     #[tokio::test]
     async fn test_valid_int() {
-        let client = IsEvenApiClient::new();
+        let server = cassette_server(harness::Scenario::Answers).await;
+        let client = IsEvenApiClient::builder()
+            .base_url(server.url())
+            .build()
+            .unwrap();
         for (&a, b) in ODD_INTS.iter().zip(EVEN_INTS) {
             assert!(client.get(a).await.unwrap().isodd());
             assert!(client.get(b).await.unwrap().iseven());
         }
     }
 
+    #[cfg(feature = "test-harness")]
     #[tokio::test]
     async fn test_out_of_range() {
-        let client = IsEvenApiClient::new();
+        let server = cassette_server(harness::Scenario::NumberOutOfRange).await;
+        let client = IsEvenApiClient::builder()
+            .base_url(server.url())
+            .build()
+            .unwrap();
         for &a in OUT_OF_RANGE_INTS.iter() {
-            assert!(client.get(a).await.is_err());
+            let error = client.get(a).await.unwrap_err();
+            assert!(matches!(
+                error,
+                IsEvenApiError::Api(ApiError::NumberOutOfRange(_))
+            ));
+            assert!(error.request_id().is_some());
         }
     }
 
+    #[cfg(feature = "client")]
     #[tokio::test]
     async fn test_invalid_input() {
         let client = IsEvenApiClient::new();
         for &a in INVALID_INPUT.iter() {
-            assert!(client.get(a).await.is_err());
+            let error = client.get_str(a).await.unwrap_err();
+            assert!(matches!(error, IsEvenApiError::InvalidInput { ref input, .. } if input == a));
+            assert_eq!(error.status(), None);
         }
     }
 
+    #[cfg(feature = "client")]
+    #[tokio::test]
+    async fn test_transport_error_request_id() {
+        let client = IsEvenApiClient::builder()
+            .base_url(Url::parse("http://127.0.0.1:1/").unwrap())
+            .retry_policy(RetryPolicy::none())
+            .build()
+            .unwrap();
+        let error = client.get(42).await.unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Transport);
+        assert!(error.request_id().is_some());
+        assert!(client
+            .get_json(42)
+            .await
+            .unwrap_err()
+            .request_id()
+            .is_some());
+    }
+
     // blocking tests
     #[test]
-    #[cfg(feature = "blocking")]
+    #[cfg(all(feature = "blocking", feature = "test-harness"))]
     fn test_valid_int_blocking() {
-        let client = IsEvenApiBlockingClient::new();
+        let tokio = tokio::runtime::Runtime::new().unwrap();
+        let server = tokio.block_on(cassette_server(harness::Scenario::Answers));
+        let client = IsEvenApiBlockingClient::builder()
+            .base_url(server.url())
+            .build_blocking()
+            .unwrap();
         for (&a, b) in ODD_INTS.iter().zip(EVEN_INTS) {
             assert!(client.get(a).unwrap().isodd());
             assert!(client.get(b).unwrap().iseven());
@@ -382,20 +1436,61 @@ mod tests {
     }
 
     #[test]
-    #[cfg(feature = "blocking")]
+    #[cfg(all(feature = "blocking", feature = "test-harness"))]
     fn test_out_of_range_blocking() {
-        let client = IsEvenApiBlockingClient::new();
+        let tokio = tokio::runtime::Runtime::new().unwrap();
+        let server = tokio.block_on(cassette_server(harness::Scenario::NumberOutOfRange));
+        let client = IsEvenApiBlockingClient::builder()
+            .base_url(server.url())
+            .build_blocking()
+            .unwrap();
         for &a in OUT_OF_RANGE_INTS.iter() {
-            assert!(client.get(a).is_err());
+            let error = client.get(a).unwrap_err();
+            assert!(matches!(
+                error,
+                IsEvenApiError::Api(ApiError::NumberOutOfRange(_))
+            ));
         }
     }
 
+    #[test]
+    #[cfg(all(feature = "blocking", feature = "test-harness"))]
+    fn test_garbage_body_blocking() {
+        let tokio = tokio::runtime::Runtime::new().unwrap();
+        let server = tokio.block_on(cassette_server(harness::Scenario::GarbageBody));
+        let client = IsEvenApiBlockingClient::builder()
+            .base_url(server.url())
+            .build_blocking()
+            .unwrap();
+        let error = client.get(42).unwrap_err();
+        assert!(matches!(
+            error,
+            IsEvenApiError::Protocol(ProtocolError::Decode(..))
+        ));
+        assert!(error.request_id().is_some());
+    }
+
+    #[test]
+    #[cfg(feature = "blocking")]
+    fn test_transport_error_request_id_blocking() {
+        let client = IsEvenApiBlockingClient::builder()
+            .base_url(Url::parse("http://127.0.0.1:1/").unwrap())
+            .retry_policy(RetryPolicy::none())
+            .build_blocking()
+            .unwrap();
+        let error = client.get(42).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Transport);
+        assert!(error.request_id().is_some());
+        assert!(client.get_json(42).unwrap_err().request_id().is_some());
+    }
+
     #[test]
     #[cfg(feature = "blocking")]
     fn test_invalid_input_blocking() {
         let client = IsEvenApiBlockingClient::new();
         for &a in INVALID_INPUT.iter() {
-            assert!(client.get(a).is_err());
+            let error = client.get_str(a).unwrap_err();
+            assert!(matches!(error, IsEvenApiError::InvalidInput { ref input, .. } if input == a));
         }
     }
 }
@@ -1,52 +1,1410 @@
-use std::{fmt::Display, process::exit};
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    fmt::Display,
+    io::{stderr, stdout, Write},
+    num::NonZeroUsize,
+    path::PathBuf,
+    pin::pin,
+    process::exit,
+    time::{Duration, Instant},
+};
 
-use clap::Parser;
+use clap::{ArgAction, ArgGroup, CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+use futures_util::{stream, StreamExt};
 use human_panic::setup_panic;
-use iseven_api::IsEvenApiBlockingClient;
+use iseven_api::{IsEvenApiClientBuilder, IsEvenApiError, Plan, ResponseCache, RetryPolicy, Url};
+use log::{debug, LevelFilter};
+
+#[cfg(unix)]
+use crate::cli::daemon::{self, DaemonArgs, DaemonClient};
+#[cfg(feature = "self-update")]
+use crate::cli::self_update::SelfUpdateArgs;
+use crate::cli::{
+    ads::AdTally,
+    bench::BenchArgs,
+    cache::{self, CacheArgs},
+    client::Client,
+    clipboard,
+    color::{ColorChoice, Colors},
+    config::{Config, ConfigError},
+    csv::{Column, Table},
+    curl,
+    exit_codes::{self, ExitCodes},
+    failure::{ErrorFormat, Failure},
+    history::{self, History, HistoryArgs},
+    input::{Input, InputError, InputParser},
+    interrupt::{self, Interrupt},
+    locale::{self, Messages},
+    mock_server::MockServerArgs,
+    offline,
+    output::{Format, Outcome, Output, Printer},
+    ping::PingArgs,
+    rate::Rate,
+    serve::ServeArgs,
+    sort::{self, Buffered, Group, GroupBy, SortKey},
+    source::{self, Inputs},
+    stats::{self, Stats},
+    template::Template,
+    tui::TuiArgs,
+    verify::VerifyArgs,
+};
+
+mod cli;
 
 /// Checks whether a number is even or odd using isEven API (https://isevenapi.xyz/).
 #[derive(Parser)]
+#[command(version)]
+#[command(group(
+    ArgGroup::new("counting")
+        .conflicts_with_all([
+            "format", "json", "template", "quiet", "ads_only", "csv", "sort", "group_by", "filter", "watch",
+            "dry_run",
+        ])
+))]
 struct Cli {
-    /// Number to check
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Number to check. If none are given, numbers are read from stdin, one per line
     numbers: Vec<String>,
 
-    /// Print JSON response
-    #[arg(long)]
+    /// Print JSON response (same as `--format json`)
+    #[arg(long, conflicts_with = "format")]
     json: bool,
+
+    /// Output format [default: human]
+    #[arg(long, value_enum)]
+    format: Option<Format>,
+
+    /// Custom output template, e.g. "{number} is {parity}". Available placeholders: {number}, {parity}, {ad},
+    /// {latency} and {error}
+    #[arg(long, conflicts_with_all = ["format", "json"])]
+    template: Option<Template>,
+
+    /// Read the numbers from a column of a CSV file ("-" for stdin), printing each row with a parity column added
+    #[arg(
+        long,
+        requires = "column",
+        conflicts_with_all = ["numbers", "format", "json", "template", "quiet", "sort", "group_by", "watch", "dry_run"]
+    )]
+    csv: Option<PathBuf>,
+
+    /// Column of the `--csv` file holding the numbers, by header name or 1-based index
+    #[arg(long, requires = "csv")]
+    column: Option<Column>,
+
+    /// Read the numbers from the system clipboard, separated by whitespace or commas. Needs pbpaste on macOS,
+    /// PowerShell on Windows, or one of wl-paste, xclip or xsel elsewhere
+    #[arg(long, conflicts_with_all = ["numbers", "csv", "watch", "dry_run"])]
+    clipboard: bool,
+
+    /// Check this many random numbers, e.g. to smoke-test the API or a proxy's cache
+    #[arg(long, value_name = "N", conflicts_with_all = ["numbers", "csv", "clipboard", "watch", "dry_run"])]
+    random: Option<usize>,
+
+    /// Smallest number generated by `--random`
+    #[arg(
+        long,
+        requires = "random",
+        allow_negative_numbers = true,
+        default_value_t = 0
+    )]
+    min: i64,
+
+    /// Largest number generated by `--random`
+    #[arg(
+        long,
+        requires = "random",
+        allow_negative_numbers = true,
+        default_value_t = 999_999
+    )]
+    max: i64,
+
+    /// Seed for `--random`, to check the same numbers again [default: chosen randomly]
+    #[arg(long, requires = "random")]
+    seed: Option<u64>,
+
+    /// Radix of all numbers, e.g. 16 for hexadecimal [default: 10, or as given by a 0x, 0o or 0b prefix]
+    #[arg(long, value_parser = clap::value_parser!(u32).range(2..=36))]
+    radix: Option<u32>,
+
+    /// Read numbers as roman numerals, e.g. "XLII"
+    #[arg(long, conflicts_with = "radix")]
+    roman: bool,
+
+    /// Read numbers as English words, e.g. "forty-two" or "one hundred and seven"
+    #[arg(long, conflicts_with_all = ["radix", "roman"])]
+    words: bool,
+
+    /// Only print `even` or `odd` for each number
+    #[arg(short, long, conflicts_with_all = ["format", "json", "template"])]
+    quiet: bool,
+
+    /// Do not print the advertisement in human-readable output
+    #[arg(long)]
+    no_ad: bool,
+
+    /// Only print the number of even and odd numbers, and of failures, once all numbers have been checked
+    #[arg(long, group = "counting")]
+    count: bool,
+
+    /// Only print the number of even numbers, once all numbers have been checked
+    #[arg(long, group = "counting")]
+    count_even: bool,
+
+    /// Only print the number of odd numbers, once all numbers have been checked
+    #[arg(long, group = "counting")]
+    count_odd: bool,
+
+    /// Only print the advertisement from each response
+    #[arg(
+        long,
+        conflicts_with_all = ["format", "json", "template", "quiet", "no_ad", "csv", "sort", "group_by", "watch", "dry_run"]
+    )]
+    ads_only: bool,
+
+    /// With `--ads-only`, print each distinct advertisement once, with the number of times it was seen
+    #[arg(long, requires = "ads_only")]
+    unique: bool,
+
+    /// Language of human-readable output, e.g. "de" [default: from LANG, or English]
+    #[arg(long, value_parser = parse_lang)]
+    lang: Option<&'static Messages>,
+
+    /// When to use colored output [default: auto]
+    #[arg(long, value_enum)]
+    color: Option<ColorChoice>,
+
+    /// Print the results in this order once all numbers have been checked
+    #[arg(long, value_enum, conflicts_with = "watch")]
+    sort: Option<SortKey>,
+
+    /// Print the results in groups with headings once all numbers have been checked
+    #[arg(long, value_enum, conflicts_with_all = ["watch", "quiet"])]
+    group_by: Option<GroupBy>,
+
+    /// Only print numbers with the given parity
+    #[arg(long, value_enum)]
+    filter: Option<Parity>,
+
+    /// Exit with status 0 if all numbers are even, 1 if any is odd and 2 on errors
+    #[arg(long)]
+    exit_code: bool,
+
+    /// Exit statuses for outcomes of the run, e.g. "odd=3,error=4,rate-limited=75"
+    ///
+    /// Outcomes are odd (some number is odd), error (some number failed to be checked) and rate-limited (some request
+    /// was rejected with HTTP 429, taking precedence over error). Outcomes not given keep their default status.
+    #[arg(long, value_name = "MAP")]
+    exit_code_map: Option<ExitCodes>,
+
+    /// Stop at the first number which fails to be checked (default)
+    #[arg(long, overrides_with = "keep_going")]
+    fail_fast: bool,
+
+    /// Keep checking the remaining numbers after a failure, exiting with an error status at the end
+    #[arg(long, overrides_with = "fail_fast")]
+    keep_going: bool,
+
+    /// Keep checking after failures, but stop once this many numbers have failed
+    #[arg(long, conflicts_with = "fail_fast")]
+    max_errors: Option<NonZeroUsize>,
+
+    /// Print each request and response status to stderr; repeat (-vv) to also print response headers and bodies
+    #[arg(short, long, action = ArgAction::Count, global = true)]
+    verbose: u8,
+
+    /// Use the blocking HTTP client instead of the async one, for debugging
+    #[arg(long)]
+    blocking: bool,
+
+    /// Number of numbers to check at a time [default: 1]
+    ///
+    /// Results are printed in the order the numbers were given, whatever the number of jobs.
+    #[arg(short, long)]
+    jobs: Option<NonZeroUsize>,
+
+    /// Maximum rate of requests, e.g. "5/s" or "100/m", to stay within the plan's quota
+    #[arg(long, conflicts_with_all = ["offline", "dry_run"])]
+    rate: Option<Rate>,
+
+    /// Send requests through a running daemon, sharing its connection and cache [default socket: see daemon --help]
+    #[cfg(unix)]
+    #[arg(long, value_name = "SOCKET", num_args = 0..=1, conflicts_with_all = ["blocking", "offline", "dry_run", "cache"])]
+    via_daemon: Option<Option<PathBuf>>,
+
+    /// Re-check the numbers at this interval, e.g. "30s", printing only changes in parity or error state
+    #[arg(long, value_parser = humantime::parse_duration, conflicts_with_all = ["offline", "dry_run"])]
+    watch: Option<Duration>,
+
+    /// Write a JSON report of the run, with counts, durations and errors by kind, to a file
+    #[arg(long)]
+    summary_json: Option<PathBuf>,
+
+    /// Write results to a file instead of stdout
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Append to the `--output` file instead of overwriting it
+    #[arg(long, requires = "output")]
+    append: bool,
+
+    /// Check each distinct number only once, answering repeated numbers from the first successful result
+    #[arg(long)]
+    dedup: bool,
+
+    /// Format of errors for numbers which could not be checked, printed to stderr
+    #[arg(long, value_enum, default_value_t = ErrorFormat::Text)]
+    errors: ErrorFormat,
+
+    /// Validate all numbers locally, including the range allowed by the plan, and report every invalid one before
+    /// making any requests
+    #[arg(long)]
+    strict: bool,
+
+    /// Flush the output after every result [default: when reading numbers from a pipe]
+    #[arg(long, conflicts_with_all = ["sort", "group_by"])]
+    line_buffered: bool,
+
+    /// Print summary statistics to stderr after all numbers have been checked
+    #[arg(long)]
+    stats: bool,
+
+    /// Answer from the cache or by computing parity locally, without making any requests
+    #[arg(long)]
+    offline: bool,
+
+    /// Print the URL which would be requested for each number, without making any requests
+    #[arg(long, conflicts_with = "offline")]
+    dry_run: bool,
+
+    /// Print an equivalent curl command for each request to stderr, or to stdout with `--dry-run`
+    #[arg(long, conflicts_with = "offline")]
+    curl: bool,
+
+    /// Number of times to retry a request after a transient failure
+    #[arg(long, default_value_t = 0)]
+    retries: u32,
+
+    /// Delay before the first retry, e.g. "500ms" or "2s"
+    #[arg(long, value_parser = humantime::parse_duration, default_value = "500ms")]
+    retry_delay: Duration,
+
+    /// Factor by which the retry delay is multiplied after each retry
+    #[arg(long, default_value_t = RetryPolicy::DEFAULT_BACKOFF, value_parser = parse_backoff)]
+    retry_backoff: f64,
+
+    /// Timeout for each request, e.g. "3s" or "500ms"
+    #[arg(long, value_parser = humantime::parse_duration)]
+    timeout: Option<Duration>,
+
+    /// Base URL of the API, e.g. a mirror or a local mock server
+    #[arg(long, env = "ISEVEN_API_URL")]
+    base_url: Option<Url>,
+
+    /// API key for paid plans
+    #[arg(long, env = "ISEVEN_API_KEY", hide_env_values = true)]
+    api_key: Option<String>,
+
+    /// Connect to the API through this Unix domain socket instead of over TCP, e.g. to a proxy started with
+    /// `serve --socket`
+    #[cfg(unix)]
+    #[arg(long, value_name = "PATH", env = "ISEVEN_API_SOCKET")]
+    unix_socket: Option<PathBuf>,
+
+    /// Cache responses on disk, reusing them in later runs
+    #[arg(long, global = true)]
+    cache: bool,
+
+    /// Time after which cached responses expire, e.g. "1h" [default: never]
+    #[arg(long, value_parser = humantime::parse_duration, global = true)]
+    cache_ttl: Option<Duration>,
+
+    /// Location of the persistent cache [default: <cache dir>/iseven_api/cache.json]
+    #[arg(long, env = "ISEVEN_API_CACHE", global = true)]
+    cache_path: Option<PathBuf>,
+
+    /// Record every successful check in the history, for the `history` subcommand
+    #[arg(long, global = true)]
+    history: bool,
+
+    /// Location of the history [default: <data dir>/iseven_api/history.jsonl]
+    #[arg(long, env = "ISEVEN_API_HISTORY", global = true)]
+    history_path: Option<PathBuf>,
+
+    /// Configuration profile to use [default: the `default` profile, if any]
+    #[arg(long, env = "ISEVEN_API_PROFILE", global = true)]
+    profile: Option<String>,
+
+    /// Path to the configuration file [default: <config dir>/iseven_api/config.toml]
+    #[arg(long, env = "ISEVEN_API_CONFIG", global = true)]
+    config: Option<PathBuf>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print the effective configuration, combining the configuration file with command line options
+    Config,
+    /// Print a shell completion script to stdout
+    Completions {
+        /// Shell to generate completions for
+        shell: Shell,
+    },
+    /// Run a local proxy server with the same API as isEven API, sharing one cache and rate limiter between all
+    /// of its clients
+    Serve(ServeArgs),
+    /// Run a mock server answering in the same format as isEven API, with locally computed parity and fake ads
+    MockServer(MockServerArgs),
+    /// Inspect and manage the persistent response cache
+    Cache(CacheArgs),
+    /// Repeatedly check a number, printing the latency of each request
+    Ping(PingArgs),
+    /// Diagnose problems with connecting to the API or with the configuration
+    Doctor,
+    /// Measure the latency, throughput and error rate of the API
+    Bench(BenchArgs),
+    /// List, search and clear the history of checked numbers
+    History(HistoryArgs),
+    /// Check numbers with both the API and local computation, reporting any answers which differ
+    Verify(VerifyArgs),
+    /// Answer requests from `--via-daemon` on a Unix domain socket, sharing one connection and cache between them
+    #[cfg(unix)]
+    Daemon(DaemonArgs),
+    /// Monitor numbers on an interactive dashboard, re-checking them periodically
+    Tui(TuiArgs),
+    /// Run a Model Context Protocol server on stdio, offering an is_even tool to AI assistants
+    Mcp,
+    /// Replace this binary with the latest release from GitHub, after verifying its signature
+    #[cfg(feature = "self-update")]
+    SelfUpdate(SelfUpdateArgs),
+    /// Print a man page in roff format to stdout
+    #[command(hide = true)]
+    Man,
+}
+
+impl Cli {
+    /// Returns the configuration given by command line options.
+    fn config_overrides(&self) -> Config {
+        Config {
+            base_url: self.base_url.clone(),
+            api_key: self.api_key.clone(),
+            timeout: self.timeout,
+            #[cfg(unix)]
+            unix_socket: self.unix_socket.clone(),
+            jobs: self.jobs,
+            format: if self.json {
+                Some(Format::Json)
+            } else {
+                self.format
+            },
+            color: self.color,
+            cache: self.cache.then_some(true),
+            cache_ttl: self.cache_ttl,
+            cache_path: self.cache_path.clone(),
+            history: self.history.then_some(true),
+            history_path: self.history_path.clone(),
+            exit_codes: self.exit_code_map,
+            ..Config::default()
+        }
+    }
+
+    /// Returns a client builder configured from the command line and configuration file.
+    fn client_builder(&self, config: &Config) -> IsEvenApiClientBuilder {
+        let retry_policy = RetryPolicy::new(self.retries)
+            .with_delay(self.retry_delay)
+            .with_backoff(self.retry_backoff);
+        let mut builder = IsEvenApiClientBuilder::new().retry_policy(retry_policy);
+        if let Some(timeout) = config.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(base_url) = config.base_url.clone() {
+            builder = builder.base_url(base_url);
+        }
+        if let Some(api_key) = config.api_key.clone() {
+            builder = builder.api_key(api_key);
+        }
+        #[cfg(unix)]
+        if let Some(path) = config.unix_socket.clone() {
+            builder = builder.unix_socket(path);
+        }
+        if let Some(rate) = self.rate {
+            builder = builder.rate_limiter(rate.limiter());
+        }
+        builder
+    }
+
+    /// Returns the socket of the daemon to send requests through, if `--via-daemon` is given.
+    fn daemon_socket(&self) -> Option<PathBuf> {
+        #[cfg(unix)]
+        if let Some(socket) = &self.via_daemon {
+            return Some(socket.clone().unwrap_or_else(daemon::default_socket));
+        }
+        None
+    }
+
+    /// Builds the client for checking numbers, connecting to the daemon if `--via-daemon` is given.
+    async fn client(&self, builder: IsEvenApiClientBuilder) -> Result<Client, Box<dyn Error>> {
+        #[cfg(unix)]
+        if let Some(socket) = self.daemon_socket() {
+            return Ok(Client::Daemon(DaemonClient::connect(socket).await?));
+        }
+        Ok(Client::build(builder, self.blocking)?)
+    }
+
+    /// Loads the configuration file and applies command line overrides.
+    fn load_config(&self) -> Result<Config, (ConfigError, Option<PathBuf>)> {
+        let path = self.config.clone().or_else(Config::default_path);
+        let file = match &path {
+            Some(path) => Config::load(path).map_err(|e| (e, Some(path.clone())))?,
+            None => Config::default(),
+        };
+        let file = file
+            .select_profile(self.profile.as_deref())
+            .map_err(|e| (e, path))?;
+        Ok(file.merge(self.config_overrides()))
+    }
+}
+
+fn parse_lang(s: &str) -> Result<&'static Messages, String> {
+    locale::find(s).ok_or_else(|| {
+        let codes = locale::LANGUAGES.map(|m| m.code).join(", ");
+        format!("unsupported language, expected one of {}", codes)
+    })
+}
+
+fn parse_backoff(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(f) if f.is_finite() && f >= 0.0 => Ok(f),
+        Ok(_) => Err("must be a finite, non-negative number".to_string()),
+        Err(e) => Err(e.to_string()),
+    }
 }
 
-fn print_error<M: Display, V: Display>(msg: M, input_value: V) -> ! {
+/// Parity of a number, as accepted by `--filter`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Parity {
+    /// Even numbers
+    Even,
+    /// Odd numbers
+    Odd,
+}
+
+impl Parity {
+    fn matches(self, iseven: bool) -> bool {
+        iseven == (self == Parity::Even)
+    }
+}
+
+fn print_error<M: Display, V: Display>(msg: M, input_value: V, colors: Colors) {
     let argv = std::env::args().collect::<Vec<_>>();
     let app_name = &argv[0];
-    eprintln!("error: {}: {}: {}", app_name, input_value, msg);
-    exit(1)
+    eprintln!(
+        "{}",
+        colors.error(format_args!(
+            "{}: {}: {}: {}",
+            locale::current().error,
+            app_name,
+            input_value,
+            msg
+        ))
+    );
+}
+
+/// Reports a number which could not be checked to stderr, in the format given by `--errors`.
+fn report_failure(format: ErrorFormat, failure: Failure, colors: Colors) {
+    match format {
+        ErrorFormat::Text => print_error(&failure.message, &failure, colors),
+        ErrorFormat::Json => eprintln!(
+            "{}",
+            serde_json::to_string(&failure).expect("Unable to serialize error")
+        ),
+    }
+}
+
+/// Initialises logging from `RUST_LOG`, additionally printing HTTP traffic at the given `--verbose` level.
+fn init_logger(verbose: u8) {
+    let mut logger = env_logger::Builder::from_default_env();
+    if verbose > 0 {
+        let level = if verbose == 1 {
+            LevelFilter::Debug
+        } else {
+            LevelFilter::Trace
+        };
+        logger
+            .filter_module("iseven_api", level)
+            .format(|buf, record| writeln!(buf, "* {}", record.args()));
+    }
+    logger.init();
 }
 
-fn main() {
-    env_logger::init();
+#[tokio::main]
+async fn main() {
     setup_panic!();
     let cli = Cli::parse();
-    let client = IsEvenApiBlockingClient::new();
-    for num in cli.numbers {
-        if cli.json {
-            match client.get_json(&num) {
-                Ok(response) => {
-                    println!("{}", response)
+    init_logger(cli.verbose);
+    locale::set(cli.lang.unwrap_or_else(locale::from_env));
+    if let Some(Command::Completions { shell }) = cli.command {
+        let mut command = Cli::command();
+        let name = command.get_name().to_string();
+        clap_complete::generate(shell, &mut command, name, &mut stdout());
+        return;
+    }
+    if let Some(Command::Man) = cli.command {
+        let colors = ColorChoice::Never.for_stream(&stderr());
+        if let Err(e) = clap_mangen::Man::new(Cli::command()).render(&mut stdout()) {
+            print_error(e, "man", colors);
+            exit(1)
+        }
+        return;
+    }
+    #[cfg(feature = "self-update")]
+    if let Some(Command::SelfUpdate(args)) = &cli.command {
+        if let Err(e) = args.run().await {
+            let colors = cli.color.unwrap_or_default().for_stream(&stderr());
+            print_error(e, "self-update", colors);
+            exit(1)
+        }
+        return;
+    }
+    if let Some(Command::Doctor) = cli.command {
+        let colors = cli.color.unwrap_or_default().for_stream(&stdout());
+        let path = cli.config.clone().or_else(Config::default_path);
+        // keep diagnosing with the command line options alone if the configuration file is broken
+        let (config, error) = match cli.load_config() {
+            Ok(config) => (config, None),
+            Err((e, _)) => (cli.config_overrides(), Some(e)),
+        };
+        let healthy = cli::doctor::run(
+            path.as_deref(),
+            error.as_ref(),
+            &config,
+            cli.client_builder(&config),
+            colors,
+        )
+        .await;
+        exit(if healthy { 0 } else { 1 })
+    }
+    // the configuration file's exit statuses are unknown if it cannot be loaded
+    let error_code = cli
+        .exit_code_map
+        .unwrap_or_default()
+        .failure(cli.exit_code, false);
+    let config = match cli.load_config() {
+        Ok(config) => config,
+        Err((e, path)) => {
+            let error_colors = cli.color.unwrap_or_default().for_stream(&stderr());
+            let path = path.map(|p| p.display().to_string()).unwrap_or_default();
+            print_error(e, path, error_colors);
+            exit(error_code)
+        }
+    };
+    let color = config.color.unwrap_or_default();
+    let error_colors = color.for_stream(&stderr());
+    match &cli.command {
+        Some(Command::Config) => print!(
+            "{}",
+            toml::to_string(&config.effective()).expect("Unable to serialize configuration")
+        ),
+        Some(Command::Serve(args)) => {
+            let persist = persistent_cache_path(&config, error_colors);
+            let result = args
+                .run(
+                    cli.client_builder(&config),
+                    config.cache_ttl,
+                    persist.as_deref(),
+                )
+                .await;
+            if let Err(e) = result {
+                print_error(e, "serve", error_colors);
+                exit(1)
+            }
+        }
+        Some(Command::MockServer(args)) => {
+            if let Err(e) = args.run().await {
+                print_error(e, "mock-server", error_colors);
+                exit(1)
+            }
+        }
+        Some(Command::Cache(args)) => {
+            let Some(path) = config.cache_path() else {
+                print_error(
+                    "unable to determine the cache directory",
+                    "cache",
+                    error_colors,
+                );
+                exit(1)
+            };
+            if let Err(e) = args.run(&path, config.cache_ttl) {
+                print_error(e, path.display(), error_colors);
+                exit(1)
+            }
+        }
+        Some(Command::History(args)) => {
+            let Some(path) = config.history_path() else {
+                print_error(
+                    "unable to determine the data directory",
+                    "history",
+                    error_colors,
+                );
+                exit(1)
+            };
+            match args.run(&path) {
+                Ok(true) => {}
+                Ok(false) => exit(1),
+                Err(e) => {
+                    print_error(e, path.display(), error_colors);
+                    exit(1)
                 }
-                Err(e) => print_error(e, num),
             }
-        } else {
-            match client.get(&num) {
-                Ok(response) => {
-                    println!("Advertisement: {}", response.ad());
-                    println!(
-                        "{} is an {} number",
-                        &num,
-                        if response.iseven() { "even" } else { "odd" }
-                    )
+        }
+        Some(Command::Ping(args)) => match args.run(cli.client_builder(&config)).await {
+            Ok(true) => {}
+            Ok(false) => exit(1),
+            Err(e) => {
+                print_error(e, "ping", error_colors);
+                exit(1)
+            }
+        },
+        #[cfg(unix)]
+        Some(Command::Daemon(args)) => {
+            let persist = persistent_cache_path(&config, error_colors);
+            let result = args
+                .run(
+                    cli.client_builder(&config),
+                    config.cache_ttl,
+                    persist.as_deref(),
+                )
+                .await;
+            if let Err(e) = result {
+                print_error(e, "daemon", error_colors);
+                exit(1)
+            }
+        }
+        Some(Command::Tui(args)) => {
+            let persist = persistent_cache_path(&config, error_colors);
+            let colors = color.for_stream(&stdout());
+            let result = args
+                .run(
+                    cli.client_builder(&config),
+                    config.cache_ttl,
+                    persist.as_deref(),
+                    colors,
+                )
+                .await;
+            if let Err(e) = result {
+                print_error(e, "tui", error_colors);
+                exit(1)
+            }
+        }
+        Some(Command::Mcp) => {
+            if let Err(e) = cli::mcp::run(cli.client_builder(&config)).await {
+                print_error(e, "mcp", error_colors);
+                exit(1)
+            }
+        }
+        Some(Command::Bench(args)) => {
+            if let Err(e) = args.run(cli.client_builder(&config)).await {
+                print_error(e, "bench", error_colors);
+                exit(1)
+            }
+        }
+        Some(Command::Verify(args)) => {
+            let plan = config.plan.unwrap_or_default();
+            match args.run(cli.client_builder(&config), plan).await {
+                Ok(true) => {}
+                Ok(false) => exit(1),
+                Err(e) => {
+                    print_error(e, "verify", error_colors);
+                    exit(1)
                 }
-                Err(e) => print_error(e, num),
             }
         }
+        Some(Command::Completions { .. } | Command::Man | Command::Doctor) => {
+            unreachable!("handled above")
+        }
+        #[cfg(feature = "self-update")]
+        Some(Command::SelfUpdate(_)) => unreachable!("handled above"),
+        None => check_numbers(cli, config, error_colors).await,
+    }
+}
+
+/// Returns the location of the persistent cache if it is enabled, exiting with an error if it cannot be determined.
+fn persistent_cache_path(config: &Config, error_colors: Colors) -> Option<PathBuf> {
+    if config.cache != Some(true) {
+        return None;
+    }
+    match config.cache_path() {
+        Some(path) => Some(path),
+        None => {
+            print_error(
+                "unable to determine the cache directory",
+                "cache",
+                error_colors,
+            );
+            exit(1)
+        }
+    }
+}
+
+/// Opens the history if checks should be recorded in it.
+fn open_history(config: &Config, error_colors: Colors, error_code: i32) -> Option<History> {
+    if config.history != Some(true) {
+        return None;
+    }
+    let Some(path) = config.history_path() else {
+        print_error(
+            "unable to determine the data directory",
+            "history",
+            error_colors,
+        );
+        exit(error_code)
+    };
+    match History::open(&path) {
+        Ok(history) => Some(history),
+        Err(e) => {
+            print_error(e, path.display(), error_colors);
+            exit(error_code)
+        }
+    }
+}
+
+/// Returns a `curl` command line for the request `client` makes for `number`.
+fn curl_command(config: &Config, client: &Client, number: &str) -> String {
+    curl::command(
+        &client.request_url(number),
+        config.api_key.is_some(),
+        config.timeout,
+    )
+}
+
+/// Returns the parser for numbers given on the command line.
+fn input_parser(cli: &Cli) -> InputParser {
+    InputParser {
+        radix: cli.radix,
+        roman: cli.roman,
+        words: cli.words,
+    }
+}
+
+/// Prints the URL which would be requested for each number given on the command line, then exits.
+fn dry_run(cli: &Cli, config: &Config, client: &Client, error_colors: Colors) -> ! {
+    let plan = config.plan.unwrap_or_default();
+    let parser = input_parser(cli);
+    let mut any_failed = false;
+    for input in &cli.numbers {
+        let input = match parser.parse(input) {
+            Ok(input) => input,
+            Err(e) => {
+                any_failed = true;
+                report_failure(cli.errors, Failure::invalid_input(input, &e), error_colors);
+                continue;
+            }
+        };
+        let num = &input.number;
+        match offline::validate(num, plan) {
+            Ok(()) if cli.curl => println!("{}", curl_command(config, client, num)),
+            Ok(()) => println!("{}", client.request_url(num)),
+            Err(e) => {
+                any_failed = true;
+                report_failure(cli.errors, Failure::api(&input, &e), error_colors);
+            }
+        }
+    }
+    let codes = config.exit_codes.unwrap_or_default();
+    exit(if any_failed {
+        codes.failure(cli.exit_code, false)
+    } else {
+        0
+    })
+}
+
+/// Returns `true` if checking should stop after a failure, given the statistics including that failure.
+fn stop_after_failure(cli: &Cli, stats: &Stats) -> bool {
+    match cli.max_errors {
+        Some(max) if stats.errors() >= max.get() => {
+            eprintln!(
+                "Stopping after {} failed numbers (--max-errors)",
+                stats.errors()
+            );
+            true
+        }
+        Some(_) => false,
+        None => !cli.keep_going,
+    }
+}
+
+/// Prints a result, exiting if the output cannot be written, e.g. because the disk is full.
+fn print_result(
+    printer: &mut Printer,
+    input: &Input,
+    result: &Result<Outcome, IsEvenApiError>,
+    latency: Duration,
+    error_colors: Colors,
+    error_code: i32,
+) {
+    if let Err(e) = printer.print(input, result, latency) {
+        print_error(e, "output", error_colors);
+        exit(error_code)
+    }
+}
+
+/// Prints a `--csv` row with the parity of its number appended, or an empty field if it could not be checked.
+fn print_csv_row(
+    printer: &mut Printer,
+    row: &[String],
+    iseven: Option<bool>,
+    error_colors: Colors,
+    error_code: i32,
+) {
+    let parity = match iseven {
+        Some(true) => "even",
+        Some(false) => "odd",
+        None => "",
+    };
+    if let Err(e) = printer.print_csv_row(row, parity) {
+        print_error(e, "output", error_colors);
+        exit(error_code)
+    }
+}
+
+/// Prints a result, or holds it back in `buffer` if results are sorted or grouped.
+fn emit(
+    printer: &mut Printer,
+    buffer: &mut Option<Vec<Buffered>>,
+    input: Input,
+    result: Result<Outcome, IsEvenApiError>,
+    latency: Duration,
+    error_colors: Colors,
+    error_code: i32,
+) {
+    match buffer {
+        Some(buffer) => buffer.push(Buffered {
+            input,
+            result,
+            latency,
+        }),
+        None => print_result(printer, &input, &result, latency, error_colors, error_code),
+    }
+}
+
+/// Prints results held back for `--sort` and `--group-by`, with a heading above each group in human-readable output.
+fn print_buffered(
+    printer: &mut Printer,
+    mut results: Vec<Buffered>,
+    sort_key: Option<SortKey>,
+    group_by: Option<GroupBy>,
+    error_colors: Colors,
+    error_code: i32,
+) {
+    sort::sort(&mut results, sort_key, group_by);
+    let headings = group_by.is_some() && printer.format == Format::Human;
+    let mut group = None;
+    for buffered in &results {
+        let this_group = Group::of(&buffered.result);
+        if headings && group != Some(this_group) {
+            let heading = this_group.heading(printer.messages);
+            if let Err(e) = printer.print_heading(heading, group.is_none()) {
+                print_error(e, "output", error_colors);
+                exit(error_code)
+            }
+            group = Some(this_group);
+        }
+        print_result(
+            printer,
+            &buffered.input,
+            &buffered.result,
+            buffered.latency,
+            error_colors,
+            error_code,
+        );
+    }
+}
+
+/// Re-checks the numbers given on the command line every `interval`, printing changes until interrupted.
+async fn watch(cli: &Cli, config: &Config, interval: Duration, error_colors: Colors) -> ! {
+    let parser = input_parser(cli);
+    let inputs = cli
+        .numbers
+        .iter()
+        .map(|input| {
+            parser.parse(input).unwrap_or_else(|e| {
+                report_failure(cli.errors, Failure::invalid_input(input, &e), error_colors);
+                exit(1)
+            })
+        })
+        .collect::<Vec<_>>();
+    // the cache would hide any changes, so it is not used
+    let client = match cli.client(cli.client_builder(config)).await {
+        Ok(client) => client,
+        Err(e) => {
+            print_error(e, "client", error_colors);
+            exit(1)
+        }
+    };
+    let interrupt = Interrupt::install(interrupt::GRACE_PERIOD);
+    let colors = config.color.unwrap_or_default().for_stream(&stdout());
+    cli::watch::run(&client, &inputs, interval, &interrupt, colors).await;
+    exit(0)
+}
+
+/// Reads all inputs and validates them without making any requests (`--strict`), returning them if all are valid.
+/// Otherwise, every invalid input is reported with its location, and the process exits.
+async fn validate_all(
+    cli: &Cli,
+    config: &Config,
+    inputs: Inputs,
+    parser: &InputParser,
+    error_colors: Colors,
+) -> Inputs {
+    let error_code = config
+        .exit_codes
+        .unwrap_or_default()
+        .failure(cli.exit_code, false);
+    let inputs = inputs.read_all().await.unwrap_or_else(|e| {
+        print_error(e, "stdin", error_colors);
+        exit(error_code)
+    });
+    let plan = config.plan.unwrap_or_default();
+    let mut invalid = 0;
+    for (location, original) in &inputs {
+        let failure = match parser.parse(original) {
+            Ok(input) => match offline::validate(&input.number, plan) {
+                Ok(()) => continue,
+                Err(e) => Failure::api(&input, &e),
+            },
+            Err(e) => Failure::invalid_input(original, &e),
+        };
+        invalid += 1;
+        report_failure(cli.errors, failure.at(*location), error_colors);
+    }
+    if invalid > 0 {
+        eprintln!(
+            "{} of {} numbers are invalid, nothing was checked",
+            invalid,
+            inputs.len()
+        );
+        exit(error_code)
+    }
+    Inputs::List(inputs.into_iter())
+}
+
+/// Generates the numbers for `--random`.
+fn random_inputs(cli: &Cli) -> Inputs {
+    if cli.min > cli.max {
+        Cli::command()
+            .error(
+                clap::error::ErrorKind::ValueValidation,
+                "--min must not be greater than --max",
+            )
+            .exit()
+    }
+    let seed = cli.seed.unwrap_or_else(|| fastrand::u64(..));
+    debug!("Generating random numbers with seed {}", seed);
+    Inputs::random(cli.random.unwrap_or_default(), cli.min..=cli.max, seed)
+}
+
+/// A number read for checking, before its result is known.
+enum Read {
+    /// Input which is not a number, with the reason
+    Invalid(String, InputError),
+    /// Input to be checked, or answered from the cache or an earlier result
+    Number {
+        input: Input,
+        /// The response is in the persistent cache
+        cached: bool,
+        /// The number was read before, and is answered with the earlier result (`--dedup`)
+        duplicate: bool,
+    },
+}
+
+/// Checks `number` with `client`, or locally for `plan` if `offline` is set, returning the outcome and how long it
+/// took. With `raw_json`, the response body is returned as is.
+async fn check(
+    client: &Client,
+    cache: Option<&ResponseCache>,
+    plan: Plan,
+    offline: bool,
+    raw_json: bool,
+    number: &str,
+) -> (Result<Outcome, IsEvenApiError>, Duration) {
+    let start = Instant::now();
+    let result = if offline {
+        offline::check(number, plan, cache).map(Outcome::Response)
+    } else if raw_json {
+        client.get_json(number).await.map(Outcome::raw)
+    } else {
+        client.get(number).await.map(Outcome::Response)
+    };
+    (result, start.elapsed())
+}
+
+/// Checks the numbers given on the command line, printing the results and exiting with the appropriate status.
+async fn check_numbers(cli: Cli, config: Config, error_colors: Colors) {
+    if let Some(interval) = cli.watch {
+        watch(&cli, &config, interval, error_colors).await;
+    }
+    let codes = config.exit_codes.unwrap_or_default();
+    let error_code = codes.failure(cli.exit_code, false);
+    // the daemon has a cache of its own
+    let persist = match cli.daemon_socket() {
+        Some(_) => None,
+        None => persistent_cache_path(&config, error_colors),
+    };
+    let mut builder = cli.client_builder(&config);
+    let cache = persist.as_ref().map(|path| {
+        let cache = cache::new_cache(config.cache_ttl);
+        // an unreadable cache is not worth failing over, the responses can always be fetched again
+        if let Err(e) = cache::load(path, &cache) {
+            print_error(e, path.display(), error_colors);
+        }
+        cache
+    });
+    if let Some(cache) = &cache {
+        builder = builder.cache(cache.clone());
+    }
+    let client = match cli.client(builder).await {
+        Ok(client) => client,
+        Err(e) => {
+            print_error(e, "client", error_colors);
+            exit(error_code)
+        }
+    };
+    if cli.dry_run {
+        dry_run(&cli, &config, &client, error_colors);
+    }
+    let parser = input_parser(&cli);
+    let table = cli.csv.as_ref().map(|path| {
+        let column = cli
+            .column
+            .as_ref()
+            .expect("--column is required with --csv");
+        let is_number = |value: &str| parser.parse(value).is_ok();
+        Table::read(path, column, is_number).unwrap_or_else(|e| {
+            print_error(e, path.display(), error_colors);
+            exit(error_code)
+        })
+    });
+    let read_stdin = table.is_none()
+        && !cli.clipboard
+        && cli.random.is_none()
+        && cli.numbers.is_empty()
+        && source::stdin_has_input();
+    let (mut inputs, source) = match &table {
+        Some(table) => (Inputs::csv(table), "csv"),
+        None if cli.random.is_some() => (random_inputs(&cli), "random"),
+        None if cli.clipboard => match clipboard::read() {
+            Ok(text) => (Inputs::clipboard(&text), "clipboard"),
+            Err(e) => {
+                print_error(e, "clipboard", error_colors);
+                exit(error_code)
+            }
+        },
+        None if read_stdin => (Inputs::stdin(), "stdin"),
+        None => (Inputs::arguments(&cli.numbers), "argument"),
+    };
+    if cli.strict {
+        inputs = validate_all(&cli, &config, inputs, &parser, error_colors).await;
+    }
+    let total = inputs.len();
+    let line_buffered = cli.line_buffered || (read_stdin && source::stdin_is_pipe());
+    let format = config.format.unwrap_or(Format::Human);
+    let out = match &cli.output {
+        Some(path) => match Output::create(path, cli.append) {
+            Ok(out) => out,
+            Err(e) => {
+                print_error(e, path.display(), error_colors);
+                exit(error_code)
+            }
+        },
+        None => Output::Stdout(stdout()),
+    };
+    let mut printer = Printer {
+        format,
+        template: cli.template.clone(),
+        quiet: cli.quiet,
+        no_ad: cli.no_ad,
+        ads_only: cli.ads_only,
+        colors: out.colors(config.color.unwrap_or_default()),
+        offline: cli.offline,
+        messages: locale::current(),
+        out,
+    };
+    if let Some(header) = table.as_ref().and_then(|t| t.header.as_ref()) {
+        if let Err(e) = printer.print_csv_row(header, "parity") {
+            print_error(e, "output", error_colors);
+            exit(error_code)
+        }
+    }
+    let mut history = open_history(&config, error_colors, error_code);
+    let interrupt = Interrupt::install(interrupt::GRACE_PERIOD);
+    let mut buffer = (cli.sort.is_some() || cli.group_by.is_some()).then(Vec::new);
+    let mut tally = cli.unique.then(AdTally::default);
+    let counting = cli.count || cli.count_even || cli.count_odd;
+    let mut stats = Stats::start();
+    // successful outcomes by number, for `--dedup`
+    let mut answered: HashMap<String, Outcome> = HashMap::new();
+    let mut any_failed = false;
+    let mut rate_limited = false;
+    // raw responses bypass the cache, so cached runs print the parsed response instead
+    let raw_json = format == Format::Json
+        && printer.template.is_none()
+        && !printer.ads_only
+        && cache.is_none();
+    let plan = config.plan.unwrap_or_default();
+    let interrupt = &interrupt;
+    // Ctrl-C also ends a wait for more input on stdin
+    let numbers = stream::unfold(inputs, |mut inputs| async move {
+        tokio::select! {
+            biased;
+            _ = interrupt.stopped() => None,
+            input = inputs.next() => input.map(|input| (input, inputs)),
+        }
+    });
+    // numbers requested so far, for `--dedup`
+    let mut requested = HashSet::new();
+    // up to `--jobs` numbers are checked at a time, but their results are handled in the order they were read
+    let checks = numbers
+        .map(|input| {
+            let read = input.map(|(_, input)| match parser.parse(&input) {
+                Err(e) => Read::Invalid(input, e),
+                Ok(input) => {
+                    let num = &input.number;
+                    let duplicate = cli.dedup && !requested.insert(num.clone());
+                    let cached =
+                        !duplicate && cache.as_ref().is_some_and(|cache| cache.get(num).is_some());
+                    if cli.curl && !duplicate && !cached {
+                        eprintln!("{}", curl_command(&config, &client, num));
+                    }
+                    Read::Number {
+                        input,
+                        cached,
+                        duplicate,
+                    }
+                }
+            });
+            let (client, cache) = (&client, cache.as_ref());
+            async move {
+                let result = match &read {
+                    Ok(Read::Number {
+                        input,
+                        duplicate: false,
+                        ..
+                    }) => {
+                        Some(check(client, cache, plan, cli.offline, raw_json, &input.number).await)
+                    }
+                    _ => None,
+                };
+                (read, result)
+            }
+        })
+        .buffered(config.jobs().get());
+    let mut checks = pin!(checks);
+    let mut read = 0;
+    loop {
+        // results are flushed before waiting for more input
+        if line_buffered {
+            if let Err(e) = printer.out.flush() {
+                print_error(e, "output", error_colors);
+                exit(error_code)
+            }
+        }
+        let next = tokio::select! {
+            biased;
+            // the requests in flight are abandoned, but the results so far are still written out
+            _ = interrupt.aborted() => break,
+            next = checks.next() => next,
+        };
+        let (input, checked) = match next {
+            Some((Err(e), _)) => {
+                print_error(e, "stdin", error_colors);
+                any_failed = true;
+                break;
+            }
+            Some((Ok(input), checked)) => (input, checked),
+            None => break,
+        };
+        let row = table.as_ref().map(|table| &table.rows[read]);
+        read += 1;
+        let (input, cached) = match input {
+            Read::Number { input, cached, .. } => (input, cached),
+            Read::Invalid(input, e) => {
+                stats.record_error("invalid_input", Duration::ZERO);
+                any_failed = true;
+                report_failure(cli.errors, Failure::invalid_input(&input, &e), error_colors);
+                if let Some(row) = row {
+                    print_csv_row(&mut printer, row, None, error_colors, error_code);
+                }
+                if stop_after_failure(&cli, &stats) {
+                    break;
+                }
+                continue;
+            }
+        };
+        let num = &input.number;
+        if cached {
+            stats.record_cache_hit();
+        }
+        // a duplicate is answered with the outcome of its first check, which precedes it, or checked again if that
+        // failed
+        let (result, latency) = match checked {
+            Some(checked) => checked,
+            None => match answered.get(num) {
+                Some(outcome) => {
+                    stats.record_duplicate();
+                    (Ok(outcome.clone()), Duration::ZERO)
+                }
+                None => tokio::select! {
+                    biased;
+                    _ = interrupt.aborted() => break,
+                    checked = check(&client, cache.as_ref(), plan, cli.offline, raw_json, num) => checked,
+                },
+            },
+        };
+        match &result {
+            Ok(outcome) => {
+                let iseven = outcome.iseven();
+                stats.record_success(iseven, latency);
+                if let (Some(file), Some(iseven)) = (&mut history, iseven) {
+                    // a history which cannot be written is reported once, without failing the run
+                    if let Err(e) = file.record(&history::Entry::new(num, iseven, source)) {
+                        print_error(e, "history", error_colors);
+                        history = None;
+                    }
+                }
+                if cli.dedup {
+                    answered
+                        .entry(num.clone())
+                        .or_insert_with(|| outcome.clone());
+                }
+                // outcomes without a known parity (API error responses in raw JSON) never match a filter
+                if cli
+                    .filter
+                    .is_some_and(|f| !iseven.is_some_and(|e| f.matches(e)))
+                {
+                    continue;
+                }
+                if let Some(row) = row {
+                    print_csv_row(&mut printer, row, iseven, error_colors, error_code);
+                    continue;
+                }
+                if let (Some(tally), Outcome::Response(response)) = (&mut tally, outcome) {
+                    tally.record(response.ad());
+                    continue;
+                }
+                if counting {
+                    continue;
+                }
+                emit(
+                    &mut printer,
+                    &mut buffer,
+                    input,
+                    result,
+                    latency,
+                    error_colors,
+                    error_code,
+                );
+            }
+            Err(e) => {
+                stats.record_error(stats::error_kind(e), latency);
+                any_failed = true;
+                rate_limited |= exit_codes::is_rate_limited(e);
+                if printer.prints_errors() {
+                    emit(
+                        &mut printer,
+                        &mut buffer,
+                        input,
+                        result,
+                        latency,
+                        error_colors,
+                        error_code,
+                    );
+                } else {
+                    report_failure(cli.errors, Failure::api(&input, e), error_colors);
+                    if let Some(row) = row {
+                        print_csv_row(&mut printer, row, None, error_colors, error_code);
+                    }
+                }
+                if stop_after_failure(&cli, &stats) {
+                    break;
+                }
+            }
+        }
+    }
+    if let Some(results) = buffer {
+        print_buffered(
+            &mut printer,
+            results,
+            cli.sort,
+            cli.group_by,
+            error_colors,
+            error_code,
+        );
+    }
+    if let Some(tally) = tally {
+        if let Err(e) = tally.write(&mut printer.out) {
+            print_error(e, "output", error_colors);
+            exit(error_code)
+        }
+    }
+    if counting {
+        let counts = if cli.count_even {
+            stats.even().to_string()
+        } else if cli.count_odd {
+            stats.odd().to_string()
+        } else {
+            format!(
+                "even: {}\nodd: {}\nerrors: {}",
+                stats.even(),
+                stats.odd(),
+                stats.errors()
+            )
+        };
+        if let Err(e) = writeln!(printer.out, "{}", counts) {
+            print_error(e, "output", error_colors);
+            exit(error_code)
+        }
+    }
+    if let Err(e) = printer.finish() {
+        print_error(e, "output", error_colors);
+        any_failed = true;
+    }
+    if let (Some(path), Some(cache)) = (&persist, &cache) {
+        if let Err(e) = cache::save(path, cache) {
+            print_error(e, path.display(), error_colors);
+        }
+    }
+    if let Some(path) = &cli.summary_json {
+        let summary = stats.summary(
+            total.unwrap_or(read),
+            interrupt.is_set(),
+            cache.as_ref().map(ResponseCache::len),
+        );
+        if let Err(e) = summary.write(path) {
+            print_error(e, path.display(), error_colors);
+            any_failed = true;
+        }
+    }
+    if interrupt.is_set() {
+        match total {
+            Some(total) => eprintln!("Checked {} of {} numbers", stats.total(), total),
+            None => eprintln!("Checked {} numbers", stats.total()),
+        }
+        eprintln!("{}", stats);
+        exit(interrupt::EXIT_CODE)
+    }
+    if cli.stats {
+        eprintln!("{}", stats);
+    } else if stats.duplicates() > 0 {
+        let saved = stats.duplicates();
+        let plural = if saved == 1 { "" } else { "s" };
+        eprintln!("Saved {} request{} by skipping duplicates", saved, plural);
+    }
+    if any_failed {
+        exit(codes.failure(cli.exit_code, rate_limited))
+    }
+    if stats.odd() > 0 {
+        exit(codes.odd(cli.exit_code))
     }
 }
@@ -0,0 +1,163 @@
+//! Details of the request which produced a response.
+
+use std::{
+    cmp::Ordering,
+    hash::{Hash, Hasher},
+    time::Duration,
+};
+
+#[cfg(any(feature = "client", feature = "blocking"))]
+use http::HeaderMap;
+
+/// Header carrying the ID of a request, sent with every request made by the clients.
+#[cfg(any(feature = "client", feature = "blocking"))]
+pub(crate) const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Details of the request which produced a response, for correlating it with logs and support requests.
+///
+/// Metadata describes how a response was obtained rather than what it says, so it is ignored when comparing and
+/// hashing responses, and is not serialized.
+#[derive(Debug, Clone, Default)]
+pub struct ResponseMetadata {
+    request_id: Option<String>,
+    cache_hint: Option<Duration>,
+    from_cache: bool,
+}
+
+impl ResponseMetadata {
+    /// Returns the ID sent in the `X-Request-Id` header of the request, which stays the same when the request is
+    /// retried. Responses served from a [`ResponseCache`](crate::ResponseCache) have the ID of the request which
+    /// originally fetched them.
+    ///
+    /// Returns `None` for responses which were not fetched by a client, e.g. ones returned by
+    /// [`sansio::parse_response`](crate::sansio::parse_response).
+    pub fn request_id(&self) -> Option<&str> {
+        self.request_id.as_deref()
+    }
+
+    /// Returns how long the response may be cached for, according to its `Cache-Control` or `Expires` header, for
+    /// callers which cache responses themselves. See [`sansio::cache_hint`](crate::sansio::cache_hint) for details.
+    ///
+    /// This is the freshness lifetime remaining when the response was received, and does not decrease while the
+    /// response is kept, so responses served from a [`ResponseCache`](crate::ResponseCache) have the hint they
+    /// originally arrived with.
+    pub fn cache_hint(&self) -> Option<Duration> {
+        self.cache_hint
+    }
+
+    /// Returns `true` if the response was served from a [`ResponseCache`](crate::ResponseCache), including stale
+    /// responses served while they are refreshed, rather than received from the API for this request.
+    pub fn from_cache(&self) -> bool {
+        self.from_cache
+    }
+
+    /// Returns the metadata of a response to the request with ID `request_id`, which had `headers`.
+    #[cfg(any(feature = "client", feature = "blocking"))]
+    pub(crate) fn new(request_id: &str, headers: &HeaderMap) -> Self {
+        Self {
+            request_id: Some(request_id.to_string()),
+            cache_hint: crate::sansio::cache_hint(headers),
+            from_cache: false,
+        }
+    }
+
+    /// Returns the metadata marked as that of a response served from a cache.
+    pub(crate) fn cached(self) -> Self {
+        Self {
+            from_cache: true,
+            ..self
+        }
+    }
+}
+
+impl PartialEq for ResponseMetadata {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for ResponseMetadata {}
+
+impl PartialOrd for ResponseMetadata {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ResponseMetadata {
+    fn cmp(&self, _other: &Self) -> Ordering {
+        Ordering::Equal
+    }
+}
+
+impl Hash for ResponseMetadata {
+    fn hash<H: Hasher>(&self, _state: &mut H) {}
+}
+
+/// Returns a new ID for a request, in the form of a random UUID with its randomness drawn from `rng`.
+#[cfg(any(feature = "client", feature = "blocking"))]
+pub(crate) fn new_request_id(rng: &dyn crate::Rng) -> String {
+    let bytes = (u128::from(rng.next_u64()) << 64 | u128::from(rng.next_u64())).to_be_bytes();
+    uuid::Builder::from_random_bytes(bytes)
+        .into_uuid()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{sansio, StatusCode};
+
+    #[test]
+    fn test_metadata_ignored_in_comparisons() {
+        let body = br#"{"ad": "ad", "iseven": true}"#;
+        let parsed = sansio::parse_response(StatusCode::OK, body).unwrap();
+        let mut fetched = parsed.clone();
+        fetched.metadata.request_id = Some("6e0f2b5c".to_string());
+        assert_eq!(parsed.metadata().request_id(), None);
+        assert_eq!(fetched.metadata().request_id(), Some("6e0f2b5c"));
+        assert_eq!(parsed, fetched);
+        assert_eq!(
+            serde_json::to_string(&fetched).unwrap(),
+            r#"{"ad":"ad","iseven":true}"#
+        );
+    }
+
+    #[cfg(feature = "mock-server")]
+    #[tokio::test]
+    async fn test_request_id() {
+        use crate::{mock::MockServer, IsEvenApiClient, Plan};
+
+        let server = MockServer::start("127.0.0.1:0", Plan::Free).await.unwrap();
+        let client = IsEvenApiClient::builder()
+            .base_url(server.url())
+            .build()
+            .unwrap();
+        let first = client.get(42).await.unwrap();
+        let second = client.get(42).await.unwrap();
+        let first_id = first.metadata().request_id().unwrap();
+        assert_eq!(first_id.len(), 36);
+        assert_ne!(Some(first_id), second.metadata().request_id());
+        let error = client.get(-1).await.unwrap_err();
+        assert!(error.request_id().is_some());
+
+        let seeded = || {
+            IsEvenApiClient::builder()
+                .base_url(server.url())
+                .rng(crate::SeededRng::new(7))
+                .build()
+                .unwrap()
+        };
+        let id = seeded()
+            .get(42)
+            .await
+            .unwrap()
+            .metadata()
+            .request_id()
+            .map(str::to_string);
+        assert_eq!(
+            id.as_deref(),
+            seeded().get(42).await.unwrap().metadata().request_id()
+        );
+        assert_eq!(id.unwrap().as_bytes()[14], b'4');
+    }
+}
@@ -0,0 +1,158 @@
+//! A mock isEven API server for developing and testing without internet access.
+
+use std::{io, net::SocketAddr};
+
+use axum::{extract::Path, extract::State, http::StatusCode, routing::get, Json, Router};
+use reqwest::Url;
+use tokio::{
+    net::{TcpListener, ToSocketAddrs},
+    task::JoinHandle,
+};
+
+use crate::{
+    test_util::AdGenerator, ApiNumber, IsEvenApiErrorResponse, IsEvenApiResponse, Plan,
+    ResponseMetadata,
+};
+
+/// A local server answering requests in the same format as isEven API, with parity computed locally and fake ads from
+/// an [`AdGenerator`].
+///
+/// The server runs on the current tokio runtime until the `MockServer` is dropped.
+///
+/// # Examples
+///
+/// ```
+/// # use std::error::Error;
+/// use iseven_api::{mock::MockServer, IsEvenApiClient, Plan};
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn Error>> {
+/// let server = MockServer::start("127.0.0.1:0", Plan::Free).await?;
+/// let client = IsEvenApiClient::builder().base_url(server.url()).build()?;
+/// assert!(client.get(42).await?.iseven());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct MockServer {
+    addr: SocketAddr,
+    handle: JoinHandle<()>,
+}
+
+impl MockServer {
+    /// Starts a server listening on `addr`, accepting numbers in the range of `plan`. Use port 0 to pick a free port.
+    pub async fn start<A: ToSocketAddrs>(addr: A, plan: Plan) -> io::Result<Self> {
+        Self::start_with_ads(addr, plan, AdGenerator::default()).await
+    }
+
+    /// Starts a server like [`Self::start`], whose ads are generated by `ads`.
+    pub async fn start_with_ads<A: ToSocketAddrs>(
+        addr: A,
+        plan: Plan,
+        ads: AdGenerator,
+    ) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr).await?;
+        let addr = listener.local_addr()?;
+        let app = Router::new()
+            .route("/api/iseven/:number", get(check))
+            .with_state((plan, ads));
+        let handle = tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, app).await {
+                log::error!("Mock server failed: {}", e);
+            }
+        });
+        Ok(Self { addr, handle })
+    }
+
+    /// Returns the address the server is listening on.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Returns the base URL of the API, for use with [`IsEvenApiClientBuilder::base_url`](crate::IsEvenApiClientBuilder::base_url).
+    pub fn url(&self) -> Url {
+        Url::parse(&format!("http://{}/api/iseven/", self.addr)).expect("valid URL")
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+/// Computes the response isEven API would give for `number` with the given plan.
+///
+/// Returns the HTTP status code of errors along with the error response.
+pub fn respond(
+    number: &str,
+    plan: Plan,
+) -> Result<IsEvenApiResponse, (StatusCode, IsEvenApiErrorResponse)> {
+    respond_with_ads(number, plan, &AdGenerator::default())
+}
+
+/// Computes the response isEven API would give for `number` like [`respond`], with an ad generated by `ads`.
+pub fn respond_with_ads(
+    number: &str,
+    plan: Plan,
+    ads: &AdGenerator,
+) -> Result<IsEvenApiResponse, (StatusCode, IsEvenApiErrorResponse)> {
+    let digits = number.strip_prefix('-').unwrap_or(number);
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            IsEvenApiErrorResponse {
+                error: "Invalid number.".to_string(),
+                metadata: ResponseMetadata::default(),
+            },
+        ));
+    }
+    match number.parse::<i64>() {
+        Ok(n) if plan.range().contains(&n) => Ok(ads.response(ApiNumber::from(n))),
+        _ => Err((
+            StatusCode::UNAUTHORIZED,
+            IsEvenApiErrorResponse {
+                error: "Number out of range. Upgrade to isEven API Premium or Enterprise."
+                    .to_string(),
+                metadata: ResponseMetadata::default(),
+            },
+        )),
+    }
+}
+
+async fn check(
+    State((plan, ads)): State<(Plan, AdGenerator)>,
+    Path(number): Path<String>,
+) -> Result<Json<IsEvenApiResponse>, (StatusCode, Json<IsEvenApiErrorResponse>)> {
+    respond_with_ads(&number, plan, &ads)
+        .map(Json)
+        .map_err(|(status, e)| (status, Json(e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_respond() {
+        assert!(respond("42", Plan::Free).unwrap().iseven());
+        assert!(respond("7", Plan::Free).unwrap().isodd());
+        assert_eq!(
+            respond("-1", Plan::Free).unwrap_err().0,
+            StatusCode::UNAUTHORIZED
+        );
+        assert!(respond("-1", Plan::Enterprise).unwrap().isodd());
+        assert_eq!(
+            respond("99999999999999999999999", Plan::Enterprise)
+                .unwrap_err()
+                .0,
+            StatusCode::UNAUTHORIZED
+        );
+        for invalid in ["abc", "1.0", "", "-", "3.14"] {
+            assert_eq!(
+                respond(invalid, Plan::Free).unwrap_err().0,
+                StatusCode::BAD_REQUEST
+            );
+        }
+    }
+}
@@ -0,0 +1,184 @@
+//! Numbers validated before they are sent to the API.
+
+use std::{
+    cmp::Ordering,
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    input::{self, ParseError},
+    Plan,
+};
+
+/// A number in the canonical form sent to the API, validated when it is created.
+///
+/// Parsing a number once into an `ApiNumber`, rather than passing strings around, means it cannot be invalid by the
+/// time it is checked. It is accepted by all client methods, which send it as is.
+///
+/// Numbers are ordered by value, and are serialized as strings, since the API accepts numbers of any length.
+///
+/// # Examples
+///
+/// ```
+/// use iseven_api::{ApiNumber, Plan};
+///
+/// let number = ApiNumber::try_from(" +1_000_000 ")?;
+/// assert_eq!(number.as_str(), "1000000");
+/// assert!(!number.is_in_range(Plan::Free));
+/// assert_eq!(number.cheapest_plan(), Some(Plan::Premium));
+/// assert!(ApiNumber::from(-7) < ApiNumber::from(2u64));
+/// # Ok::<(), iseven_api::input::ParseError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ApiNumber(String);
+
+impl ApiNumber {
+    /// Returns the number in canonical form, as described in [`input::normalize`].
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns `true` if the number is less than zero.
+    pub fn is_negative(&self) -> bool {
+        self.0.starts_with('-')
+    }
+
+    /// Returns the number as an `i64`, or `None` if it is too large.
+    pub fn to_i64(&self) -> Option<i64> {
+        self.0.parse().ok()
+    }
+
+    /// Returns `true` if the number can be checked with `plan`, according to [`Plan::range`].
+    pub fn is_in_range(&self, plan: Plan) -> bool {
+        self.to_i64().is_some_and(|n| plan.range().contains(&n))
+    }
+
+    /// Returns the cheapest plan which can check the number, or `None` if no plan can.
+    pub fn cheapest_plan(&self) -> Option<Plan> {
+        Plan::ALL.into_iter().find(|&plan| self.is_in_range(plan))
+    }
+
+    /// Returns the digits of the number, without the sign.
+    fn digits(&self) -> &str {
+        self.0.trim_start_matches('-')
+    }
+}
+
+impl Display for ApiNumber {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl AsRef<str> for ApiNumber {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<ApiNumber> for String {
+    fn from(number: ApiNumber) -> Self {
+        number.0
+    }
+}
+
+impl FromStr for ApiNumber {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        input::parse(s).map(|number| ApiNumber(number.into_owned()))
+    }
+}
+
+impl TryFrom<&str> for ApiNumber {
+    type Error = ParseError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl TryFrom<String> for ApiNumber {
+    type Error = ParseError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+macro_rules! impl_from_int {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for ApiNumber {
+                fn from(n: $t) -> Self {
+                    ApiNumber(n.to_string())
+                }
+            }
+        )*
+    };
+}
+
+impl_from_int!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+impl PartialOrd for ApiNumber {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Orders numbers by value. Canonical numbers have no leading zeros, so longer digits mean a larger magnitude.
+impl Ord for ApiNumber {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let magnitude = self
+            .digits()
+            .len()
+            .cmp(&other.digits().len())
+            .then_with(|| self.digits().cmp(other.digits()));
+        match (self.is_negative(), other.is_negative()) {
+            (false, false) => magnitude,
+            (true, true) => magnitude.reverse(),
+            (true, false) => Ordering::Less,
+            (false, true) => Ordering::Greater,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_api_number() {
+        assert_eq!(ApiNumber::try_from("-042").unwrap().as_str(), "-42");
+        assert_eq!(ApiNumber::from(u128::MAX).as_str(), u128::MAX.to_string());
+        assert_eq!(
+            ApiNumber::try_from("4.2"),
+            Err(ParseError::InvalidCharacter {
+                character: '.',
+                position: 2
+            })
+        );
+        assert_eq!(ApiNumber::from(-1).cheapest_plan(), Some(Plan::Enterprise));
+        assert_eq!(ApiNumber::from(u64::MAX).cheapest_plan(), None);
+
+        let mut numbers: Vec<ApiNumber> = ["10", "-3", "99999999999999999999", "0", "-12", "9"]
+            .map(|n| n.parse().unwrap())
+            .into();
+        numbers.sort();
+        let sorted: Vec<_> = numbers.iter().map(ApiNumber::as_str).collect();
+        assert_eq!(
+            sorted,
+            ["-12", "-3", "0", "9", "10", "99999999999999999999"]
+        );
+
+        assert_eq!(
+            serde_json::to_string(&ApiNumber::from(42)).unwrap(),
+            r#""42""#
+        );
+        assert!(serde_json::from_str::<ApiNumber>(r#""abc""#).is_err());
+    }
+}
@@ -0,0 +1,75 @@
+//! isEven API pricing plans.
+
+use std::{
+    fmt::{Display, Formatter},
+    ops::RangeInclusive,
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+
+/// An isEven API [pricing plan](https://isevenapi.xyz/#pricing), which determines the range of numbers that can be
+/// checked.
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Plan {
+    /// Free plan, supporting numbers from 0 to 999,999
+    #[default]
+    Free,
+    /// Premium plan, supporting numbers from 0 to 999,999,999
+    Premium,
+    /// Enterprise plan, supporting numbers from -999,999,999 to 999,999,999
+    Enterprise,
+}
+
+impl Plan {
+    /// All plans, from cheapest to most expensive.
+    pub const ALL: [Plan; 3] = [Plan::Free, Plan::Premium, Plan::Enterprise];
+
+    /// Returns the lowercase name of the plan.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Plan::Free => "free",
+            Plan::Premium => "premium",
+            Plan::Enterprise => "enterprise",
+        }
+    }
+
+    /// Returns the range of numbers which can be checked with this plan.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iseven_api::Plan;
+    ///
+    /// assert!(Plan::Free.range().contains(&42));
+    /// assert!(!Plan::Free.range().contains(&1_000_000));
+    /// assert!(Plan::Premium.range().contains(&1_000_000));
+    /// ```
+    pub fn range(&self) -> RangeInclusive<i64> {
+        match self {
+            Plan::Free => 0..=999_999,
+            Plan::Premium => 0..=999_999_999,
+            Plan::Enterprise => -999_999_999..=999_999_999,
+        }
+    }
+}
+
+impl Display for Plan {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Plan {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Plan::ALL
+            .into_iter()
+            .find(|plan| plan.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown plan `{}`", s))
+    }
+}
@@ -0,0 +1,107 @@
+//! Client-side rate limiting.
+
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::{Clock, SystemClock};
+
+/// Spaces out requests so that no more than a given number are sent per time period.
+///
+/// Cloning a `RateLimiter` gives another handle to the same limiter, so a single limiter can be shared between several
+/// clients to keep them all within one quota.
+///
+/// # Examples
+///
+#[cfg_attr(feature = "client", doc = "```")]
+#[cfg_attr(not(feature = "client"), doc = "```ignore")]
+/// # use std::error::Error;
+/// use std::time::Duration;
+/// use iseven_api::{IsEvenApiClient, RateLimiter};
+///
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// // At most 5 requests per second
+/// let limiter = RateLimiter::new(5, Duration::from_secs(1));
+/// let client = IsEvenApiClient::builder().rate_limiter(limiter).build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    interval: Duration,
+    next: Arc<Mutex<Option<Instant>>>,
+    clock: Arc<dyn Clock>,
+}
+
+impl RateLimiter {
+    /// Creates a limiter allowing `requests` requests every `per`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `requests` is 0.
+    pub fn new(requests: u32, per: Duration) -> Self {
+        assert!(requests > 0, "requests must be greater than 0");
+        Self {
+            interval: per / requests,
+            next: Arc::new(Mutex::new(None)),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Reads the time from `clock` instead of the [`SystemClock`].
+    pub fn with_clock<C: Clock + 'static>(self, clock: C) -> Self {
+        Self {
+            clock: Arc::new(clock),
+            ..self
+        }
+    }
+
+    /// Returns the minimum time between two requests.
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Reserves a slot for a request, returning how long to wait before sending it.
+    pub fn reserve(&self) -> Duration {
+        let now = self.clock.now();
+        let mut next = self.next.lock().unwrap_or_else(|e| e.into_inner());
+        let slot = match *next {
+            Some(next) if next > now => next,
+            _ => now,
+        };
+        *next = Some(slot + self.interval);
+        slot - now
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reserve_spaces_requests() {
+        let limiter = RateLimiter::new(10, Duration::from_secs(1));
+        assert_eq!(limiter.interval(), Duration::from_millis(100));
+        assert_eq!(limiter.reserve(), Duration::ZERO);
+        let second = limiter.reserve();
+        assert!(second > Duration::from_millis(90) && second <= Duration::from_millis(100));
+        let third = limiter.clone().reserve();
+        assert!(third > Duration::from_millis(190) && third <= Duration::from_millis(200));
+    }
+
+    #[cfg(feature = "test-util")]
+    #[test]
+    fn test_reserve_with_clock() {
+        use crate::test_util::MockClock;
+
+        let clock = MockClock::new();
+        let limiter = RateLimiter::new(10, Duration::from_secs(1)).with_clock(clock.clone());
+        assert_eq!(limiter.reserve(), Duration::ZERO);
+        assert_eq!(limiter.reserve(), Duration::from_millis(100));
+        clock.advance(Duration::from_millis(150));
+        assert_eq!(limiter.reserve(), Duration::from_millis(50));
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(limiter.reserve(), Duration::ZERO);
+    }
+}
@@ -0,0 +1,167 @@
+//! Per-request options, set with the builder returned by `request` on the clients.
+
+#[cfg(feature = "blocking")]
+use crate::IsEvenApiBlockingClient;
+#[cfg(feature = "client")]
+use crate::IsEvenApiClient;
+use crate::{IsEvenApiError, IsEvenApiResponse};
+
+/// How a request uses the client's [`ResponseCache`](crate::ResponseCache).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum CacheMode {
+    /// Returns cached responses, and caches new ones
+    #[default]
+    Use,
+    /// Always makes a request, and caches its response
+    NoCache,
+    /// Always makes a request, asking caches on the way not to answer it either, and caches its response
+    Refresh,
+}
+
+/// Builder for a single request, returned by [`IsEvenApiClient::request`] and [`IsEvenApiBlockingClient::request`].
+///
+/// # Examples
+///
+#[cfg_attr(feature = "client", doc = "```no_run")]
+#[cfg_attr(not(feature = "client"), doc = "```ignore")]
+/// # use std::error::Error;
+/// use iseven_api::{IsEvenApiClient, ResponseCache};
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn Error>> {
+/// let client = IsEvenApiClient::builder().cache(ResponseCache::new(100)).build()?;
+/// // skips the cached response, if any, and caches the new one
+/// let response = client.request(42).no_cache().send().await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+#[must_use = "requests do nothing unless sent"]
+pub struct GetRequest<'a, C> {
+    client: &'a C,
+    number: String,
+    cache_mode: CacheMode,
+}
+
+impl<'a, C> GetRequest<'a, C> {
+    pub(crate) fn new(client: &'a C, number: String) -> Self {
+        Self {
+            client,
+            number,
+            cache_mode: CacheMode::Use,
+        }
+    }
+
+    /// Makes the request even if a response is cached. The new response is still cached, and replaces the cached
+    /// one.
+    pub fn no_cache(self) -> Self {
+        Self {
+            cache_mode: CacheMode::NoCache,
+            ..self
+        }
+    }
+
+    /// Like [`Self::no_cache`], but also sends `Cache-Control: no-cache`, so that caches between the client and the
+    /// API, such as an `iseven_api serve` proxy, make the request too instead of answering it. The cached response is
+    /// only replaced if the request succeeds.
+    pub fn refresh(self) -> Self {
+        Self {
+            cache_mode: CacheMode::Refresh,
+            ..self
+        }
+    }
+}
+
+#[cfg(feature = "client")]
+impl GetRequest<'_, IsEvenApiClient> {
+    /// Sends the request. See [`IsEvenApiClient::get`] for the possible errors.
+    pub async fn send(self) -> Result<IsEvenApiResponse, IsEvenApiError> {
+        self.client.get_with(self.number, self.cache_mode).await
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl GetRequest<'_, IsEvenApiBlockingClient> {
+    /// Sends the request. See [`IsEvenApiClient::get`] for the possible errors.
+    pub fn send(self) -> Result<IsEvenApiResponse, IsEvenApiError> {
+        self.client.get_with(self.number, self.cache_mode)
+    }
+}
+
+#[cfg(all(test, feature = "mock-server"))]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use crate::{
+        layer::{IsEvenLayer, Request},
+        mock::MockServer,
+        IsEvenApiClient, Plan, ResponseCache, Url,
+    };
+
+    #[tokio::test]
+    async fn test_cache_bypass() {
+        let server = MockServer::start("127.0.0.1:0", Plan::Free).await.unwrap();
+        let cache = ResponseCache::new(10);
+        let client = IsEvenApiClient::builder()
+            .base_url(server.url())
+            .cache(cache.clone())
+            .build()
+            .unwrap();
+        let request_id = |response: crate::IsEvenApiResponse| {
+            response.metadata().request_id().unwrap().to_string()
+        };
+
+        let first = client.get(42).await.unwrap();
+        assert!(!first.metadata().from_cache());
+        let first = request_id(first);
+        let cached = client.get(42).await.unwrap();
+        assert!(cached.metadata().from_cache());
+        assert_eq!(request_id(cached), first);
+        let fresh = request_id(client.request(42).no_cache().send().await.unwrap());
+        assert_ne!(fresh, first);
+        assert_eq!(request_id(client.get(42).await.unwrap()), fresh);
+
+        // a server which is not running, sharing the cache
+        let unreachable = IsEvenApiClient::builder()
+            .base_url(Url::parse("http://127.0.0.1:1/").unwrap())
+            .cache(cache.clone())
+            .build()
+            .unwrap();
+        assert!(unreachable.request(42).no_cache().send().await.is_err());
+        assert!(cache.get("42").is_some());
+        // the cached response is kept when refreshing it fails
+        assert!(unreachable.request(42).refresh().send().await.is_err());
+        assert!(cache.get("42").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_refresh_header() {
+        #[derive(Default)]
+        struct CacheControl(Mutex<Vec<Option<String>>>);
+
+        impl IsEvenLayer for Arc<CacheControl> {
+            fn on_request(&self, request: &mut Request) {
+                let value = request.headers().get("cache-control");
+                self.0
+                    .lock()
+                    .unwrap()
+                    .push(value.map(|v| v.to_str().unwrap().to_string()));
+            }
+        }
+
+        let server = MockServer::start("127.0.0.1:0", Plan::Free).await.unwrap();
+        let sent = Arc::new(CacheControl::default());
+        let client = IsEvenApiClient::builder()
+            .base_url(server.url())
+            .cache(ResponseCache::new(10))
+            .layer(sent.clone())
+            .build()
+            .unwrap();
+        client.request(42).no_cache().send().await.unwrap();
+        client.request(42).refresh().send().await.unwrap();
+        assert_eq!(
+            *sent.0.lock().unwrap(),
+            [None, Some("no-cache".to_string())]
+        );
+    }
+}
@@ -0,0 +1,343 @@
+//! Retrying of failed requests.
+
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::{Clock, IsEvenApiError, Rng, SystemClock};
+
+/// Policy controlling how failed requests are retried.
+///
+/// Only transient failures are retried (see [`IsEvenApiError::is_transient`]); errors such as an invalid number are
+/// returned immediately. The default policy does not retry at all.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use iseven_api::RetryPolicy;
+///
+/// // Retry up to 3 times, waiting 100ms, 200ms, then 400ms between attempts
+/// let policy = RetryPolicy::new(3)
+///     .with_delay(Duration::from_millis(100))
+///     .with_backoff(2.0);
+/// assert_eq!(policy.delay_for(2), Duration::from_millis(400));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    max_retries: u32,
+    delay: Duration,
+    backoff: f64,
+    jitter: f64,
+}
+
+impl RetryPolicy {
+    /// Default delay before the first retry.
+    pub const DEFAULT_DELAY: Duration = Duration::from_millis(500);
+    /// Default factor by which the delay is multiplied after each retry.
+    pub const DEFAULT_BACKOFF: f64 = 2.0;
+
+    /// Creates a policy retrying up to `max_retries` times with the default delay and backoff.
+    pub fn new(max_retries: u32) -> Self {
+        Self {
+            max_retries,
+            delay: Self::DEFAULT_DELAY,
+            backoff: Self::DEFAULT_BACKOFF,
+            jitter: 0.0,
+        }
+    }
+
+    /// Creates a policy which never retries.
+    pub fn none() -> Self {
+        Self::new(0)
+    }
+
+    /// Sets the delay before the first retry.
+    pub fn with_delay(self, delay: Duration) -> Self {
+        Self { delay, ..self }
+    }
+
+    /// Sets the factor by which the delay is multiplied after each retry. A factor of `1.0` gives a constant delay.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `backoff` is negative or not finite.
+    pub fn with_backoff(self, backoff: f64) -> Self {
+        assert!(
+            backoff.is_finite() && backoff >= 0.0,
+            "backoff must be a finite, non-negative number"
+        );
+        Self { backoff, ..self }
+    }
+
+    /// Randomly shortens each delay by up to `jitter` times its length, e.g. by up to half with `0.5`, so that clients
+    /// which failed at the same time do not all retry at the same time. No jitter is applied by default.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `jitter` is not between 0 and 1.
+    pub fn with_jitter(self, jitter: f64) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&jitter),
+            "jitter must be between 0 and 1"
+        );
+        Self { jitter, ..self }
+    }
+
+    /// Returns the maximum number of retries.
+    pub fn max_retries(&self) -> u32 {
+        self.max_retries
+    }
+
+    /// Returns the delay before retry number `retry` (starting from 0).
+    pub fn delay_for(&self, retry: u32) -> Duration {
+        let factor = self.backoff.powi(retry.min(i32::MAX as u32) as i32);
+        Duration::try_from_secs_f64(self.delay.as_secs_f64() * factor).unwrap_or(Duration::MAX)
+    }
+
+    /// Returns the delay before retry number `retry` like [`Self::delay_for`], shortened by a random amount drawn from
+    /// `rng` if the policy has [jitter](Self::with_jitter).
+    pub fn jittered_delay_for(&self, retry: u32, rng: &dyn Rng) -> Duration {
+        let delay = self.delay_for(retry);
+        if self.jitter == 0.0 {
+            return delay;
+        }
+        let factor = 1.0 - self.jitter * rng.next_f64();
+        Duration::try_from_secs_f64(delay.as_secs_f64() * factor).unwrap_or(delay)
+    }
+
+    /// Returns `true` if a request which failed with `error` after `retry` previous retries should be retried.
+    pub fn should_retry(&self, retry: u32, error: &IsEvenApiError) -> bool {
+        retry < self.max_retries && error.is_transient()
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+/// Limits how many retries are made over time, however many requests fail.
+///
+/// A [`RetryPolicy`] limits the retries of each request, but when the API is down every request fails, and many tasks
+/// sharing a client would all retry at once, multiplying the load on the API while it recovers. A budget is a token
+/// bucket which every retry takes a token from. It holds up to a given number of tokens and refills at a steady rate,
+/// allowing bursts of retries while spreading them out during longer outages. Once it is empty, failed requests return
+/// their error without retrying until tokens are refilled.
+///
+/// Clones of a client share its budget, and cloning a `RetryBudget` gives another handle to the same bucket, so a
+/// budget can also be shared between clients.
+///
+/// # Examples
+///
+#[cfg_attr(feature = "client", doc = "```")]
+#[cfg_attr(not(feature = "client"), doc = "```ignore")]
+/// # use std::error::Error;
+/// use std::time::Duration;
+/// use iseven_api::{IsEvenApiClient, RetryBudget, RetryPolicy};
+///
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// // Each request may be retried 3 times, but all requests together at most 10 times per second
+/// let client = IsEvenApiClient::builder()
+///     .retry_policy(RetryPolicy::new(3))
+///     .retry_budget(RetryBudget::new(10, Duration::from_secs(1)))
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct RetryBudget {
+    capacity: u32,
+    refill: Duration,
+    bucket: Arc<Mutex<Bucket>>,
+    clock: Arc<dyn Clock>,
+}
+
+/// Tokens in a [`RetryBudget`], as of the last time they were counted.
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    counted: Option<Instant>,
+}
+
+impl RetryBudget {
+    /// Creates a full budget allowing `retries` retries every `per`, and up to `retries` in a burst.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `retries` is 0.
+    pub fn new(retries: u32, per: Duration) -> Self {
+        assert!(retries > 0, "retries must be greater than 0");
+        Self {
+            capacity: retries,
+            refill: per / retries,
+            bucket: Arc::new(Mutex::new(Bucket {
+                tokens: f64::from(retries),
+                counted: None,
+            })),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Reads the time from `clock` instead of the [`SystemClock`].
+    pub fn with_clock<C: Clock + 'static>(self, clock: C) -> Self {
+        Self {
+            clock: Arc::new(clock),
+            ..self
+        }
+    }
+
+    /// Returns the largest number of tokens the budget holds.
+    pub fn capacity(&self) -> u32 {
+        self.capacity
+    }
+
+    /// Returns the time it takes to refill one token.
+    pub fn refill(&self) -> Duration {
+        self.refill
+    }
+
+    /// Returns the number of whole tokens left, i.e. how many retries can be made right now.
+    pub fn available(&self) -> u32 {
+        self.count(&mut self.bucket.lock().unwrap_or_else(|e| e.into_inner())) as u32
+    }
+
+    /// Takes a token for a retry, returning `false` without taking one if the budget is empty.
+    pub fn try_acquire(&self) -> bool {
+        let mut bucket = self.bucket.lock().unwrap_or_else(|e| e.into_inner());
+        if self.count(&mut bucket) < 1.0 {
+            return false;
+        }
+        bucket.tokens -= 1.0;
+        true
+    }
+
+    /// Adds the tokens refilled since `bucket` was last counted, and returns how many it holds.
+    fn count(&self, bucket: &mut Bucket) -> f64 {
+        let now = self.clock.now();
+        if let Some(counted) = bucket.counted {
+            let refilled =
+                now.saturating_duration_since(counted).as_secs_f64() / self.refill.as_secs_f64();
+            bucket.tokens = (bucket.tokens + refilled).min(f64::from(self.capacity));
+        }
+        bucket.counted = Some(now);
+        bucket.tokens
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SeededRng;
+
+    #[test]
+    fn test_delay_for() {
+        let policy = RetryPolicy::new(5)
+            .with_delay(Duration::from_millis(100))
+            .with_backoff(3.0);
+        assert_eq!(policy.delay_for(0), Duration::from_millis(100));
+        assert_eq!(policy.delay_for(1), Duration::from_millis(300));
+        assert_eq!(policy.delay_for(2), Duration::from_millis(900));
+        assert_eq!(
+            policy.with_backoff(1.0).delay_for(4),
+            Duration::from_millis(100)
+        );
+        assert_eq!(policy.delay_for(u32::MAX), Duration::MAX);
+    }
+
+    #[test]
+    fn test_jittered_delay_for() {
+        let policy = RetryPolicy::new(5).with_delay(Duration::from_secs(1));
+        let rng = SeededRng::new(0);
+        assert_eq!(policy.jittered_delay_for(1, &rng), Duration::from_secs(2));
+
+        let policy = policy.with_jitter(0.5);
+        let delays: Vec<_> = (0..100)
+            .map(|_| policy.jittered_delay_for(1, &rng))
+            .collect();
+        assert!(delays
+            .iter()
+            .all(|&d| d > Duration::from_secs(1) && d <= Duration::from_secs(2)));
+        assert!(delays.iter().any(|&d| d < Duration::from_millis(1500)));
+        let again = SeededRng::new(0);
+        let repeated: Vec<_> = (0..100)
+            .map(|_| policy.jittered_delay_for(1, &again))
+            .collect();
+        assert_eq!(delays, repeated);
+        assert!(policy.jittered_delay_for(u32::MAX, &rng) > Duration::from_secs(1));
+    }
+
+    #[cfg(feature = "test-harness")]
+    #[tokio::test]
+    async fn test_retry_waits_on_clock() {
+        use crate::{
+            harness::{Cassette, CassetteServer, Scenario},
+            test_util::MockClock,
+            IsEvenApiClient,
+        };
+
+        let server = CassetteServer::start(Cassette::scenario(Scenario::ServerError))
+            .await
+            .unwrap();
+        let clock = MockClock::new();
+        let client = IsEvenApiClient::builder()
+            .base_url(server.url())
+            .retry_policy(RetryPolicy::new(2).with_delay(Duration::from_secs(10)))
+            .clock(clock.clone())
+            .build()
+            .unwrap();
+        assert!(client.get(42).await.unwrap().iseven());
+        assert_eq!(clock.elapsed(), Duration::from_secs(30));
+    }
+
+    #[cfg(feature = "test-util")]
+    #[test]
+    fn test_retry_budget() {
+        use crate::test_util::MockClock;
+
+        let clock = MockClock::new();
+        let budget = RetryBudget::new(2, Duration::from_secs(1)).with_clock(clock.clone());
+        assert_eq!(budget.refill(), Duration::from_millis(500));
+        assert!(budget.try_acquire());
+        assert!(budget.clone().try_acquire());
+        assert!(!budget.try_acquire());
+        clock.advance(Duration::from_millis(400));
+        assert!(!budget.try_acquire());
+        clock.advance(Duration::from_millis(100));
+        assert!(budget.try_acquire());
+        assert_eq!(budget.available(), 0);
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(budget.available(), budget.capacity());
+    }
+
+    #[cfg(feature = "test-harness")]
+    #[tokio::test]
+    async fn test_retry_budget_shared_by_clones() {
+        use crate::{
+            harness::{Cassette, CassetteServer, Scenario},
+            IsEvenApiClient, ProtocolError,
+        };
+
+        let server = CassetteServer::start(Cassette::scenario(Scenario::ServerError))
+            .await
+            .unwrap();
+        let budget = RetryBudget::new(1, Duration::from_secs(60));
+        let client = IsEvenApiClient::builder()
+            .base_url(server.url())
+            .retry_policy(RetryPolicy::new(2).with_delay(Duration::ZERO))
+            .retry_budget(budget.clone())
+            .build()
+            .unwrap();
+        // the budget allows one retry, so the second failure is returned although the policy allows another retry
+        let error = client.clone().get(42).await.unwrap_err();
+        assert!(matches!(
+            error,
+            IsEvenApiError::Protocol(ProtocolError::UnexpectedStatus(..))
+        ));
+        assert_eq!(server.requests().len(), 2);
+        assert_eq!(budget.available(), 0);
+        assert!(client.get(42).await.unwrap().iseven());
+    }
+}
@@ -0,0 +1,63 @@
+//! Sources of randomness, so that randomized behavior can be reproduced.
+
+use std::{
+    fmt::Debug,
+    sync::{Arc, Mutex},
+};
+
+/// A source of random numbers for the randomized behavior of the clients: [jitter](crate::RetryPolicy::with_jitter)
+/// in retry delays and the IDs of requests.
+///
+/// Clients use [`ThreadRng`] unless another source is set with
+/// [`IsEvenApiClientBuilder::rng`](crate::IsEvenApiClientBuilder::rng). A [`SeededRng`] makes the behavior the same on
+/// every run, e.g. for tests.
+pub trait Rng: Debug + Send + Sync {
+    /// Returns a uniformly distributed random number.
+    fn next_u64(&self) -> u64;
+
+    /// Returns a uniformly distributed random number in the range `0.0..1.0`.
+    fn next_f64(&self) -> f64 {
+        // the top 53 bits, which an f64 can represent exactly
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// A fast, non-cryptographic generator local to each thread, seeded from the operating system.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ThreadRng;
+
+impl Rng for ThreadRng {
+    fn next_u64(&self) -> u64 {
+        fastrand::u64(..)
+    }
+}
+
+/// A generator which gives the same numbers on every run for the same seed. Clones share the same generator.
+///
+/// # Examples
+///
+/// ```
+/// use iseven_api::{Rng, SeededRng};
+///
+/// let rng = SeededRng::new(42);
+/// assert_eq!(rng.next_u64(), SeededRng::new(42).next_u64());
+/// ```
+#[derive(Debug, Clone)]
+pub struct SeededRng {
+    rng: Arc<Mutex<fastrand::Rng>>,
+}
+
+impl SeededRng {
+    /// Creates a generator seeded with `seed`.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: Arc::new(Mutex::new(fastrand::Rng::with_seed(seed))),
+        }
+    }
+}
+
+impl Rng for SeededRng {
+    fn next_u64(&self) -> u64 {
+        self.rng.lock().unwrap_or_else(|e| e.into_inner()).u64(..)
+    }
+}
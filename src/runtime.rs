@@ -0,0 +1,99 @@
+//! Support for awaiting the async client outside of tokio, enabled with the `runtime-agnostic` feature.
+//!
+//! reqwest needs a tokio runtime to drive its connections and timers. When [`IsEvenApiClient`](crate::IsEvenApiClient)
+//! is awaited from another executor, such as async-std or smol, requests are instead run on a small background tokio
+//! runtime shared by all clients. Awaiting the result of a spawned task only needs a waker, so it works on any
+//! executor.
+
+use std::{future::Future, panic, sync::OnceLock};
+
+use tokio::runtime::{Builder, Handle, Runtime};
+
+/// Returns `true` if the current thread is not running a tokio runtime.
+pub(crate) fn outside_tokio() -> bool {
+    Handle::try_current().is_err()
+}
+
+/// Runs `future` on the background runtime and waits for its output.
+pub(crate) async fn spawn<F>(future: F) -> F::Output
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    match background().spawn(future).await {
+        Ok(output) => output,
+        Err(e) => panic::resume_unwind(e.into_panic()),
+    }
+}
+
+fn background() -> &'static Runtime {
+    static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+    RUNTIME.get_or_init(|| {
+        Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("iseven-api-runtime")
+            .enable_all()
+            .build()
+            .expect("Unable to start the background runtime")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        pin::pin,
+        sync::Arc,
+        task::{Context, Poll, Wake, Waker},
+        thread::{self, Thread},
+    };
+
+    use super::*;
+
+    /// Waker unparking the thread of a minimal executor, standing in for a non-tokio runtime.
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        let mut future = pin!(future);
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+
+    #[test]
+    fn test_spawn_outside_tokio() {
+        assert!(outside_tokio());
+        let output = block_on(spawn(async {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            outside_tokio()
+        }));
+        assert!(!output);
+    }
+
+    #[cfg(feature = "mock-server")]
+    #[test]
+    fn test_client_outside_tokio() {
+        use crate::{mock::MockServer, IsEvenApiClient, Plan};
+
+        let tokio = Runtime::new().unwrap();
+        let server = tokio
+            .block_on(MockServer::start("127.0.0.1:0", Plan::Free))
+            .unwrap();
+        let client = IsEvenApiClient::builder()
+            .base_url(server.url())
+            .build()
+            .unwrap();
+        assert!(block_on(client.get(42)).unwrap().iseven());
+        assert!(block_on(client.get_json(7)).unwrap().contains("false"));
+    }
+}
@@ -0,0 +1,224 @@
+//! Building blocks for checking numbers over a transport of your own.
+//!
+//! These functions do no I/O and are available without the `client` feature, so a crate which already has an HTTP
+//! client (or no HTTP at all, e.g. a message queue in front of a proxy) can talk to isEven API without pulling in
+//! reqwest and tokio:
+//!
+//! ```
+//! use iseven_api::{sansio, StatusCode, API_URL};
+//!
+//! let url = sansio::request_url(API_URL, "1_000");
+//! assert_eq!(url, "https://api.isevenapi.xyz/api/iseven/1000");
+//!
+//! // send a GET request to `url` with your client of choice, then
+//! let body = br#"{"ad": "Buy isEvenCoin!", "iseven": true}"#;
+//! let response = sansio::parse_response(StatusCode::OK, body).unwrap();
+//! assert!(response.iseven());
+//! ```
+
+use std::{
+    fmt::Display,
+    time::{Duration, SystemTime},
+};
+
+use http::{
+    header::{AGE, CACHE_CONTROL, DATE, EXPIRES},
+    HeaderMap, StatusCode,
+};
+use serde::Deserialize;
+
+use crate::{
+    input, ApiError, IsEvenApiError, IsEvenApiErrorResponse, IsEvenApiResponse, ProtocolError,
+    ResponseMetadata,
+};
+
+/// Enum of response types for serde
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+pub(crate) enum IsEvenResponseType {
+    Ok(IsEvenApiResponse),
+    Err(IsEvenApiErrorResponse),
+}
+
+/// Returns the URL to request for `number` from the API at `base_url`, with `number` in the canonical form returned
+/// by [`input::normalize`].
+pub fn request_url<T: Display>(base_url: &str, number: T) -> String {
+    let number = number.to_string();
+    let number = input::normalize(&number);
+    if base_url.ends_with('/') {
+        format!("{}{}", base_url, number)
+    } else {
+        format!("{}/{}", base_url, number)
+    }
+}
+
+/// Parses the body of a response from the API, answered with `status`.
+///
+/// # Errors
+///
+/// Returns the same errors for error responses as [`IsEvenApiClient::get`](crate::IsEvenApiClient::get). If `body`
+/// is neither an answer nor an error response, returns [`ProtocolError::Decode`] when `status` is successful, or else
+/// [`ProtocolError::UnexpectedBody`], which keeps the status.
+pub fn parse_response(
+    status: StatusCode,
+    body: &[u8],
+) -> Result<IsEvenApiResponse, IsEvenApiError> {
+    decode(status, body, ResponseMetadata::default())
+}
+
+/// Returns how long a response with `headers` may be cached for, according to its cache-related headers, or `None` if
+/// it has none.
+///
+/// `Cache-Control: no-store` and `no-cache` give a hint of zero. Otherwise, the `max-age` directive is used, falling
+/// back to the time from the `Date` header (or now) until the `Expires` header. Either way, the time the response has
+/// already spent in other caches, given by the `Age` header, is subtracted.
+pub fn cache_hint(headers: &HeaderMap) -> Option<Duration> {
+    let header = |name| headers.get(name).and_then(|value| value.to_str().ok());
+    let age = Duration::from_secs(header(AGE).and_then(|age| age.parse().ok()).unwrap_or(0));
+
+    let mut max_age = None;
+    let directives = headers
+        .get_all(CACHE_CONTROL)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','));
+    for directive in directives {
+        let (name, value) = match directive.split_once('=') {
+            Some((name, value)) => (name.trim(), Some(value.trim().trim_matches('"'))),
+            None => (directive.trim(), None),
+        };
+        if name.eq_ignore_ascii_case("no-store") || name.eq_ignore_ascii_case("no-cache") {
+            return Some(Duration::ZERO);
+        }
+        if name.eq_ignore_ascii_case("max-age") {
+            max_age = value
+                .and_then(|value| value.parse().ok())
+                .map(Duration::from_secs);
+        }
+    }
+    if let Some(max_age) = max_age {
+        return Some(max_age.saturating_sub(age));
+    }
+
+    // an invalid date in Expires means the response has already expired
+    let Ok(expires) = httpdate::parse_http_date(header(EXPIRES)?) else {
+        return Some(Duration::ZERO);
+    };
+    let date = header(DATE)
+        .and_then(|date| httpdate::parse_http_date(date).ok())
+        .unwrap_or_else(SystemTime::now);
+    let lifetime = expires.duration_since(date).unwrap_or_default();
+    Some(lifetime.saturating_sub(age))
+}
+
+/// Decodes the body of a response answered with `status` into the result of a request, attaching `metadata` to it.
+pub(crate) fn decode(
+    status: StatusCode,
+    body: &[u8],
+    metadata: ResponseMetadata,
+) -> Result<IsEvenApiResponse, IsEvenApiError> {
+    match serde_json::from_slice(body) {
+        Ok(json) => classify(json, status, metadata),
+        Err(e) if status.is_success() => {
+            Err(ProtocolError::Decode(e, metadata.request_id().map(str::to_owned)).into())
+        }
+        Err(_) => Err(ProtocolError::UnexpectedBody(
+            String::from_utf8_lossy(body).into_owned(),
+            status,
+            metadata.request_id().map(str::to_owned),
+        )
+        .into()),
+    }
+}
+
+/// Turns a decoded response into the result of a request, attaching `metadata` to it.
+pub(crate) fn classify(
+    json: IsEvenResponseType,
+    status: StatusCode,
+    metadata: ResponseMetadata,
+) -> Result<IsEvenApiResponse, IsEvenApiError> {
+    match json {
+        IsEvenResponseType::Ok(mut r) => {
+            r.metadata = metadata;
+            Ok(r)
+        }
+        IsEvenResponseType::Err(mut e) => {
+            e.metadata = metadata;
+            match ApiError::from_status(status, e) {
+                Ok(e) => Err(IsEvenApiError::Api(e)),
+                Err(e) => Err(ProtocolError::UnexpectedStatus(e, status).into()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_response() {
+        let answer = parse_response(StatusCode::OK, br#"{"ad": "ad", "iseven": false}"#).unwrap();
+        assert!(answer.isodd());
+        assert!(matches!(
+            parse_response(StatusCode::BAD_REQUEST, br#"{"error": "Invalid number."}"#),
+            Err(IsEvenApiError::Api(ApiError::InvalidNumber(_)))
+        ));
+        let error =
+            parse_response(StatusCode::BAD_GATEWAY, b"<html>Bad gateway</html>").unwrap_err();
+        assert!(matches!(
+            error,
+            IsEvenApiError::Protocol(ProtocolError::UnexpectedBody(ref body, StatusCode::BAD_GATEWAY, _))
+                if body == "<html>Bad gateway</html>"
+        ));
+        assert!(error.is_transient());
+        assert!(matches!(
+            parse_response(StatusCode::OK, b"<html>Application error</html>"),
+            Err(IsEvenApiError::Protocol(ProtocolError::Decode(..)))
+        ));
+        assert_eq!(
+            request_url("http://localhost/api", 42),
+            "http://localhost/api/42"
+        );
+    }
+
+    #[test]
+    fn test_cache_hint() {
+        let hint = |pairs: &[(&'static str, &'static str)]| {
+            let mut headers = HeaderMap::new();
+            for &(name, value) in pairs {
+                headers.append(name, value.parse().unwrap());
+            }
+            cache_hint(&headers)
+        };
+        let secs = |secs| Some(Duration::from_secs(secs));
+        assert_eq!(hint(&[]), None);
+        assert_eq!(
+            hint(&[("cache-control", "public, max-age=3600")]),
+            secs(3600)
+        );
+        assert_eq!(
+            hint(&[("cache-control", "max-age=\"60\""), ("age", "20")]),
+            secs(40)
+        );
+        assert_eq!(
+            hint(&[("cache-control", "max-age=60"), ("age", "90")]),
+            secs(0)
+        );
+        assert_eq!(
+            hint(&[
+                ("cache-control", "max-age=60"),
+                ("cache-control", "no-cache")
+            ]),
+            secs(0)
+        );
+        assert_eq!(
+            hint(&[
+                ("date", "Sun, 06 Nov 1994 08:49:37 GMT"),
+                ("expires", "Sun, 06 Nov 1994 09:49:37 GMT"),
+            ]),
+            secs(3600)
+        );
+        assert_eq!(hint(&[("expires", "0")]), secs(0));
+    }
+}
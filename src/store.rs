@@ -0,0 +1,216 @@
+//! Persistent storage behind a [`ResponseCache`](crate::ResponseCache).
+
+use std::{
+    fmt::Write as _,
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use log::warn;
+
+use crate::CachedResponse;
+
+/// Counter making the names of temporary files unique within the process.
+static TMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Storage for cached responses, added to a [`ResponseCache`](crate::ResponseCache) with
+/// [`ResponseCache::with_store`](crate::ResponseCache::with_store) so that responses outlive the process.
+///
+/// The cache writes every response it caches through to the store, and looks up responses it does not hold in memory
+/// in the store. Stores do not need to evict or expire entries themselves, as the cache removes expired entries it
+/// finds. Implement this trait to keep responses in a database of your choice, e.g. Redis.
+///
+/// Errors from the store are logged and otherwise treated like cache misses, so an unavailable store never fails a
+/// request.
+pub trait CacheStore: Send + Sync {
+    /// Returns the stored entry for `number`, if any.
+    fn get(&self, number: &str) -> io::Result<Option<CachedResponse>>;
+
+    /// Stores `entry`, replacing any entry for the same number.
+    fn put(&self, entry: &CachedResponse) -> io::Result<()>;
+
+    /// Removes the entry for `number`, if any.
+    fn remove(&self, number: &str) -> io::Result<()>;
+
+    /// Returns all stored entries, in no particular order.
+    fn entries(&self) -> io::Result<Vec<CachedResponse>>;
+}
+
+impl<S: CacheStore + ?Sized> CacheStore for std::sync::Arc<S> {
+    fn get(&self, number: &str) -> io::Result<Option<CachedResponse>> {
+        (**self).get(number)
+    }
+
+    fn put(&self, entry: &CachedResponse) -> io::Result<()> {
+        (**self).put(entry)
+    }
+
+    fn remove(&self, number: &str) -> io::Result<()> {
+        (**self).remove(number)
+    }
+
+    fn entries(&self) -> io::Result<Vec<CachedResponse>> {
+        (**self).entries()
+    }
+}
+
+/// A [`CacheStore`] keeping each response as a JSON file in a directory.
+///
+/// Each response is written to a temporary file of its own, then renamed over the previous file, so several processes
+/// can share a directory without ever reading a partly written response. Files which cannot be read are skipped by
+/// [`CacheStore::entries`].
+///
+/// # Examples
+///
+/// ```no_run
+/// use iseven_api::{store::DirectoryStore, ResponseCache};
+///
+/// let store = DirectoryStore::new("/var/cache/iseven");
+/// let cache = ResponseCache::new(1000).with_store(store);
+/// ```
+#[derive(Debug, Clone)]
+pub struct DirectoryStore {
+    dir: PathBuf,
+}
+
+impl DirectoryStore {
+    /// Creates a store in `dir`, which is created when the first response is stored.
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Returns the directory the responses are kept in.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Returns the path of the file for `number`. Characters which may not be valid in file names are escaped.
+    fn path(&self, number: &str) -> PathBuf {
+        let mut name = String::with_capacity(number.len() + 5);
+        for b in number.bytes() {
+            if b.is_ascii_alphanumeric() || b == b'-' || b == b'+' {
+                name.push(b as char);
+            } else {
+                let _ = write!(name, "%{:02X}", b);
+            }
+        }
+        name.push_str(".json");
+        self.dir.join(name)
+    }
+
+    /// Returns a path for writing the file at `path` before renaming it, unique to this process and call so that
+    /// concurrent writers never share one. It does not end in `.json`, so it is never read as an entry.
+    fn tmp_path(path: &Path) -> PathBuf {
+        let counter = TMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+        path.with_extension(format!("json.{}.{}.tmp", process::id(), counter))
+    }
+}
+
+impl CacheStore for DirectoryStore {
+    fn get(&self, number: &str) -> io::Result<Option<CachedResponse>> {
+        match File::open(self.path(number)) {
+            Ok(file) => Ok(Some(serde_json::from_reader(BufReader::new(file))?)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn put(&self, entry: &CachedResponse) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let path = self.path(entry.number());
+        let tmp_path = Self::tmp_path(&path);
+        let result = File::create(&tmp_path).and_then(|file| {
+            let mut writer = BufWriter::new(file);
+            serde_json::to_writer(&mut writer, entry)?;
+            writer.flush()?;
+            fs::rename(&tmp_path, path)
+        });
+        if result.is_err() {
+            let _ = fs::remove_file(&tmp_path);
+        }
+        result
+    }
+
+    fn remove(&self, number: &str) -> io::Result<()> {
+        match fs::remove_file(self.path(number)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    fn entries(&self) -> io::Result<Vec<CachedResponse>> {
+        let dir = match fs::read_dir(&self.dir) {
+            Ok(dir) => dir,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let mut entries = Vec::new();
+        for file in dir {
+            let path = match file {
+                Ok(file) => file.path(),
+                Err(e) => {
+                    warn!("Unable to list {}: {}", self.dir.display(), e);
+                    continue;
+                }
+            };
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            let entry = File::open(&path).and_then(|file| {
+                serde_json::from_reader(BufReader::new(file)).map_err(io::Error::from)
+            });
+            match entry {
+                Ok(entry) => entries.push(entry),
+                // removed by another process since the directory was listed
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => warn!("Skipping unreadable cache entry {}: {}", path.display(), e),
+            }
+        }
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::SystemTime;
+
+    use super::*;
+    use crate::sansio;
+
+    #[test]
+    fn test_directory_store() {
+        let dir = std::env::temp_dir().join(format!("iseven_api-store-{}", std::process::id()));
+        let store = DirectoryStore::new(&dir);
+        assert!(store.entries().unwrap().is_empty());
+
+        let response =
+            sansio::parse_response(http::StatusCode::OK, br#"{"ad": "ad", "iseven": true}"#)
+                .unwrap();
+        for number in ["42", "-42", "../42"] {
+            let entry =
+                CachedResponse::new(number.to_string(), response.clone(), SystemTime::now());
+            store.put(&entry).unwrap();
+        }
+        assert_eq!(store.get("../42").unwrap().unwrap().number(), "../42");
+        assert!(store.path("../42").starts_with(&dir));
+        assert_eq!(store.entries().unwrap().len(), 3);
+
+        store.remove("42").unwrap();
+        store.remove("42").unwrap();
+        assert!(store.get("42").unwrap().is_none());
+        assert_eq!(store.entries().unwrap().len(), 2);
+
+        // a corrupt file is skipped, and temporary files are not entries
+        fs::write(store.path("7"), "{").unwrap();
+        fs::write(DirectoryStore::tmp_path(&store.path("8")), "{}").unwrap();
+        assert_eq!(store.entries().unwrap().len(), 2);
+        assert_ne!(
+            DirectoryStore::tmp_path(&store.path("8")),
+            DirectoryStore::tmp_path(&store.path("8"))
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
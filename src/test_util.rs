@@ -0,0 +1,237 @@
+//! Utilities for testing code which uses the API, enabled with the `test-util` feature.
+
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime},
+};
+
+use crate::{ApiNumber, Clock, IsEvenApiResponse, ResponseMetadata, Rng, Sleep};
+
+/// Products advertised in generated ads.
+const PRODUCTS: &[&str] = &[
+    "isEven API Premium",
+    "isEven API Enterprise",
+    "isEvenCoin",
+    "isOdd API",
+    "ParityPal",
+    "EvenFlow Pro",
+    "ModuloMax",
+    "Bit Zero Cloud",
+    "NumberNanny",
+    "OddBlock",
+];
+
+/// Ads with a `{}` placeholder for the product.
+const TEMPLATES: &[&str] = &[
+    "Buy {}, the hottest new cryptocurrency!",
+    "Did you know? {} is 100% hand-crafted in artisanal fashion.",
+    "Tired of odd numbers? Upgrade to {}!",
+    "{}: now with negative numbers.",
+    "Never count on your fingers again with {}.",
+    "{}, trusted by over 3 developers worldwide.",
+    "Parity as a service? There's {} for that.",
+    "Limited offer: {} for just $0.99 per bit.",
+    "{}: because modulo is hard.",
+    "Is your number even? {} knows.",
+    "Your numbers deserve {}. Sign up today!",
+    "Free trial of {} for the first 2 even numbers.",
+];
+
+/// Generates realistic fake ads, like the ones isEven API shows, from a bundled corpus.
+///
+/// The ad for a number depends only on the number and the seed, so generated ads are the same on every run and
+/// platform, and in whatever order numbers are checked. This makes them suitable for snapshot tests of code which
+/// displays responses. The mock server uses a generator for its ads.
+///
+/// # Examples
+///
+/// ```
+/// use iseven_api::{test_util::AdGenerator, ApiNumber};
+///
+/// let ads = AdGenerator::new(7);
+/// assert_eq!(ads.ad("42"), AdGenerator::new(7).ad("42"));
+///
+/// let response = ads.response(ApiNumber::from(42));
+/// assert!(response.iseven());
+/// assert_eq!(response.ad(), ads.ad("42"));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct AdGenerator {
+    seed: u64,
+}
+
+impl AdGenerator {
+    /// Creates a generator whose ads are determined by `seed`.
+    pub fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+
+    /// Creates a generator with a seed drawn from `rng`, e.g. a [`SeededRng`](crate::SeededRng) shared with the client
+    /// under test.
+    pub fn from_rng(rng: &dyn Rng) -> Self {
+        Self::new(rng.next_u64())
+    }
+
+    /// Returns the seed of the generator.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Returns the ad shown with the answer for `number`.
+    pub fn ad(&self, number: &str) -> String {
+        let hash = self.hash(number);
+        let template = TEMPLATES[(hash % TEMPLATES.len() as u64) as usize];
+        let product = PRODUCTS[((hash >> 32) % PRODUCTS.len() as u64) as usize];
+        template.replace("{}", product)
+    }
+
+    /// Returns the response the API would give for `number`, with a generated ad.
+    pub fn response(&self, number: ApiNumber) -> IsEvenApiResponse {
+        IsEvenApiResponse {
+            ad: self.ad(number.as_str()),
+            iseven: number.as_str().ends_with(['0', '2', '4', '6', '8']),
+            number: Some(number),
+            metadata: ResponseMetadata::default(),
+        }
+    }
+
+    /// Hashes `number` with the seed, using FNV-1a followed by the SplitMix64 finalizer, which unlike the standard
+    /// library's hashers is guaranteed not to change between releases.
+    fn hash(&self, number: &str) -> u64 {
+        let mut hash = 0xcbf2_9ce4_8422_2325 ^ self.seed;
+        for b in number.bytes() {
+            hash = (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3);
+        }
+        hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        hash ^ (hash >> 31)
+    }
+}
+
+/// A [`Clock`] whose time only passes when it is advanced, for testing time-dependent behavior without waiting.
+///
+/// Sleeping on the clock advances it by the time slept and returns immediately, so that e.g. retries with long delays
+/// run instantly, while the clock still tells how long they would have waited. Clones share the same time.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use iseven_api::{test_util::MockClock, EvictionPolicy, IsEvenApiResponse, ResponseCache};
+///
+/// let clock = MockClock::new();
+/// let cache = ResponseCache::new(10)
+///     .with_ttl(Duration::from_secs(60))
+///     .with_clock(clock.clone());
+/// # let response: IsEvenApiResponse = serde_json::from_str(r#"{"ad": "", "iseven": true}"#).unwrap();
+/// cache.insert("42".to_string(), response);
+/// assert!(cache.get("42").is_some());
+///
+/// clock.advance(Duration::from_secs(60));
+/// assert!(cache.get("42").is_none());
+/// ```
+#[derive(Debug, Clone)]
+pub struct MockClock {
+    start: Instant,
+    system_start: SystemTime,
+    elapsed: Arc<Mutex<Duration>>,
+}
+
+impl MockClock {
+    /// Creates a clock starting at the current time.
+    pub fn new() -> Self {
+        Self::at(SystemTime::now())
+    }
+
+    /// Creates a clock whose wall-clock time starts at `time`.
+    pub fn at(time: SystemTime) -> Self {
+        Self {
+            start: Instant::now(),
+            system_start: time,
+            elapsed: Arc::new(Mutex::new(Duration::ZERO)),
+        }
+    }
+
+    /// Moves the clock forward by `duration`.
+    pub fn advance(&self, duration: Duration) {
+        *self.elapsed.lock().unwrap_or_else(|e| e.into_inner()) += duration;
+    }
+
+    /// Returns how far the clock has moved since it was created.
+    pub fn elapsed(&self) -> Duration {
+        *self.elapsed.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Default for MockClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        self.start + self.elapsed()
+    }
+
+    fn system_time(&self) -> SystemTime {
+        self.system_start + self.elapsed()
+    }
+
+    fn sleep(&self, duration: Duration) -> Sleep {
+        self.advance(duration);
+        Box::pin(std::future::ready(()))
+    }
+
+    fn sleep_blocking(&self, duration: Duration) {
+        self.advance(duration);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn test_ads_deterministic() {
+        let ads = AdGenerator::default();
+        // pinned, so that changes to the generator which would break users' snapshots are noticed
+        assert_eq!(
+            ads.ad("42"),
+            "Parity as a service? There's ParityPal for that."
+        );
+        assert_eq!(
+            ads.ad("7"),
+            "Limited offer: isOdd API for just $0.99 per bit."
+        );
+        let first: Vec<_> = (0..100).map(|n| ads.ad(&n.to_string())).collect();
+        let again: Vec<_> = (0..100).rev().map(|n| ads.ad(&n.to_string())).collect();
+        assert!(first.iter().eq(again.iter().rev()));
+        assert!(first.iter().all(|ad| !ad.contains("{}")));
+        assert!(first.iter().collect::<HashSet<_>>().len() > 20);
+
+        let reseeded: Vec<_> = (0..100)
+            .map(|n| AdGenerator::new(1).ad(&n.to_string()))
+            .collect();
+        assert_ne!(first, reseeded);
+
+        let rng = crate::SeededRng::new(5);
+        assert_eq!(
+            AdGenerator::from_rng(&rng),
+            AdGenerator::new(crate::SeededRng::new(5).next_u64())
+        );
+    }
+
+    #[test]
+    fn test_response() {
+        let ads = AdGenerator::new(3);
+        assert!(ads.response(ApiNumber::from(-10)).iseven());
+        assert!(ads.response(ApiNumber::from(7)).isodd());
+        assert_eq!(
+            ads.response(ApiNumber::from(7)).number(),
+            Some(&ApiNumber::from(7))
+        );
+    }
+}
@@ -0,0 +1,114 @@
+//! Client for browsers and other JavaScript environments, enabled with the `wasm` feature.
+//!
+//! [`WasmIsEvenClient`] makes requests with the global `fetch` function, so it works in browsers, web workers and
+//! Node.js 18 or later. It is exported to JavaScript with [`wasm_bindgen`]; see `examples/wasm` for a complete
+//! example.
+//!
+//! ```js
+//! import init, { WasmIsEvenClient } from './pkg/iseven_wasm_example.js';
+//!
+//! await init();
+//! const client = new WasmIsEvenClient();
+//! const response = await client.isEven('42');
+//! console.log(response.iseven, response.ad);
+//! ```
+
+use js_sys::{Error, Promise};
+use wasm_bindgen::{prelude::wasm_bindgen, JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{Request, RequestInit, Response};
+
+use crate::{
+    builder::ClientConfig, sansio, IsEvenApiClientBuilder, IsEvenApiResponse, StatusCode, Url,
+};
+
+#[wasm_bindgen]
+extern "C" {
+    /// The global `fetch` function, present in windows, workers and Node.js alike.
+    #[wasm_bindgen(js_name = fetch)]
+    fn fetch_with_request(request: &Request) -> Promise;
+}
+
+/// Client for isEven API using the `fetch` API of the JavaScript environment.
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct WasmIsEvenClient {
+    config: ClientConfig,
+}
+
+#[wasm_bindgen]
+impl WasmIsEvenClient {
+    /// Creates a client for the API at `base_url`, or the public API if it is not given, authenticating with
+    /// `api_key` if it is given.
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        base_url: Option<String>,
+        api_key: Option<String>,
+    ) -> Result<WasmIsEvenClient, Error> {
+        let mut builder = IsEvenApiClientBuilder::new();
+        if let Some(base_url) = base_url {
+            let url = Url::parse(&base_url)
+                .map_err(|e| Error::new(&format!("invalid base URL: {}", e)))?;
+            builder = builder.base_url(url);
+        }
+        if let Some(api_key) = api_key {
+            builder = builder.api_key(api_key);
+        }
+        Ok(Self {
+            config: builder.into_config(),
+        })
+    }
+
+    /// Checks whether `number` is even. Rejects with an `Error` if the request fails or the API answers with an
+    /// error.
+    #[wasm_bindgen(js_name = isEven)]
+    pub async fn is_even(&self, number: String) -> Result<WasmIsEvenResponse, JsValue> {
+        let init = RequestInit::new();
+        init.set_method("GET");
+        let request = Request::new_with_str_and_init(&self.config.request_url(&number), &init)?;
+        request.headers().set("Accept", "application/json")?;
+        if let Some(api_key) = self.config.api_key() {
+            request
+                .headers()
+                .set("Authorization", &format!("Bearer {}", api_key))?;
+        }
+        let response: Response = JsFuture::from(fetch_with_request(&request))
+            .await?
+            .dyn_into()?;
+        let text = JsFuture::from(response.text()?)
+            .await?
+            .as_string()
+            .unwrap_or_default();
+        let status = StatusCode::from_u16(response.status())
+            .map_err(|e| Error::new(&format!("invalid status code: {}", e)))?;
+        sansio::parse_response(status, text.as_bytes())
+            .map(|response| WasmIsEvenResponse(response.for_number(&number)))
+            .map_err(|e| Error::new(&e.to_string()).into())
+    }
+}
+
+/// Answer from isEven API, as returned to JavaScript.
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct WasmIsEvenResponse(IsEvenApiResponse);
+
+#[wasm_bindgen]
+impl WasmIsEvenResponse {
+    /// Whether the number is even.
+    #[wasm_bindgen(getter)]
+    pub fn iseven(&self) -> bool {
+        self.0.iseven()
+    }
+
+    /// Whether the number is odd.
+    #[wasm_bindgen(getter)]
+    pub fn isodd(&self) -> bool {
+        self.0.isodd()
+    }
+
+    /// Advertisement sent along with the answer.
+    #[wasm_bindgen(getter)]
+    pub fn ad(&self) -> String {
+        self.0.ad().to_string()
+    }
+}
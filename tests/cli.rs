@@ -0,0 +1,139 @@
+//! Golden-file tests of the command line app's output formats, run against the bundled mock server.
+//!
+//! Each case runs the app and compares its stdout, stderr and exit status with a file in `tests/golden`. After an
+//! intended change in output, run the tests with `UPDATE_GOLDEN=1` to rewrite the files, and review the diff.
+
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use iseven_api::{mock::MockServer, Plan};
+
+const BIN: &str = env!("CARGO_BIN_EXE_iseven_api");
+
+/// Numbers checked by most cases: an even number, an odd number and a number out of range for the free plan.
+const NUMBERS: [&str; 3] = ["42", "7", "1000000"];
+
+const CSV: &str = "name,value\nanswer,42\nlucky,7\nbig,1000000\n";
+
+/// Runs the app with `args` and `stdin` against the API at `base_url`, returning its output in golden file form.
+fn run(base_url: &str, args: &[&str], stdin: &str) -> String {
+    let mut child = Command::new(BIN)
+        .args(["--base-url", base_url, "--color", "never", "--keep-going"])
+        .args(args)
+        .env_clear()
+        .env("PATH", std::env::var_os("PATH").unwrap_or_default())
+        .env("LANG", "C")
+        .env("ISEVEN_API_CONFIG", "/nonexistent/config.toml")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to run iseven_api");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    // error messages are prefixed with the path of the binary, which depends on the build directory
+    let stderr = String::from_utf8_lossy(&output.stderr).replace(BIN, "iseven_api");
+    format!(
+        "$ iseven_api {}\n--- stdout\n{}--- stderr\n{}--- status: {}\n",
+        args.iter()
+            .map(|arg| if arg.contains(' ') {
+                format!("'{}'", arg)
+            } else {
+                arg.to_string()
+            })
+            .collect::<Vec<_>>()
+            .join(" "),
+        String::from_utf8_lossy(&output.stdout),
+        stderr,
+        output.status.code().unwrap_or(-1)
+    )
+}
+
+/// Compares `actual` with the golden file `name`, or rewrites it if `UPDATE_GOLDEN` is set.
+fn check(name: &str, actual: &str) -> Result<(), String> {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "golden", name]
+        .iter()
+        .collect();
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::write(&path, actual).unwrap();
+        return Ok(());
+    }
+    let expected = std::fs::read_to_string(&path).map_err(|e| {
+        format!(
+            "{}: {} (run with UPDATE_GOLDEN=1 to create it)",
+            path.display(),
+            e
+        )
+    })?;
+    if expected == actual {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} differs\n--- expected\n{}--- actual\n{}",
+            Path::new(name).display(),
+            expected,
+            actual
+        ))
+    }
+}
+
+#[test]
+fn test_output_formats() {
+    let tokio = tokio::runtime::Runtime::new().unwrap();
+    let server = tokio
+        .block_on(MockServer::start("127.0.0.1:0", Plan::Free))
+        .unwrap();
+    let base_url = server.url().to_string();
+
+    let template = "{number} is {parity}: {ad} [{error}]";
+    let cases: [(&str, Vec<&str>, &str); 10] = [
+        ("human.txt", NUMBERS.to_vec(), ""),
+        // results are printed in the order the numbers were given, however many are checked at a time
+        (
+            "human_jobs.txt",
+            [&["--jobs", "3"], &NUMBERS[..]].concat(),
+            "",
+        ),
+        ("human_no_ad.txt", [&["--no-ad"], &NUMBERS[..]].concat(), ""),
+        ("quiet.txt", [&["--quiet"], &NUMBERS[..]].concat(), ""),
+        (
+            "json.txt",
+            [&["--format", "json"], &NUMBERS[..]].concat(),
+            "",
+        ),
+        (
+            "jsonl.txt",
+            [&["--format", "jsonl"], &NUMBERS[..]].concat(),
+            "",
+        ),
+        (
+            "yaml.txt",
+            [&["--format", "yaml"], &NUMBERS[..]].concat(),
+            "",
+        ),
+        (
+            "toml.txt",
+            [&["--format", "toml"], &NUMBERS[..]].concat(),
+            "",
+        ),
+        (
+            "template.txt",
+            [&["--template", template], &NUMBERS[..]].concat(),
+            "",
+        ),
+        ("csv.txt", vec!["--csv", "-", "--column", "value"], CSV),
+    ];
+    let failures: Vec<_> = cases
+        .iter()
+        .filter_map(|(name, args, stdin)| check(name, &run(&base_url, args, stdin)).err())
+        .collect();
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
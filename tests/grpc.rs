@@ -0,0 +1,106 @@
+//! Calls the gRPC facade of `serve --grpc` over HTTP/2 with the h2 client, against the bundled mock server.
+//!
+//! The service's messages are encoded by hand in `src/cli/grpc.rs`, so this checks the parts a gRPC client relies on
+//! beyond the messages themselves: HTTP/2 without TLS, message framing and the status in the trailers.
+
+use std::{
+    io::{BufRead, BufReader},
+    net::SocketAddr,
+    process::{Child, Command, Stdio},
+};
+
+use bytes::Bytes;
+use iseven_api::{mock::MockServer, Plan};
+use tokio::net::TcpStream;
+
+const BIN: &str = env!("CARGO_BIN_EXE_iseven_api");
+
+/// Runs `serve --grpc` on a free port in front of the API at `base_url`, returning the process and its address.
+fn serve(base_url: &str) -> (Child, SocketAddr) {
+    let mut child = Command::new(BIN)
+        .args(["--base-url", base_url, "serve", "--grpc", "--port", "0"])
+        .env_clear()
+        .env("ISEVEN_API_CONFIG", "/nonexistent/config.toml")
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to run iseven_api");
+    let mut stderr = BufReader::new(child.stderr.take().unwrap());
+    let mut line = String::new();
+    stderr.read_line(&mut line).unwrap();
+    let addr = line
+        .trim()
+        .strip_prefix("Listening on http://")
+        .unwrap_or_else(|| panic!("unexpected output: {}", line))
+        .parse()
+        .unwrap();
+    (child, addr)
+}
+
+/// Calls `CheckParity` for `number`, returning the reply message, if any, and the `grpc-status` trailer.
+async fn check_parity(addr: SocketAddr, number: &str) -> (Option<Vec<u8>>, String) {
+    let stream = TcpStream::connect(addr).await.unwrap();
+    let (client, connection) = h2::client::handshake(stream).await.unwrap();
+    tokio::spawn(connection);
+    let mut client = client.ready().await.unwrap();
+    let request = http::Request::post(format!("http://{}/iseven.v1.Parity/CheckParity", addr))
+        .header("content-type", "application/grpc")
+        .header("te", "trailers")
+        .body(())
+        .unwrap();
+    let (response, mut body) = client.send_request(request, false).unwrap();
+    // CheckParityRequest { number }, prefixed by the compression flag and the length of the message
+    let mut message = vec![0x0a, number.len() as u8];
+    message.extend_from_slice(number.as_bytes());
+    let mut frame = vec![0];
+    frame.extend_from_slice(&(message.len() as u32).to_be_bytes());
+    frame.extend_from_slice(&message);
+    body.send_data(Bytes::from(frame), true).unwrap();
+
+    let response = response.await.unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.headers()["content-type"], "application/grpc");
+    let mut body = response.into_body();
+    let mut data = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.unwrap();
+        body.flow_control().release_capacity(chunk.len()).unwrap();
+        data.extend_from_slice(&chunk);
+    }
+    let trailers = body.trailers().await.unwrap().expect("no trailers");
+    let status = trailers["grpc-status"].to_str().unwrap().to_string();
+    let reply = (!data.is_empty()).then(|| {
+        assert_eq!(data[0], 0, "compressed reply");
+        let len = u32::from_be_bytes(data[1..5].try_into().unwrap()) as usize;
+        assert_eq!(data.len(), 5 + len);
+        data[5..].to_vec()
+    });
+    (reply, status)
+}
+
+#[tokio::test]
+async fn test_check_parity() {
+    let server = MockServer::start("127.0.0.1:0", Plan::Free).await.unwrap();
+    let (mut child, addr) = serve(server.url().as_str());
+
+    let (reply, status) = check_parity(addr, "42").await;
+    assert_eq!(status, "0");
+    // ParityReply { iseven: true, ad }
+    let reply = reply.expect("no reply");
+    assert_eq!(reply[..3], [0x08, 0x01, 0x12]);
+    assert_eq!(reply[3] as usize, reply.len() - 4);
+
+    let (reply, status) = check_parity(addr, "7").await;
+    assert_eq!(status, "0");
+    // iseven is false, so only the ad is encoded
+    assert_eq!(reply.expect("no reply")[0], 0x12);
+
+    // OUT_OF_RANGE and INVALID_ARGUMENT
+    assert_eq!(
+        check_parity(addr, "1000000").await,
+        (None, "11".to_string())
+    );
+    assert_eq!(check_parity(addr, "abc").await, (None, "3".to_string()));
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}